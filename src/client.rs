@@ -136,15 +136,18 @@
 //! [`Error`]: ../struct.Error.html
 
 use crate::codec::{Codec, RecvError, SendError, UserError};
+use crate::ext::{AltSvc, ExtensionFrame, GoAway, Protocol, StreamDependency};
 use crate::frame::{Headers, Pseudo, Reason, Settings, StreamId};
 use crate::proto;
-use crate::{FlowControl, PingPong, RecvStream, SendStream};
+use crate::timer::Time;
+use crate::{FlowControl, Padding, PingPong, RecvStream, RemoteSettings, SendStream, Timer};
 
 use bytes::{Buf, Bytes};
-use http::{uri, HeaderMap, Method, Request, Response, Version};
+use http::{uri, HeaderMap, HeaderValue, Method, Request, Response, Version};
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 use std::usize;
@@ -232,6 +235,9 @@ pub struct Connection<T, B: Buf = Bytes> {
     inner: proto::Connection<T, Peer, B>,
 }
 
+/// The handles returned by `Builder::handshake_upgrade`.
+type UpgradeParts<T, B> = (SendRequest<B>, ResponseFuture, Connection<T, B>);
+
 /// A future of an HTTP response.
 #[derive(Debug)]
 #[must_use = "futures do nothing unless polled"]
@@ -262,6 +268,14 @@ pub struct PushPromise {
     response: PushedResponseFuture,
 }
 
+/// A stream of informational (1xx) responses received before the final
+/// response.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct InformationalResponses {
+    inner: proto::OpaqueStreamRef,
+}
+
 /// A stream of pushed responses and corresponding promised requests
 #[derive(Debug)]
 #[must_use = "streams do nothing unless polled"]
@@ -319,6 +333,22 @@ pub struct Builder {
     /// Initial target window size for new connections.
     initial_target_connection_window_size: Option<u32>,
 
+    /// Maximum window size reached by adaptive flow control, if enabled.
+    adaptive_window: Option<u32>,
+
+    /// Whether received data releases its capacity automatically.
+    auto_release_capacity: bool,
+
+    /// Maximum amount of data buffered on each stream, waiting to be sent.
+    max_send_buffer_size: usize,
+
+    /// Maximum amount of data buffered on the connection beyond the capacity
+    /// of its streams.
+    max_connection_send_buffer_size: usize,
+
+    /// Maximum amount of received data held by the connection, if limited.
+    recv_memory_budget: Option<u32>,
+
     /// Maximum number of locally reset streams to keep at a time.
     reset_stream_max: usize,
 
@@ -328,8 +358,35 @@ pub struct Builder {
     /// The stream ID of the first (lowest) stream. Subsequent streams will use
     /// monotonically increasing stream IDs.
     stream_id: StreamId,
+
+    /// Padding policy for outgoing frames.
+    padding: Padding,
+
+    /// Timer used to wake up connections when a deadline passes.
+    time: Time,
+
+    /// How long to hold back the body of requests sent with
+    /// `Expect: 100-continue`, if at all.
+    expect_continue_timeout: Option<Duration>,
+
+    /// Maximum number of pushed streams to cache and how long to keep them,
+    /// if pushed streams are cached.
+    push_cache: Option<(usize, Duration)>,
+
+    /// Decides which pushed streams are accepted, if set.
+    push_policy: Option<PushPolicy>,
+
+    /// Maximum number of pushed streams not yet claimed by the application.
+    max_pending_pushed_streams: Option<usize>,
 }
 
+/// Decides whether a promised request is accepted, or the reason the pushed
+/// stream is reset with.
+#[derive(Clone)]
+pub(crate) struct PushPolicy(Arc<PushPolicyFn>);
+
+type PushPolicyFn = dyn Fn(&Request<()>) -> Result<(), Reason> + Send + Sync;
+
 #[derive(Debug)]
 pub(crate) struct Peer;
 
@@ -421,6 +478,36 @@ where
     /// The caller should always set the request's version field to 2.0 unless
     /// specifically transmitting an HTTP 1.1 request over 2.0.
     ///
+    /// # Extended CONNECT
+    ///
+    /// If the request extensions contain a [`Protocol`], the request is sent
+    /// as an [extended CONNECT] request with a `:protocol` pseudo-header. The
+    /// request method must be `CONNECT`, the URI must be absolute, and the
+    /// server must have enabled the protocol (see
+    /// [`is_extended_connect_protocol_enabled`]); otherwise an error is
+    /// returned.
+    ///
+    /// [`Protocol`]: ../ext/struct.Protocol.html
+    /// [extended CONNECT]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+    /// [`is_extended_connect_protocol_enabled`]: #method.is_extended_connect_protocol_enabled
+    ///
+    /// # Priority
+    ///
+    /// If the request extensions contain a [`StreamDependency`], it is sent
+    /// with the `HEADERS` frame to tell the server how to prioritize the
+    /// response. It can later be changed with [`ResponseFuture::reprioritize`].
+    ///
+    /// [`StreamDependency`]: ../ext/struct.StreamDependency.html
+    /// [`ResponseFuture::reprioritize`]: struct.ResponseFuture.html#method.reprioritize
+    ///
+    /// # Receive window
+    ///
+    /// If the request extensions contain an [`InitialWindowSize`], it sets
+    /// the window of the response body in place of the `initial_window_size`
+    /// setting of the connection.
+    ///
+    /// [`InitialWindowSize`]: ../ext/struct.InitialWindowSize.html
+    ///
     /// # Examples
     ///
     /// Sending a request with no body
@@ -517,6 +604,125 @@ where
                 (response, stream)
             })
     }
+
+    /// Returns whether the [extended CONNECT protocol][1] is enabled or not.
+    ///
+    /// This setting is configured by the server peer by sending the
+    /// [`SETTINGS_ENABLE_CONNECT_PROTOCOL` parameter][2] in a `SETTINGS` frame.
+    /// This method returns the currently acknowledged value received from the
+    /// remote.
+    ///
+    /// [1]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+    /// [2]: https://datatracker.ietf.org/doc/html/rfc8441#section-3
+    pub fn is_extended_connect_protocol_enabled(&self) -> bool {
+        self.inner.is_extended_connect_protocol_enabled()
+    }
+
+    /// Returns the settings received from the server so far.
+    ///
+    /// See [`poll_remote_settings`] to wait for the initial `SETTINGS`
+    /// exchange to complete.
+    ///
+    /// [`poll_remote_settings`]: #method.poll_remote_settings
+    pub fn remote_settings(&self) -> RemoteSettings {
+        RemoteSettings::new(self.inner.remote_settings())
+    }
+
+    /// Polls for the completion of the initial `SETTINGS` exchange.
+    ///
+    /// This resolves with the server's settings once its first `SETTINGS`
+    /// frame has been received and our own `SETTINGS` frame has been
+    /// acknowledged. The [`Connection`] must be polled for this to make
+    /// progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns the connection error if the connection fails first.
+    ///
+    /// [`Connection`]: struct.Connection.html
+    pub fn poll_remote_settings(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<RemoteSettings, crate::Error>> {
+        self.inner
+            .poll_remote_settings(cx)
+            .map_ok(RemoteSettings::new)
+            .map_err(Into::into)
+    }
+
+    /// Returns the origin set received from the server in `ORIGIN` frames.
+    ///
+    /// The origins are ASCII serialized, e.g. `https://example.com`. As
+    /// described in [RFC 8336], a connection may be reused for requests to
+    /// any origin in the set, provided the server's certificate is valid for
+    /// it.
+    ///
+    /// Returns `None` if no `ORIGIN` frame has been received yet. The set only
+    /// grows as more frames are received.
+    ///
+    /// [RFC 8336]: https://datatracker.ietf.org/doc/html/rfc8336
+    pub fn origin_set(&self) -> Option<Vec<String>> {
+        self.inner.origin_set()
+    }
+
+    /// Polls for the next `ALTSVC` frame received from the server.
+    ///
+    /// Both the frames sent on the connection and the ones sent on a request
+    /// stream are returned, see [`AltSvc::stream_id`]. Frames that RFC 7838
+    /// requires clients to ignore are not returned.
+    ///
+    /// Only the most recent task to call this method is notified when a frame
+    /// arrives, so a single `SendRequest` handle should be used to receive
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// Once the connection has failed and no more frames are buffered, this
+    /// returns the connection error.
+    ///
+    /// [`AltSvc::stream_id`]: ../ext/struct.AltSvc.html#method.stream_id
+    pub fn poll_alt_svc(&mut self, cx: &mut Context) -> Poll<Result<AltSvc, crate::Error>> {
+        self.inner.poll_alt_svc(cx).map_err(Into::into)
+    }
+
+    /// Sends an extension frame on the connection (stream 0).
+    ///
+    /// The frame is queued and written the next time the [`Connection`] is
+    /// polled, ahead of any pending stream frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame type is one that h2 implements, if the
+    /// payload is larger than the maximum frame size advertised by the
+    /// server, or if the connection has already failed.
+    ///
+    /// [`Connection`]: struct.Connection.html
+    pub fn send_extension_frame(&mut self, frame: ExtensionFrame) -> Result<(), crate::Error> {
+        self.inner.send_extension_frame(frame).map_err(Into::into)
+    }
+
+    /// Polls for the next extension frame received on the connection (stream
+    /// 0).
+    ///
+    /// Frames of unknown types that are received on a stream are yielded by
+    /// [`RecvStream::poll_extension_frame`] instead.
+    ///
+    /// Only the most recent task to call this method is notified when a frame
+    /// arrives, so a single `SendRequest` handle should be used to receive
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// Once the connection has failed and no more frames are buffered, this
+    /// returns the connection error.
+    ///
+    /// [`RecvStream::poll_extension_frame`]: ../struct.RecvStream.html#method.poll_extension_frame
+    pub fn poll_extension_frame(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<ExtensionFrame, crate::Error>> {
+        self.inner.poll_extension_frame(cx).map_err(Into::into)
+    }
 }
 
 impl<B> fmt::Debug for SendRequest<B>
@@ -617,9 +823,20 @@ impl Builder {
             reset_stream_duration: Duration::from_secs(proto::DEFAULT_RESET_STREAM_SECS),
             reset_stream_max: proto::DEFAULT_RESET_STREAM_MAX,
             initial_target_connection_window_size: None,
+            adaptive_window: None,
+            auto_release_capacity: false,
+            max_send_buffer_size: usize::MAX,
+            max_connection_send_buffer_size: usize::MAX,
+            recv_memory_budget: None,
             initial_max_send_streams: usize::MAX,
             settings: Default::default(),
             stream_id: 1.into(),
+            padding: Padding::none(),
+            time: Time::default(),
+            expect_continue_timeout: None,
+            push_cache: None,
+            push_policy: None,
+            max_pending_pushed_streams: None,
         }
     }
 
@@ -655,6 +872,7 @@ impl Builder {
     /// ```
     pub fn initial_window_size(&mut self, size: u32) -> &mut Self {
         self.settings.set_initial_window_size(Some(size));
+        self.cap_initial_window_size();
         self
     }
 
@@ -693,6 +911,242 @@ impl Builder {
         self
     }
 
+    /// Enables adaptive flow control for received data.
+    ///
+    /// The connection estimates the bandwidth-delay product of the link, by
+    /// sending `PING` frames while `DATA` frames are received and measuring
+    /// how much data arrives over a round trip. As the estimate grows, the
+    /// connection window and the windows of the streams are grown to match,
+    /// up to `max_window_size`. Windows are never shrunk, and windows set
+    /// larger than the estimate with [`initial_window_size`] or
+    /// [`initial_connection_window_size`] are kept.
+    ///
+    /// This lets transfers over links with a high latency use the available
+    /// bandwidth, without configuring large windows for every peer.
+    ///
+    /// By default, adaptive flow control is disabled.
+    ///
+    /// [`initial_window_size`]: #method.initial_window_size
+    /// [`initial_connection_window_size`]: #method.initial_connection_window_size
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .adaptive_window(16 * 1024 * 1024)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn adaptive_window(&mut self, max_window_size: u32) -> &mut Self {
+        self.adaptive_window = Some(std::cmp::min(max_window_size, proto::MAX_WINDOW_SIZE));
+        self
+    }
+
+    /// Sets whether received data releases its capacity automatically.
+    ///
+    /// By default, the application has to release the capacity of every
+    /// chunk of data it takes from a [`RecvStream`] by calling
+    /// [`FlowControl::release_capacity`]. If it forgets to, the peer runs out
+    /// of window and the stream stalls.
+    ///
+    /// When enabled, capacity is released as soon as data is handed to the
+    /// application, and `WINDOW_UPDATE` frames are sent once enough of it
+    /// has been released. This gives up backpressure: the peer may keep
+    /// sending as long as the application keeps reading. The setting can be
+    /// changed for a single stream with [`FlowControl::set_auto_release`].
+    ///
+    /// Data whose capacity has already been released automatically must not
+    /// be released again: while enabled, [`FlowControl::release_capacity`]
+    /// returns an error unless the data was taken from the stream before
+    /// automatic release was enabled for it.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`RecvStream`]: ../struct.RecvStream.html
+    /// [`FlowControl::release_capacity`]: ../struct.FlowControl.html#method.release_capacity
+    /// [`FlowControl::set_auto_release`]: ../struct.FlowControl.html#method.set_auto_release
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .auto_release_capacity(true)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn auto_release_capacity(&mut self, enabled: bool) -> &mut Self {
+        self.auto_release_capacity = enabled;
+        self
+    }
+
+    /// Sets the maximum amount of data buffered on each stream, waiting to
+    /// be sent.
+    ///
+    /// Data passed to [`SendStream::send_data`] is held by the connection
+    /// until the peer's flow control windows allow it to be sent. Without a
+    /// limit, a slow peer can make that buffer grow without bound. Once
+    /// `max` bytes are buffered on a stream, `send_data` returns an error
+    /// instead of buffering more. A single chunk larger than `max` is still
+    /// accepted when nothing is buffered on the stream, so it can be sent at
+    /// all.
+    ///
+    /// The capacity reported by [`SendStream::capacity`] and
+    /// [`SendStream::poll_capacity`] accounts for this limit, so data sent
+    /// within the capacity is never refused. To also bound the data buffered
+    /// by the whole connection, use [`max_connection_send_buffer_size`].
+    ///
+    /// The default value is unlimited.
+    ///
+    /// [`SendStream::send_data`]: ../struct.SendStream.html#method.send_data
+    /// [`SendStream::capacity`]: ../struct.SendStream.html#method.capacity
+    /// [`SendStream::poll_capacity`]: ../struct.SendStream.html#method.poll_capacity
+    /// [`max_connection_send_buffer_size`]: #method.max_connection_send_buffer_size
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .max_send_buffer_size(1024 * 1024)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_send_buffer_size(&mut self, max: usize) -> &mut Self {
+        self.max_send_buffer_size = max;
+        self
+    }
+
+    /// Sets the maximum amount of data buffered on the whole connection,
+    /// waiting to be sent.
+    ///
+    /// Only data sent beyond the capacity of its stream counts against this
+    /// limit, as data within the capacity is bounded by the peer's flow
+    /// control windows. Once `max` bytes are buffered on the connection,
+    /// [`SendStream::send_data`] returns an error for data exceeding the
+    /// capacity of its stream. A single chunk larger than `max` is still
+    /// accepted when nothing is buffered on the connection.
+    ///
+    /// The default value is unlimited.
+    ///
+    /// [`SendStream::send_data`]: ../struct.SendStream.html#method.send_data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .max_connection_send_buffer_size(16 * 1024 * 1024)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_connection_send_buffer_size(&mut self, max: usize) -> &mut Self {
+        self.max_connection_send_buffer_size = max;
+        self
+    }
+
+    /// Sets the maximum amount of received data the connection may hold.
+    ///
+    /// Each stream has its own flow control window, so with many streams
+    /// open, the data the peer is allowed to send can add up to far more
+    /// than the memory available for one connection. With a budget set, the
+    /// connection window never grows past `budget`, which bounds the data
+    /// received but not released yet across all streams. Within it, every
+    /// open stream is given an equal share: a stream's window is only grown
+    /// while its window and the data it has not released stay within
+    /// `budget` divided by the number of open streams.
+    ///
+    /// The [`initial_window_size`] sent to the peer is capped to `budget`
+    /// divided by [`max_concurrent_streams`], or to `budget` if the number of
+    /// streams is not limited, so that new streams start within their share.
+    /// It is never capped below one byte.
+    /// The connection window always starts at 65,535 bytes, so a lower
+    /// budget only takes effect once that window has been used.
+    ///
+    /// The default value is unlimited.
+    ///
+    /// [`initial_window_size`]: #method.initial_window_size
+    /// [`max_concurrent_streams`]: #method.max_concurrent_streams
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .recv_memory_budget(16 * 1024 * 1024)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn recv_memory_budget(&mut self, budget: u32) -> &mut Self {
+        self.recv_memory_budget = Some(std::cmp::min(budget, proto::MAX_WINDOW_SIZE));
+        self.cap_initial_window_size();
+        self
+    }
+
+    /// Keeps the initial window size sent to the peer within the share of
+    /// the receive memory budget of each stream.
+    fn cap_initial_window_size(&mut self) {
+        if let Some(budget) = self.recv_memory_budget {
+            self.settings.cap_initial_window_size(budget);
+        }
+    }
+
     /// Indicates the size (in octets) of the largest HTTP/2.0 frame payload that the
     /// configured client is able to accept.
     ///
@@ -813,6 +1267,7 @@ impl Builder {
     /// ```
     pub fn max_concurrent_streams(&mut self, max: u32) -> &mut Self {
         self.settings.set_max_concurrent_streams(Some(max));
+        self.cap_initial_window_size();
         self
     }
 
@@ -986,63 +1441,375 @@ impl Builder {
         self
     }
 
-    /// Sets the first stream ID to something other than 1.
-    #[cfg(feature = "unstable")]
-    pub fn initial_stream_id(&mut self, stream_id: u32) -> &mut Self {
-        self.stream_id = stream_id.into();
-        assert!(
-            self.stream_id.is_client_initiated(),
-            "stream id must be odd"
-        );
-        self
-    }
-
-    /// Creates a new configured HTTP/2.0 client backed by `io`.
-    ///
-    /// It is expected that `io` already be in an appropriate state to commence
-    /// the [HTTP/2.0 handshake]. The handshake is completed once both the connection
-    /// preface and the initial settings frame is sent by the client.
-    ///
-    /// The handshake future does not wait for the initial settings frame from the
-    /// server.
+    /// Adds a setting that h2 doesn't implement to the initial SETTINGS
+    /// frame.
     ///
-    /// Returns a future which resolves to the [`Connection`] / [`SendRequest`]
-    /// tuple once the HTTP/2.0 handshake has been completed.
+    /// Extensions to HTTP/2.0 may define new settings, which peers that don't
+    /// know them ignore. This allows advertising such a setting, while the
+    /// extension itself is implemented by the application. Setting the same
+    /// `id` twice keeps the last value.
     ///
-    /// This function also allows the caller to configure the send payload data
-    /// type. See [Outbound data type] for more details.
+    /// # Panics
     ///
-    /// [HTTP/2.0 handshake]: http://httpwg.org/specs/rfc7540.html#ConnectionHeader
-    /// [`Connection`]: struct.Connection.html
-    /// [`SendRequest`]: struct.SendRequest.html
-    /// [Outbound data type]: ../index.html#outbound-data-type.
+    /// This function panics if `id` identifies a setting that h2 implements,
+    /// in which case the dedicated builder method must be used instead, or if
+    /// 64 custom settings have already been added.
     ///
     /// # Examples
     ///
-    /// Basic usage:
-    ///
     /// ```
     /// # use tokio::io::{AsyncRead, AsyncWrite};
     /// # use h2::client::*;
     /// # use bytes::Bytes;
     /// #
     /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
-    ///     -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
     /// # {
     /// // `client_fut` is a future representing the completion of the HTTP/2.0
     /// // handshake.
     /// let client_fut = Builder::new()
+    ///     .custom_setting(0xf000, 1)
     ///     .handshake(my_io);
     /// # client_fut.await
     /// # }
     /// #
     /// # pub fn main() {}
     /// ```
+    pub fn custom_setting(&mut self, id: u16, value: u32) -> &mut Self {
+        self.settings.set_unknown_setting(id, value);
+        self
+    }
+
+    /// Sets the padding policy for outgoing `DATA`, `HEADERS` and
+    /// `PUSH_PROMISE` frames.
     ///
-    /// Configures the send-payload data type. In this case, the outbound data
-    /// type will be `&'static [u8]`.
+    /// Padding hides the size of messages from traffic analysis, at the cost
+    /// of bandwidth. The padding of `DATA` frames is counted against flow
+    /// control windows, as any other payload.
     ///
-    /// ```
+    /// By default, frames are not padded. See [`Padding`] for the available
+    /// policies.
+    ///
+    /// [`Padding`]: ../struct.Padding.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use h2::Padding;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .padding(Padding::block(64))
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn padding(&mut self, padding: Padding) -> &mut Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the timer used to wake up connections when a deadline passes.
+    ///
+    /// Deadlines, such as the [`expect_continue_timeout`], are checked
+    /// whenever the connection is polled. Without a timer, a deadline that
+    /// passes while the connection is idle is only noticed once it is polled
+    /// again.
+    ///
+    /// By default, no timer is set. See [`Timer`] for an example using the
+    /// Tokio timer.
+    ///
+    /// [`expect_continue_timeout`]: #method.expect_continue_timeout
+    /// [`Timer`]: ../trait.Timer.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use h2::{Sleep, Timer};
+    /// # use std::time::Instant;
+    /// # use bytes::Bytes;
+    /// #
+    /// # struct MyTimer;
+    /// # impl Timer for MyTimer {
+    /// #     fn sleep_until(&self, deadline: Instant) -> Sleep {
+    /// #         Box::pin(tokio::time::sleep_until(deadline.into()))
+    /// #     }
+    /// # }
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .timer(MyTimer)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn timer<T: Timer + 'static>(&mut self, timer: T) -> &mut Self {
+        self.time = Time::new(timer);
+        self
+    }
+
+    /// Holds back the body of requests sent with an `Expect: 100-continue`
+    /// header until the server responds, or until `timeout` has elapsed.
+    ///
+    /// The body of such a request is released once a `100 Continue` or a
+    /// final response is received. Until then, the capacity of the request's
+    /// [`SendStream`] is reported as zero, so a large upload doesn't start
+    /// before the server had a chance to reject it. Data that is sent anyway
+    /// is buffered.
+    ///
+    /// The timeout is checked whenever the [`Connection`] is polled. To
+    /// release the body on time even if the connection is idle, set a
+    /// [`timer`].
+    ///
+    /// By default, request bodies are never held back.
+    ///
+    /// [`SendStream`]: ../struct.SendStream.html
+    /// [`Connection`]: struct.Connection.html
+    /// [`timer`]: #method.timer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use std::time::Duration;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .expect_continue_timeout(Duration::from_secs(1))
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn expect_continue_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.expect_continue_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables caching of pushed streams.
+    ///
+    /// When the cache is enabled, the streams pushed by the server are not
+    /// handed out by [`ResponseFuture::push_promises`]. Instead, each one is
+    /// kept until a later call to [`SendRequest::send_request`] asks for the
+    /// same resource. That request is then answered by the pushed stream and
+    /// no new stream is opened.
+    ///
+    /// A request matches a promise if it has no body and the same method,
+    /// authority and path. Once the pushed response has been received, the request
+    /// headers listed in its `Vary` header must match as well. A response
+    /// with `Vary: *` is never reused. The `ResponseFuture` returned for a
+    /// matched request has the ID of the pushed stream.
+    ///
+    /// At most `max_entries` pushed streams are kept at a time. When the
+    /// cache is full, the oldest one is evicted. Pushed streams that have not
+    /// been claimed after `max_age` are evicted as well. Evicted streams that
+    /// are still open are reset with `CANCEL`. Expiry is checked when the
+    /// connection is polled, or when the [`timer`] fires if one is set.
+    ///
+    /// By default, pushed streams are not cached.
+    ///
+    /// [`ResponseFuture::push_promises`]: struct.ResponseFuture.html#method.push_promises
+    /// [`SendRequest::send_request`]: struct.SendRequest.html#method.send_request
+    /// [`timer`]: #method.timer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use std::time::Duration;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .push_cache(16, Duration::from_secs(30))
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn push_cache(&mut self, max_entries: usize, max_age: Duration) -> &mut Self {
+        self.push_cache = Some((max_entries, max_age));
+        self
+    }
+
+    /// Sets a policy deciding which pushed streams are accepted.
+    ///
+    /// `f` is called with the request promised by each received
+    /// `PUSH_PROMISE` frame. Returning `Ok(())` accepts the pushed stream.
+    /// Returning an error resets the pushed stream with the given reason,
+    /// before any of its data is received, and the application never sees
+    /// it.
+    ///
+    /// `f` is called while the state of the connection is locked. It must
+    /// not call back into h2, for example by sending a request or polling a
+    /// stream, as that would deadlock.
+    ///
+    /// By default, every pushed stream is accepted. Use [`enable_push`] to
+    /// refuse all of them up front.
+    ///
+    /// [`enable_push`]: #method.enable_push
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use h2::Reason;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     // Only accept pushed stylesheets.
+    ///     .push_policy(|request| {
+    ///         if request.uri().path().ends_with(".css") {
+    ///             Ok(())
+    ///         } else {
+    ///             Err(Reason::REFUSED_STREAM)
+    ///         }
+    ///     })
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn push_policy<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&Request<()>) -> Result<(), Reason> + Send + Sync + 'static,
+    {
+        self.push_policy = Some(PushPolicy(Arc::new(f)));
+        self
+    }
+
+    /// Sets the maximum number of pushed streams that have not been claimed
+    /// by the application yet.
+    ///
+    /// A pushed stream is claimed once it is yielded by
+    /// [`ResponseFuture::push_promises`], or taken from the [push cache] by a
+    /// matching request. Pushed streams received while the limit is reached
+    /// are reset with `REFUSED_STREAM`.
+    ///
+    /// By default, there is no limit.
+    ///
+    /// [`ResponseFuture::push_promises`]: struct.ResponseFuture.html#method.push_promises
+    /// [push cache]: #method.push_cache
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .max_pending_pushed_streams(4)
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_pending_pushed_streams(&mut self, max: usize) -> &mut Self {
+        self.max_pending_pushed_streams = Some(max);
+        self
+    }
+
+    /// Sets the first stream ID to something other than 1.
+    #[cfg(feature = "unstable")]
+    pub fn initial_stream_id(&mut self, stream_id: u32) -> &mut Self {
+        self.stream_id = stream_id.into();
+        assert!(
+            self.stream_id.is_client_initiated(),
+            "stream id must be odd"
+        );
+        self
+    }
+
+    /// Creates a new configured HTTP/2.0 client backed by `io`.
+    ///
+    /// It is expected that `io` already be in an appropriate state to commence
+    /// the [HTTP/2.0 handshake]. The handshake is completed once both the connection
+    /// preface and the initial settings frame is sent by the client.
+    ///
+    /// The handshake future does not wait for the initial settings frame from the
+    /// server.
+    ///
+    /// Returns a future which resolves to the [`Connection`] / [`SendRequest`]
+    /// tuple once the HTTP/2.0 handshake has been completed.
+    ///
+    /// This function also allows the caller to configure the send payload data
+    /// type. See [Outbound data type] for more details.
+    ///
+    /// [HTTP/2.0 handshake]: http://httpwg.org/specs/rfc7540.html#ConnectionHeader
+    /// [`Connection`]: struct.Connection.html
+    /// [`SendRequest`]: struct.SendRequest.html
+    /// [Outbound data type]: ../index.html#outbound-data-type.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    ///     -> Result<((SendRequest<Bytes>, Connection<T, Bytes>)), h2::Error>
+    /// # {
+    /// // `client_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let client_fut = Builder::new()
+    ///     .handshake(my_io);
+    /// # client_fut.await
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// Configures the send-payload data type. In this case, the outbound data
+    /// type will be `&'static [u8]`.
+    ///
+    /// ```
     /// # use tokio::io::{AsyncRead, AsyncWrite};
     /// # use h2::client::*;
     /// #
@@ -1068,6 +1835,117 @@ impl Builder {
     {
         Connection::handshake2(io, self.clone())
     }
+
+    /// Returns the value of the `HTTP2-Settings` header to send in an
+    /// HTTP/1.1 request to upgrade the connection to HTTP/2.0 with
+    /// `Upgrade: h2c`.
+    ///
+    /// The header holds the initial settings configured on this builder,
+    /// which the server applies as soon as it upgrades the connection.
+    ///
+    /// See [`handshake_upgrade`] for more details.
+    ///
+    /// [`handshake_upgrade`]: #method.handshake_upgrade
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::client::*;
+    /// # use http::Request;
+    /// #
+    /// # fn doc() -> Request<()>
+    /// # {
+    /// let builder = Builder::new();
+    ///
+    /// let request = Request::builder()
+    ///     .uri("http://example.com/")
+    ///     .header("connection", "Upgrade, HTTP2-Settings")
+    ///     .header("upgrade", "h2c")
+    ///     .header("http2-settings", builder.http2_settings())
+    ///     .body(())
+    ///     .unwrap();
+    /// # request
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn http2_settings(&self) -> HeaderValue {
+        proto::upgrade::encode_settings(&self.settings)
+    }
+
+    /// Creates a new configured HTTP/2.0 client backed by `io`, which was
+    /// upgraded from HTTP/1.1 with `Upgrade: h2c`.
+    ///
+    /// It is expected that the upgrade request, carrying the
+    /// [`http2_settings`] header of this builder, was sent on `io` and that
+    /// the `101 Switching Protocols` response was read from it. The handshake
+    /// then proceeds as with [`handshake`].
+    ///
+    /// The upgrade request is stream 1, already half-closed. Along with the
+    /// [`Connection`] / [`SendRequest`] tuple, the returned future resolves to
+    /// the [`ResponseFuture`] of that stream, which completes once the server
+    /// sends the response over HTTP/2.0. New requests sent with
+    /// [`SendRequest`] start at stream 3.
+    ///
+    /// The returned future fails if the upgrade stream can't be opened, i.e.
+    /// when [`initial_max_send_streams`] is 0.
+    ///
+    /// See [RFC 7540, section 3.2] for more details.
+    ///
+    /// [`http2_settings`]: #method.http2_settings
+    /// [`handshake`]: #method.handshake
+    /// [`initial_max_send_streams`]: #method.initial_max_send_streams
+    /// [`Connection`]: struct.Connection.html
+    /// [`SendRequest`]: struct.SendRequest.html
+    /// [`ResponseFuture`]: struct.ResponseFuture.html
+    /// [RFC 7540, section 3.2]: https://httpwg.org/specs/rfc7540.html#discover-http
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::client::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin + Send + 'static>(my_io: T)
+    /// # -> Result<(), h2::Error>
+    /// # {
+    /// let (send_request, response, connection) = Builder::new()
+    ///     .handshake_upgrade::<_, Bytes>(my_io)
+    ///     .await?;
+    ///
+    /// tokio::spawn(async move {
+    ///     connection.await.unwrap();
+    /// });
+    ///
+    /// // The response to the upgrade request
+    /// let response = response.await?;
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn handshake_upgrade<T, B>(
+        &self,
+        io: T,
+    ) -> impl Future<Output = Result<UpgradeParts<T, B>, crate::Error>>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+        B: Buf + 'static,
+    {
+        let handshake = Connection::handshake2(io, self.clone());
+
+        async move {
+            let (mut send_request, connection) = handshake.await?;
+
+            let response = ResponseFuture {
+                inner: send_request.inner.open_upgrade_stream()?,
+                push_promise_consumed: false,
+            };
+
+            Ok((send_request, response, connection))
+        }
+    }
 }
 
 impl Default for Builder {
@@ -1160,6 +2038,8 @@ where
             codec.set_max_recv_header_list_size(max as usize);
         }
 
+        codec.set_padding(builder.padding.clone());
+
         // Send initial settings frame
         codec
             .buffer(builder.settings.clone().into())
@@ -1173,6 +2053,17 @@ where
                 reset_stream_duration: builder.reset_stream_duration,
                 reset_stream_max: builder.reset_stream_max,
                 settings: builder.settings.clone(),
+                padding: builder.padding.clone(),
+                expect_continue_timeout: builder.expect_continue_timeout,
+                push_cache: builder.push_cache,
+                push_policy: builder.push_policy.clone(),
+                max_pending_pushed_streams: builder.max_pending_pushed_streams,
+                adaptive_window: builder.adaptive_window,
+                auto_release_capacity: builder.auto_release_capacity,
+                max_send_buffer_size: builder.max_send_buffer_size,
+                max_connection_send_buffer_size: builder.max_connection_send_buffer_size,
+                recv_memory_budget: builder.recv_memory_budget,
+                time: builder.time.clone(),
             },
         );
         let send_request = SendRequest {
@@ -1265,6 +2156,55 @@ where
     pub fn max_concurrent_recv_streams(&self) -> usize {
         self.inner.max_recv_streams()
     }
+
+    /// Returns the settings received from the server so far, including the
+    /// ones that h2 doesn't implement.
+    pub fn remote_settings(&self) -> RemoteSettings {
+        RemoteSettings::new(self.inner.remote_settings())
+    }
+
+    /// Returns the last `GOAWAY` frame received from the server, if any.
+    ///
+    /// A `GOAWAY` frame with `NO_ERROR` is not reported as an error when the
+    /// connection completes; its last stream ID and debug data can be read
+    /// here instead.
+    pub fn received_go_away(&self) -> Option<GoAway> {
+        self.inner.received_go_away().cloned().map(GoAway::new)
+    }
+
+    /// Polls for the completion of the initial `SETTINGS` exchange.
+    ///
+    /// This resolves with the server's settings once its first `SETTINGS`
+    /// frame has been received and our own `SETTINGS` frame has been
+    /// acknowledged. The connection itself must also be polled for this to
+    /// make progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns the connection error if the connection fails first.
+    pub fn poll_remote_settings(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<RemoteSettings, crate::Error>> {
+        self.inner
+            .poll_remote_settings(cx)
+            .map_ok(RemoteSettings::new)
+            .map_err(Into::into)
+    }
+
+    /// Starts a [graceful shutdown][1] process.
+    ///
+    /// This sends a `GOAWAY` frame telling the server that no pushed streams
+    /// beyond the ones already received will be accepted. Requests that are
+    /// already in flight are allowed to complete, and once all active streams
+    /// have completed, the connection is closed.
+    ///
+    /// The connection must continue being polled to close.
+    ///
+    /// [1]: http://httpwg.org/specs/rfc7540.html#GOAWAY
+    pub fn graceful_shutdown(&mut self) {
+        self.inner.go_away_gracefully();
+    }
 }
 
 impl<T, B> Future for Connection<T, B>
@@ -1313,8 +2253,43 @@ impl ResponseFuture {
     pub fn stream_id(&self) -> crate::StreamId {
         crate::StreamId::from_internal(self.inner.stream_id())
     }
+
+    /// Changes the priority of the request by sending a `PRIORITY` frame.
+    ///
+    /// The frame is sent even if the request or the response is complete,
+    /// which the server may use to keep the stream in the dependency tree.
+    /// If the `HEADERS` frame of the request has not been written yet, the
+    /// dependency is sent in it instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dependency` makes the stream depend on itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::client::ResponseFuture;
+    /// # use h2::ext::StreamDependency;
+    /// # fn doc(mut response: ResponseFuture) {
+    /// // The response is now the most important one.
+    /// response
+    ///     .reprioritize(StreamDependency::new(256).exclusive(true))
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn reprioritize(&mut self, dependency: StreamDependency) -> Result<(), crate::Error> {
+        self.inner
+            .send_priority(dependency.to_frame())
+            .map_err(Into::into)
+    }
+
     /// Returns a stream of PushPromises
     ///
+    /// If the [push cache] is enabled, pushed streams are kept in the cache
+    /// instead and this stream yields nothing.
+    ///
+    /// [push cache]: struct.Builder.html#method.push_cache
+    ///
     /// # Panics
     ///
     /// If this method has been called before
@@ -1328,6 +2303,65 @@ impl ResponseFuture {
             inner: self.inner.clone(),
         }
     }
+
+    /// Returns a stream of the informational (1xx) responses received before
+    /// the final response, such as `103 Early Hints`.
+    ///
+    /// The stream ends once the final response is available. Informational
+    /// responses that haven't been taken from the stream by then are
+    /// discarded when the final response is polled.
+    /// At most 16 informational responses are buffered until they are taken
+    /// from the stream; the ones received beyond that are discarded.
+    ///
+    /// The stream and the `ResponseFuture` may be polled from different
+    /// tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::client::ResponseFuture;
+    /// # async fn doc(mut response: ResponseFuture) -> Result<(), h2::Error> {
+    /// let mut informational = response.informational_responses();
+    /// while let Some(interim) = informational.informational_response().await {
+    ///     println!("informational response: {}", interim.status());
+    /// }
+    ///
+    /// let response = response.await?;
+    /// # Ok(())
+    /// # }
+    /// # pub fn main() {}
+    /// ```
+    pub fn informational_responses(&mut self) -> InformationalResponses {
+        InformationalResponses {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// ===== impl InformationalResponses =====
+
+impl InformationalResponses {
+    /// Get the next informational response.
+    pub async fn informational_response(&mut self) -> Option<Response<()>> {
+        futures_util::future::poll_fn(move |cx| self.poll_informational_response(cx)).await
+    }
+
+    #[doc(hidden)]
+    pub fn poll_informational_response(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Response<()>>> {
+        self.inner.poll_informational(cx)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl futures_core::Stream for InformationalResponses {
+    type Item = Response<()>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_informational_response(cx)
+    }
 }
 
 // ===== impl PushPromises =====
@@ -1410,12 +2444,27 @@ impl PushedResponseFuture {
     }
 }
 
+// ===== impl PushPolicy =====
+
+impl PushPolicy {
+    pub(crate) fn check(&self, request: &Request<()>) -> Result<(), Reason> {
+        (self.0)(request)
+    }
+}
+
+impl fmt::Debug for PushPolicy {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("PushPolicy(..)")
+    }
+}
+
 // ===== impl Peer =====
 
 impl Peer {
     pub fn convert_send_message(
         id: StreamId,
         request: Request<()>,
+        protocol: Option<Protocol>,
         end_of_stream: bool,
     ) -> Result<Headers, SendError> {
         use http::request::Parts;
@@ -1433,11 +2482,26 @@ impl Peer {
 
         let is_connect = method == Method::CONNECT;
 
+        // The `:protocol` pseudo-header is only defined for CONNECT requests
+        // (RFC 8441 section 4).
+        if protocol.is_some() && !is_connect {
+            tracing::debug!("convert_send_message; :protocol on non-CONNECT request");
+            return Err(UserError::MalformedHeaders.into());
+        }
+
+        let is_extended_connect = protocol.is_some();
+
         // Build the set pseudo header set. All requests will include `method`
         // and `path`.
-        let mut pseudo = Pseudo::request(method, uri);
+        let mut pseudo = Pseudo::request(method, uri, protocol);
 
         if pseudo.scheme.is_none() {
+            // An extended CONNECT request must include `:scheme` and `:path`,
+            // so it can only be sent with an absolute URI.
+            if is_extended_connect {
+                return Err(UserError::MissingUriSchemeAndAuthority.into());
+            }
+
             // If the scheme is not set, then there are a two options.
             //
             // 1) Authority is not set. In this case, a request was issued with
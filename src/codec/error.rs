@@ -63,6 +63,19 @@ pub enum UserError {
 
     /// Tries to send push promise to peer who has disabled server push
     PeerDisabledServerPush,
+
+    /// Tries to make a stream depend on itself.
+    SelfDependency,
+
+    /// Tries to send an informational response without a 1xx status code,
+    /// or with 101, which HTTP/2 doesn't support.
+    InvalidInformationalStatusCode,
+
+    /// Tries to buffer more data on a stream than the configured maximum.
+    SendBufferFull,
+
+    /// Tries to send an `ALTSVC` frame with an empty or too long origin.
+    InvalidOrigin,
 }
 
 // ===== impl RecvError =====
@@ -136,6 +149,10 @@ impl fmt::Display for UserError {
             SendPingWhilePending => "send_ping before received previous pong",
             SendSettingsWhilePending => "sending SETTINGS before received previous ACK",
             PeerDisabledServerPush => "sending PUSH_PROMISE to peer who disabled server push",
+            SelfDependency => "stream cannot depend on itself",
+            InvalidInformationalStatusCode => "invalid informational status code",
+            SendBufferFull => "send buffer full",
+            InvalidOrigin => "invalid origin",
         })
     }
 }
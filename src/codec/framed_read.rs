@@ -260,6 +260,44 @@ fn decode_frame(
                 }
             }
         }
+        Kind::PriorityUpdate => {
+            match frame::PriorityUpdate::load(head, &bytes[frame::HEADER_LEN..]) {
+                Ok(frame) => frame.into(),
+                Err(frame::Error::BadFrameSize) => {
+                    proto_err!(conn: "PRIORITY_UPDATE frame too short");
+                    return Err(Connection(Reason::FRAME_SIZE_ERROR));
+                }
+                Err(e) => {
+                    proto_err!(conn: "failed to load PRIORITY_UPDATE frame; err={:?}", e);
+                    return Err(Connection(Reason::PROTOCOL_ERROR));
+                }
+            }
+        }
+        Kind::AltSvc => match frame::AltSvc::load(head, &bytes[frame::HEADER_LEN..]) {
+            Ok(frame) => frame.into(),
+            Err(e) => {
+                // Malformed ALTSVC frames must be ignored (RFC 7838,
+                // section 4).
+                tracing::debug!("ignoring malformed ALTSVC frame; err={:?}", e);
+                return Ok(None);
+            }
+        },
+        Kind::Origin => {
+            // ORIGIN frames on other streams must be ignored (RFC 8336,
+            // section 2.1).
+            if !head.stream_id().is_zero() {
+                tracing::trace!(stream_id = ?head.stream_id(), "ignoring ORIGIN frame");
+                return Ok(None);
+            }
+
+            match frame::Origin::load(head, &bytes[frame::HEADER_LEN..]) {
+                Ok(frame) => frame.into(),
+                Err(e) => {
+                    proto_err!(conn: "failed to load ORIGIN frame; err={:?}", e);
+                    return Err(Connection(Reason::FRAME_SIZE_ERROR));
+                }
+            }
+        }
         Kind::Continuation => {
             let is_end_headers = (head.flag() & 0x4) == 0x4;
 
@@ -331,8 +369,11 @@ fn decode_frame(
             }
         }
         Kind::Unknown => {
-            // Unknown frames are ignored
-            return Ok(None);
+            // Unknown frames are passed on, the application decides whether
+            // to ignore them.
+            let kind = bytes[3];
+            let _ = bytes.split_to(frame::HEADER_LEN);
+            frame::Unknown::load(head, kind, bytes.freeze()).into()
         }
     };
 
@@ -2,6 +2,7 @@ use crate::codec::UserError;
 use crate::codec::UserError::*;
 use crate::frame::{self, Frame, FrameSize};
 use crate::hpack;
+use crate::Padding;
 
 use bytes::{Buf, BufMut, BytesMut};
 use std::pin::Pin;
@@ -47,6 +48,9 @@ struct Encoder<B> {
 
     /// Whether or not the wrapped `AsyncWrite` supports vectored IO.
     is_write_vectored: bool,
+
+    /// Padding policy for HEADERS and PUSH_PROMISE frames
+    padding: Padding,
 }
 
 #[derive(Debug)]
@@ -86,6 +90,7 @@ where
                 last_data_frame: None,
                 max_frame_size: frame::DEFAULT_MAX_FRAME_SIZE,
                 is_write_vectored,
+                padding: Padding::none(),
             },
         }
     }
@@ -234,13 +239,15 @@ where
         match item {
             Frame::Data(mut v) => {
                 // Ensure that the payload is not greater than the max frame.
-                let len = v.payload().remaining();
+                let len = v.flow_controlled_len();
 
                 if len > self.max_frame_size() {
                     return Err(PayloadTooBig);
                 }
 
-                if len >= CHAIN_THRESHOLD {
+                // Padding follows the payload, so padded frames are always
+                // copied to the buffer.
+                if len >= CHAIN_THRESHOLD && v.padding().is_none() {
                     let head = v.head();
 
                     // Encode the frame head to the buffer
@@ -260,14 +267,22 @@ where
                 }
             }
             Frame::Headers(v) => {
+                let padding = &self.padding;
+                let id = v.stream_id();
                 let mut buf = limited_write_buf!(self);
-                if let Some(continuation) = v.encode(&mut self.hpack, &mut buf) {
+                if let Some(continuation) =
+                    v.encode(&mut self.hpack, &mut buf, |len| padding.pad_len(id, len))
+                {
                     self.next = Some(Next::Continuation(continuation));
                 }
             }
             Frame::PushPromise(v) => {
+                let padding = &self.padding;
+                let id = v.stream_id();
                 let mut buf = limited_write_buf!(self);
-                if let Some(continuation) = v.encode(&mut self.hpack, &mut buf) {
+                if let Some(continuation) =
+                    v.encode(&mut self.hpack, &mut buf, |len| padding.pad_len(id, len))
+                {
                     self.next = Some(Next::Continuation(continuation));
                 }
             }
@@ -288,17 +303,42 @@ where
                 tracing::trace!(rem = self.buf.remaining(), "encoded window_update");
             }
 
-            Frame::Priority(_) => {
-                /*
+            Frame::Priority(v) => {
+                v.encode(self.buf.get_mut());
+                tracing::trace!(rem = self.buf.remaining(), "encoded priority");
+            }
+            Frame::PriorityUpdate(v) => {
                 v.encode(self.buf.get_mut());
-                tracing::trace!("encoded priority; rem={:?}", self.buf.remaining());
-                */
-                unimplemented!();
+                tracing::trace!(rem = self.buf.remaining(), "encoded priority_update");
             }
             Frame::Reset(v) => {
                 v.encode(self.buf.get_mut());
                 tracing::trace!(rem = self.buf.remaining(), "encoded reset");
             }
+            Frame::AltSvc(v) => {
+                if v.payload_len() > self.max_frame_size() {
+                    return Err(PayloadTooBig);
+                }
+
+                v.encode(self.buf.get_mut());
+                tracing::trace!(rem = self.buf.remaining(), "encoded altsvc");
+            }
+            Frame::Origin(v) => {
+                if v.payload_len() > self.max_frame_size() {
+                    return Err(PayloadTooBig);
+                }
+
+                v.encode(self.buf.get_mut());
+                tracing::trace!(rem = self.buf.remaining(), "encoded origin");
+            }
+            Frame::Unknown(v) => {
+                if v.payload().len() > self.max_frame_size() {
+                    return Err(PayloadTooBig);
+                }
+
+                v.encode(self.buf.get_mut());
+                tracing::trace!(rem = self.buf.remaining(), "encoded unknown");
+            }
         }
 
         Ok(())
@@ -339,6 +379,11 @@ impl<T, B> FramedWrite<T, B> {
         self.encoder.hpack.update_max_size(val);
     }
 
+    /// Set the padding policy for HEADERS and PUSH_PROMISE frames.
+    pub fn set_padding(&mut self, padding: Padding) {
+        self.encoder.padding = padding;
+    }
+
     /// Retrieve the last data frame that has been sent
     pub fn take_last_data_frame(&mut self) -> Option<frame::Data<B>> {
         self.encoder.last_data_frame.take()
@@ -8,6 +8,7 @@ use self::framed_read::FramedRead;
 use self::framed_write::FramedWrite;
 
 use crate::frame::{self, Data, Frame};
+use crate::Padding;
 
 use bytes::Buf;
 use futures_core::Stream;
@@ -94,6 +95,11 @@ impl<T, B> Codec<T, B> {
         self.framed_write().set_header_table_size(val)
     }
 
+    /// Set the padding policy for HEADERS and PUSH_PROMISE frames.
+    pub fn set_padding(&mut self, padding: Padding) {
+        self.framed_write().set_padding(padding)
+    }
+
     /// Set the max header list size that can be received.
     pub fn set_max_recv_header_list_size(&mut self, val: usize) {
         self.inner.set_max_header_list_size(val);
@@ -1,6 +1,8 @@
 use crate::codec::{SendError, UserError};
 use crate::proto;
+use crate::{frame, StreamId};
 
+use bytes::Bytes;
 use std::{error, fmt, io};
 
 pub use crate::frame::Reason;
@@ -28,6 +30,10 @@ enum Kind {
     /// action taken by the peer (i.e. a protocol error).
     Proto(Reason),
 
+    /// The peer closed the connection with a GOAWAY frame, carrying the
+    /// error reason, the debug data and the last stream ID of the frame.
+    GoAway(Bytes, Reason, frame::StreamId),
+
     /// An error resulting from an invalid action taken by the user of this
     /// library.
     User(UserError),
@@ -45,19 +51,47 @@ impl Error {
     /// action taken by the peer (i.e. a protocol error).
     pub fn reason(&self) -> Option<Reason> {
         match self.kind {
-            Kind::Proto(reason) => Some(reason),
+            Kind::Proto(reason) | Kind::GoAway(_, reason, _) => Some(reason),
             _ => None,
         }
     }
 
-    /// Returns the true if the error is an io::Error
-    pub fn is_io(&self) -> bool {
+    /// Returns true if the error was received from the peer in a GOAWAY
+    /// frame.
+    pub fn is_go_away(&self) -> bool {
+        matches!(self.kind, Kind::GoAway(..))
+    }
+
+    /// If the error was received in a GOAWAY frame, the debug data of the
+    /// frame.
+    ///
+    /// The debug data is meant for diagnostics; its content is up to the
+    /// peer and it may be empty.
+    pub fn debug_data(&self) -> Option<&[u8]> {
         match self.kind {
-            Kind::Io(_) => true,
-            _ => false,
+            Kind::GoAway(ref debug_data, ..) => Some(debug_data),
+            _ => None,
         }
     }
 
+    /// If the error was received in a GOAWAY frame, the last stream ID of the
+    /// frame.
+    ///
+    /// This is the highest numbered stream initiated by us that the peer may
+    /// have processed. Streams with higher IDs were not processed and can
+    /// safely be retried on another connection.
+    pub fn last_stream_id(&self) -> Option<StreamId> {
+        match self.kind {
+            Kind::GoAway(_, _, last_stream_id) => Some(StreamId::from_internal(last_stream_id)),
+            _ => None,
+        }
+    }
+
+    /// Returns the true if the error is an io::Error
+    pub fn is_io(&self) -> bool {
+        matches!(self.kind, Kind::Io(_))
+    }
+
     /// Returns the error if the error is an io::Error
     pub fn get_io(&self) -> Option<&io::Error> {
         match self.kind {
@@ -88,6 +122,9 @@ impl From<proto::Error> for Error {
         Error {
             kind: match src {
                 Proto(reason) => Kind::Proto(reason),
+                GoAway(debug_data, reason, last_stream_id) => {
+                    Kind::GoAway(debug_data, reason, last_stream_id)
+                }
                 Io(e) => Kind::Io(e),
             },
         }
@@ -126,6 +163,12 @@ impl fmt::Display for Error {
 
         match self.kind {
             Proto(ref reason) => write!(fmt, "protocol error: {}", reason),
+            GoAway(ref debug_data, ref reason, _) if !debug_data.is_empty() => write!(
+                fmt,
+                "protocol error: {}; debug data: {:?}",
+                reason, debug_data
+            ),
+            GoAway(_, ref reason, _) => write!(fmt, "protocol error: {}", reason),
             User(ref e) => write!(fmt, "user error: {}", e),
             Io(ref e) => fmt::Display::fmt(e, fmt),
         }
@@ -0,0 +1,569 @@
+//! Extensions specific to the HTTP/2.0 protocol.
+
+use crate::frame;
+use crate::hpack::BytesStr;
+use crate::proto;
+use crate::{Reason, StreamId};
+
+use bytes::Bytes;
+use std::fmt;
+
+/// Represents the `:protocol` pseudo-header used by
+/// the [Extended CONNECT Protocol].
+///
+/// A `Protocol` is carried in the extensions of a `Request`. When sending a
+/// request with `SendRequest::send_request`, inserting a `Protocol` into the
+/// request extensions turns the `CONNECT` request into an extended `CONNECT`.
+/// When receiving a request on the server, the `:protocol` pseudo-header (if
+/// any) is made available the same way.
+///
+/// [Extended CONNECT Protocol]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+#[derive(Clone, Eq, PartialEq)]
+pub struct Protocol {
+    value: BytesStr,
+}
+
+impl Protocol {
+    /// Converts a static string to a protocol name.
+    pub const fn from_static(value: &'static str) -> Self {
+        Self {
+            value: BytesStr::from_static(value),
+        }
+    }
+
+    /// Returns a str representation of the header.
+    pub fn as_str(&self) -> &str {
+        self.value.as_str()
+    }
+
+    pub(crate) fn try_from(bytes: Bytes) -> Result<Self, std::str::Utf8Error> {
+        Ok(Self {
+            value: BytesStr::try_from(bytes)?,
+        })
+    }
+}
+
+impl<'a> From<&'a str> for Protocol {
+    fn from(value: &'a str) -> Self {
+        Self {
+            value: BytesStr::from(value),
+        }
+    }
+}
+
+impl AsRef<[u8]> for Protocol {
+    fn as_ref(&self) -> &[u8] {
+        self.value.as_ref()
+    }
+}
+
+impl fmt::Debug for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// The priority of a stream, as defined by the [Extensible Priority Scheme].
+///
+/// A `Priority` is made of an urgency, from 0 (most urgent) to 7 (least
+/// urgent), and an incremental flag indicating whether the response can be
+/// processed as it arrives. Streams with a lower urgency send their `DATA`
+/// first. Among streams of the same urgency, non-incremental streams are
+/// served one at a time, in stream ID order, while incremental streams share
+/// the connection in a round-robin fashion.
+///
+/// Clients signal the priority of a request with the `priority` header field
+/// and may change it later with `PRIORITY_UPDATE` frames.
+///
+/// [Extensible Priority Scheme]: https://datatracker.ietf.org/doc/html/rfc9218
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Priority {
+    urgency: u8,
+    incremental: bool,
+}
+
+impl Priority {
+    /// The default urgency, when none is signaled.
+    pub const DEFAULT_URGENCY: u8 = 3;
+
+    /// The least urgent value.
+    pub const MAX_URGENCY: u8 = 7;
+
+    /// Creates a new `Priority`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `urgency` is greater than 7.
+    pub fn new(urgency: u8, incremental: bool) -> Self {
+        assert!(urgency <= Self::MAX_URGENCY, "invalid urgency");
+        Priority {
+            urgency,
+            incremental,
+        }
+    }
+
+    /// Returns the urgency, from 0 (most urgent) to 7 (least urgent).
+    pub fn urgency(&self) -> u8 {
+        self.urgency
+    }
+
+    /// Returns `true` if the response may be processed incrementally.
+    pub fn is_incremental(&self) -> bool {
+        self.incremental
+    }
+
+    /// Parses the value of a `priority` header field or `PRIORITY_UPDATE`
+    /// frame.
+    ///
+    /// The value is a Structured Fields dictionary. Parameters that are
+    /// missing, unknown or invalid are ignored, leaving their default value.
+    pub(crate) fn parse(src: &[u8]) -> Self {
+        let mut priority = Priority::default();
+
+        for member in src.split(|b| *b == b',') {
+            // Drop the member's parameters, they have no meaning here.
+            let member = member.split(|b| *b == b';').next().unwrap_or(&[]);
+            let member = trim(member);
+
+            let (key, value) = match member.iter().position(|b| *b == b'=') {
+                Some(pos) => (&member[..pos], Some(&member[pos + 1..])),
+                None => (member, None),
+            };
+
+            match (key, value) {
+                (b"u", Some(value)) => {
+                    let urgency = std::str::from_utf8(value)
+                        .ok()
+                        .and_then(|value| value.parse::<u8>().ok());
+
+                    if let Some(urgency) = urgency.filter(|u| *u <= Self::MAX_URGENCY) {
+                        priority.urgency = urgency;
+                    }
+                }
+                (b"i", None) | (b"i", Some(b"?1")) => priority.incremental = true,
+                (b"i", Some(b"?0")) => priority.incremental = false,
+                _ => {}
+            }
+        }
+
+        priority
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority {
+            urgency: Self::DEFAULT_URGENCY,
+            incremental: false,
+        }
+    }
+}
+
+impl fmt::Debug for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Priority")
+            .field("urgency", &self.urgency)
+            .field("incremental", &self.incremental)
+            .finish()
+    }
+}
+
+impl fmt::Display for Priority {
+    /// Formats the priority as a `priority` header field value, omitting
+    /// default parameters.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.urgency, self.incremental) {
+            (Self::DEFAULT_URGENCY, false) => Ok(()),
+            (Self::DEFAULT_URGENCY, true) => f.write_str("i"),
+            (urgency, false) => write!(f, "u={}", urgency),
+            (urgency, true) => write!(f, "u={}, i", urgency),
+        }
+    }
+}
+
+/// The priority of a request in the dependency tree defined by [RFC 7540].
+///
+/// A `StreamDependency` is made of the parent stream the request depends on,
+/// the root of the tree by default, a weight in the range 1 to 256 and
+/// whether the dependency is exclusive. See [section 5.3] of RFC 7540 for
+/// their meaning.
+///
+/// A `StreamDependency` is carried in the extensions of a `Request`. When
+/// sending a request with `SendRequest::send_request`, inserting a
+/// `StreamDependency` into the request extensions sends it with the
+/// `HEADERS` frame. The priority of an open stream is changed with
+/// `ResponseFuture::reprioritize`.
+///
+/// # Examples
+///
+/// ```
+/// # use h2::ext::StreamDependency;
+/// # fn doc(parent: h2::StreamId) {
+/// let mut request = http::Request::new(());
+///
+/// // Depend exclusively on the stream of a previous request.
+/// request
+///     .extensions_mut()
+///     .insert(StreamDependency::new(32).parent(parent).exclusive(true));
+/// # }
+/// ```
+///
+/// [RFC 7540]: https://datatracker.ietf.org/doc/html/rfc7540
+/// [section 5.3]: https://datatracker.ietf.org/doc/html/rfc7540#section-5.3
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamDependency {
+    parent: Option<StreamId>,
+    weight: u16,
+    exclusive: bool,
+}
+
+impl StreamDependency {
+    /// The weight of streams that signal no priority.
+    pub const DEFAULT_WEIGHT: u16 = 16;
+
+    /// Creates a non-exclusive dependency on the root of the tree with the
+    /// given `weight`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `weight` is not in the range 1 to 256.
+    pub fn new(weight: u16) -> Self {
+        assert!((1..=256).contains(&weight), "invalid weight");
+        StreamDependency {
+            parent: None,
+            weight,
+            exclusive: false,
+        }
+    }
+
+    /// Makes the stream depend on the stream `parent`.
+    pub fn parent(mut self, parent: StreamId) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Sets whether the dependency is exclusive.
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Returns the stream the stream depends on, or `None` for the root of
+    /// the tree.
+    pub fn parent_id(&self) -> Option<StreamId> {
+        self.parent.clone()
+    }
+
+    /// Returns the weight, from 1 to 256.
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// Returns `true` if the dependency is exclusive.
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    pub(crate) fn to_frame(&self) -> frame::StreamDependency {
+        let parent = self
+            .parent
+            .as_ref()
+            .map_or(frame::StreamId::zero(), StreamId::to_internal);
+
+        frame::StreamDependency::new(parent, (self.weight - 1) as u8, self.exclusive)
+    }
+}
+
+impl Default for StreamDependency {
+    fn default() -> Self {
+        StreamDependency::new(Self::DEFAULT_WEIGHT)
+    }
+}
+
+/// The size of the window for data received on a single stream.
+///
+/// The initial window of every stream is set by the `initial_window_size`
+/// setting of the connection. An `InitialWindowSize` overrides it for one
+/// stream: when sending a request with `SendRequest::send_request`, inserting
+/// an `InitialWindowSize` into the request extensions sets the window of the
+/// response body.
+///
+/// If the size is larger than the setting, a `WINDOW_UPDATE` frame for the
+/// difference is sent right after the request `HEADERS`. If it is smaller,
+/// window updates are held back until the data in flight drops below it.
+///
+/// The window of a stream that is already open, such as the request body of
+/// a request accepted by a server, is set with
+/// `FlowControl::set_target_window_size`.
+///
+/// # Examples
+///
+/// ```
+/// # use h2::ext::InitialWindowSize;
+/// let mut request = http::Request::new(());
+///
+/// // The response is expected to be large.
+/// request
+///     .extensions_mut()
+///     .insert(InitialWindowSize::new(4 * 1024 * 1024));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InitialWindowSize(u32);
+
+impl InitialWindowSize {
+    /// Creates a new `InitialWindowSize` of `size` octets.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `size` is larger than 2^31-1, the largest
+    /// window allowed by HTTP/2.
+    pub fn new(size: u32) -> Self {
+        assert!(size <= proto::MAX_WINDOW_SIZE, "invalid window size");
+        InitialWindowSize(size)
+    }
+
+    /// Returns the size of the window, in octets.
+    pub fn size(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A frame of a type that h2 does not implement.
+///
+/// Protocol extensions may define new frame types (see [section 5.5] of RFC
+/// 7540). h2 hands the frames it doesn't know to the application as an
+/// `ExtensionFrame`, and allows sending them, either on the connection
+/// (stream 0) or on a stream.
+///
+/// The frame types defined by the HTTP/2 specification and the extensions
+/// implemented by h2 cannot be sent this way.
+///
+/// [section 5.5]: https://datatracker.ietf.org/doc/html/rfc7540#section-5.5
+#[derive(Clone, Eq, PartialEq)]
+pub struct ExtensionFrame {
+    kind: u8,
+    flags: u8,
+    payload: Bytes,
+}
+
+impl ExtensionFrame {
+    /// Creates a new frame with the given type, flags and payload.
+    pub fn new(kind: u8, flags: u8, payload: Bytes) -> Self {
+        ExtensionFrame {
+            kind,
+            flags,
+            payload,
+        }
+    }
+
+    /// Returns the frame type.
+    pub fn kind(&self) -> u8 {
+        self.kind
+    }
+
+    /// Returns the frame flags.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Returns the frame payload.
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// Consumes the frame, returning its payload.
+    pub fn into_payload(self) -> Bytes {
+        self.payload
+    }
+
+    /// Returns `true` if the frame type is handled by h2 itself.
+    pub(crate) fn is_known_kind(&self) -> bool {
+        frame::Kind::new(self.kind) != frame::Kind::Unknown
+    }
+
+    pub(crate) fn into_frame(self, stream_id: frame::StreamId) -> frame::Unknown {
+        frame::Unknown::new(stream_id, self.kind, self.flags, self.payload)
+    }
+}
+
+impl From<frame::Unknown> for ExtensionFrame {
+    fn from(src: frame::Unknown) -> Self {
+        ExtensionFrame {
+            kind: src.kind(),
+            flags: src.flags(),
+            payload: src.into_payload(),
+        }
+    }
+}
+
+impl fmt::Debug for ExtensionFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionFrame")
+            .field("kind", &self.kind)
+            .field("flags", &self.flags)
+            .field("payload", &self.payload)
+            .finish()
+    }
+}
+
+/// Alternative services advertised by the server with an `ALTSVC` frame.
+///
+/// See [RFC 7838] for the meaning of the value, which has the same syntax as
+/// the `Alt-Svc` header field.
+///
+/// [RFC 7838]: https://datatracker.ietf.org/doc/html/rfc7838
+#[derive(Clone, Eq, PartialEq)]
+pub struct AltSvc {
+    stream_id: Option<frame::StreamId>,
+    origin: Bytes,
+    value: Bytes,
+}
+
+impl AltSvc {
+    /// Returns the ID of the stream the frame was received on.
+    ///
+    /// The alternative services then apply to the origin of the request sent
+    /// on that stream. Returns `None` if the frame was received on the
+    /// connection, in which case the origin is given by [`origin`].
+    ///
+    /// [`origin`]: #method.origin
+    pub fn stream_id(&self) -> Option<StreamId> {
+        self.stream_id.map(StreamId::from_internal)
+    }
+
+    /// Returns the origin the alternative services apply to, as an ASCII
+    /// serialized origin (e.g. `https://example.com`).
+    ///
+    /// Returns `None` if the frame was received on a stream.
+    pub fn origin(&self) -> Option<&[u8]> {
+        if self.stream_id.is_some() {
+            None
+        } else {
+            Some(&self.origin)
+        }
+    }
+
+    /// Returns the alternative services, as an `Alt-Svc` field value.
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+}
+
+impl From<frame::AltSvc> for AltSvc {
+    fn from(src: frame::AltSvc) -> Self {
+        let stream_id = if src.stream_id().is_zero() {
+            None
+        } else {
+            Some(src.stream_id())
+        };
+
+        AltSvc {
+            stream_id,
+            origin: src.origin().clone(),
+            value: src.value().clone(),
+        }
+    }
+}
+
+impl fmt::Debug for AltSvc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AltSvc")
+            .field("stream_id", &self.stream_id)
+            .field("origin", &self.origin)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+/// A `GOAWAY` frame received from the peer.
+///
+/// A `GOAWAY` frame reporting an error is returned as an `Error` when the
+/// connection completes. One with `NO_ERROR`, which announces a graceful
+/// shutdown, is only made available through the connection.
+#[derive(Clone)]
+pub struct GoAway {
+    inner: frame::GoAway,
+}
+
+impl GoAway {
+    pub(crate) fn new(inner: frame::GoAway) -> Self {
+        GoAway { inner }
+    }
+
+    /// Returns the error code of the frame.
+    pub fn reason(&self) -> Reason {
+        self.inner.reason()
+    }
+
+    /// Returns the last stream ID of the frame.
+    ///
+    /// This is the highest numbered stream initiated by us that the peer may
+    /// have processed. Streams with higher IDs were not processed and can
+    /// safely be retried on another connection.
+    pub fn last_stream_id(&self) -> StreamId {
+        StreamId::from_internal(self.inner.last_stream_id())
+    }
+
+    /// Returns the debug data of the frame, which may be empty.
+    pub fn debug_data(&self) -> &[u8] {
+        self.inner.debug_data()
+    }
+}
+
+impl fmt::Debug for GoAway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GoAway")
+            .field("reason", &self.reason())
+            .field("last_stream_id", &self.last_stream_id())
+            .field("debug_data", &Bytes::copy_from_slice(self.debug_data()))
+            .finish()
+    }
+}
+
+fn trim(src: &[u8]) -> &[u8] {
+    let is_ows = |b: &u8| *b == b' ' || *b == b'\t';
+
+    let start = src.iter().position(|b| !is_ows(b)).unwrap_or(src.len());
+    let end = src
+        .iter()
+        .rposition(|b| !is_ows(b))
+        .map_or(start, |i| i + 1);
+
+    &src[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_priority() {
+        let p = Priority::parse(b"");
+        assert_eq!(p, Priority::default());
+
+        let p = Priority::parse(b"u=5, i");
+        assert_eq!(p, Priority::new(5, true));
+
+        let p = Priority::parse(b"i=?1;foo=bar,u=0");
+        assert_eq!(p, Priority::new(0, true));
+
+        let p = Priority::parse(b"u=1, i=?0, x=9");
+        assert_eq!(p, Priority::new(1, false));
+
+        // Out of range or malformed values are ignored
+        let p = Priority::parse(b"u=8, i=yes");
+        assert_eq!(p, Priority::default());
+
+        // The last value wins
+        let p = Priority::parse(b"u=1, u=6");
+        assert_eq!(p, Priority::new(6, false));
+    }
+
+    #[test]
+    fn display_priority() {
+        assert_eq!(Priority::default().to_string(), "");
+        assert_eq!(Priority::new(3, true).to_string(), "i");
+        assert_eq!(Priority::new(0, false).to_string(), "u=0");
+        assert_eq!(Priority::new(7, true).to_string(), "u=7, i");
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use bytes::{BufMut, Bytes};
+
+use crate::frame::{self, Error, Head, Kind, StreamId};
+
+/// The `ALTSVC` frame, as defined in RFC 7838, section 4.
+///
+/// It advertises alternative services for an origin. When sent on stream 0,
+/// the origin is carried in the frame. When sent on a stream, the origin is
+/// the one of the stream and the origin field is empty.
+#[derive(Clone, Eq, PartialEq)]
+pub struct AltSvc {
+    stream_id: StreamId,
+    origin: Bytes,
+    value: Bytes,
+}
+
+impl AltSvc {
+    pub fn new(stream_id: StreamId, origin: Bytes, value: Bytes) -> Self {
+        AltSvc {
+            stream_id,
+            origin,
+            value,
+        }
+    }
+
+    pub fn stream_id(&self) -> StreamId {
+        self.stream_id
+    }
+
+    /// The origin the alternative services apply to, empty unless the frame
+    /// is sent on stream 0.
+    pub fn origin(&self) -> &Bytes {
+        &self.origin
+    }
+
+    /// The alternative services, as an `Alt-Svc` header field value.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    pub fn payload_len(&self) -> usize {
+        2 + self.origin.len() + self.value.len()
+    }
+
+    pub fn load(head: Head, payload: &[u8]) -> Result<AltSvc, Error> {
+        if payload.len() < 2 {
+            return Err(Error::BadFrameSize);
+        }
+
+        let origin_len = ((payload[0] as usize) << 8) | payload[1] as usize;
+        let payload = &payload[2..];
+
+        if origin_len > payload.len() {
+            return Err(Error::BadFrameSize);
+        }
+
+        Ok(AltSvc {
+            stream_id: head.stream_id(),
+            origin: Bytes::copy_from_slice(&payload[..origin_len]),
+            value: Bytes::copy_from_slice(&payload[origin_len..]),
+        })
+    }
+
+    pub fn encode<B: BufMut>(&self, dst: &mut B) {
+        tracing::trace!(
+            "encoding ALTSVC; id={:?} origin={:?} value={:?}",
+            self.stream_id,
+            self.origin,
+            self.value
+        );
+        debug_assert!(self.origin.len() <= u16::MAX as usize, "origin too long");
+
+        let head = Head::new(Kind::AltSvc, 0, self.stream_id);
+        head.encode(self.payload_len(), dst);
+        dst.put_u16(self.origin.len() as u16);
+        dst.put_slice(&self.origin);
+        dst.put_slice(&self.value);
+    }
+}
+
+impl<B> From<AltSvc> for frame::Frame<B> {
+    fn from(src: AltSvc) -> Self {
+        frame::Frame::AltSvc(src)
+    }
+}
+
+impl fmt::Debug for AltSvc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AltSvc")
+            .field("stream_id", &self.stream_id)
+            .field("origin", &self.origin)
+            .field("value", &self.value)
+            .finish()
+    }
+}
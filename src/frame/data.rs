@@ -75,6 +75,23 @@ impl<T> Data<T> {
         self.flags.set_padded();
     }
 
+    /// Sets the number of padding octets to send with this frame, setting
+    /// the `PADDED` flag, or sends it unpadded if `None`.
+    pub fn set_padding(&mut self, pad_len: Option<u8>) {
+        self.pad_len = pad_len;
+
+        if pad_len.is_some() {
+            self.flags.set_padded();
+        } else {
+            self.flags.unset_padded();
+        }
+    }
+
+    /// Returns the number of padding octets of this frame, if it is padded.
+    pub(crate) fn padding(&self) -> Option<u8> {
+        self.pad_len
+    }
+
     /// Returns a reference to this frame's payload.
     ///
     /// This does **not** include any padding that might have been originally
@@ -142,18 +159,33 @@ impl Data<Bytes> {
 }
 
 impl<T: Buf> Data<T> {
+    /// Returns the length of the frame payload, including the padding.
+    ///
+    /// This is the size counted against flow control windows.
+    pub(crate) fn flow_controlled_len(&self) -> usize {
+        self.data.remaining() + self.pad_len.map_or(0, |pad_len| pad_len as usize + 1)
+    }
+
     /// Encode the data frame into the `dst` buffer.
     ///
     /// # Panics
     ///
     /// Panics if `dst` cannot contain the data frame.
     pub(crate) fn encode_chunk<U: BufMut>(&mut self, dst: &mut U) {
-        let len = self.data.remaining() as usize;
+        let len = self.flow_controlled_len();
 
         assert!(dst.remaining_mut() >= len);
 
         self.head().encode(len, dst);
-        dst.put(&mut self.data);
+
+        match self.pad_len {
+            Some(pad_len) => {
+                dst.put_u8(pad_len);
+                dst.put(&mut self.data);
+                dst.put_slice(&util::PADDING[..pad_len as usize]);
+            }
+            None => dst.put(&mut self.data),
+        }
     }
 }
 
@@ -205,10 +237,13 @@ impl DataFlags {
         self.0 & PADDED == PADDED
     }
 
-    #[cfg(feature = "unstable")]
     fn set_padded(&mut self) {
         self.0 |= PADDED
     }
+
+    fn unset_padded(&mut self) {
+        self.0 &= !PADDED
+    }
 }
 
 impl Default for DataFlags {
@@ -8,7 +8,6 @@ use crate::frame::{self, Error, Head, Kind, Reason, StreamId};
 pub struct GoAway {
     last_stream_id: StreamId,
     error_code: Reason,
-    #[allow(unused)]
     debug_data: Bytes,
 }
 
@@ -29,11 +28,18 @@ impl GoAway {
         self.error_code
     }
 
-    #[cfg(feature = "unstable")]
     pub fn debug_data(&self) -> &[u8] {
         &self.debug_data
     }
 
+    pub(crate) fn clone_debug_data(&self) -> Bytes {
+        self.debug_data.clone()
+    }
+
+    pub fn set_debug_data(&mut self, debug_data: Bytes) {
+        self.debug_data = debug_data;
+    }
+
     pub fn load(payload: &[u8]) -> Result<GoAway, Error> {
         if payload.len() < 8 {
             return Err(Error::BadFrameSize);
@@ -53,9 +59,10 @@ impl GoAway {
     pub fn encode<B: BufMut>(&self, dst: &mut B) {
         tracing::trace!("encoding GO_AWAY; code={:?}", self.error_code);
         let head = Head::new(Kind::GoAway, 0, StreamId::zero());
-        head.encode(8, dst);
+        head.encode(8 + self.debug_data.len(), dst);
         dst.put_u32(self.last_stream_id.into());
         dst.put_u32(self.error_code.into());
+        dst.put_slice(&self.debug_data);
     }
 }
 
@@ -1,4 +1,5 @@
 use super::{util, StreamDependency, StreamId};
+use crate::ext::Protocol;
 use crate::frame::{Error, Frame, Head, Kind};
 use crate::hpack::{self, BytesStr};
 
@@ -7,8 +8,8 @@ use http::{uri, HeaderMap, Method, Request, StatusCode, Uri};
 
 use bytes::{BufMut, Bytes, BytesMut};
 
-use std::fmt;
 use std::io::Cursor;
+use std::{cmp, fmt};
 
 type EncodeBuf<'a> = bytes::buf::Limit<&'a mut BytesMut>;
 /// Header frame
@@ -66,6 +67,7 @@ pub struct Pseudo {
     pub scheme: Option<BytesStr>,
     pub authority: Option<BytesStr>,
     pub path: Option<BytesStr>,
+    pub protocol: Option<Protocol>,
 
     // Response
     pub status: Option<StatusCode>,
@@ -228,10 +230,26 @@ impl Headers {
         self.flags.set_end_stream()
     }
 
+    /// Sets the value for the `PADDED` flag on this frame.
+    #[cfg(feature = "unstable")]
+    pub fn set_padded(&mut self) {
+        self.flags.set_padded()
+    }
+
     pub fn is_over_size(&self) -> bool {
         self.header_block.is_over_size
     }
 
+    pub fn stream_dep(&self) -> Option<&StreamDependency> {
+        self.stream_dep.as_ref()
+    }
+
+    /// Sets the stream dependency, setting the `PRIORITY` flag.
+    pub fn set_stream_dep(&mut self, stream_dep: StreamDependency) {
+        self.flags.set_priority();
+        self.stream_dep = Some(stream_dep);
+    }
+
     pub fn into_parts(self) -> (Pseudo, HeaderMap) {
         (self.header_block.pseudo, self.header_block.fields)
     }
@@ -246,6 +264,11 @@ impl Headers {
         self.header_block.pseudo.is_informational()
     }
 
+    /// Whether it has status 100
+    pub(crate) fn is_continue(&self) -> bool {
+        self.header_block.pseudo.status == Some(StatusCode::CONTINUE)
+    }
+
     pub fn fields(&self) -> &HeaderMap {
         &self.header_block.fields
     }
@@ -254,20 +277,33 @@ impl Headers {
         self.header_block.fields
     }
 
-    pub fn encode(
+    /// Encodes the frame, padded with the number of octets `pad` returns for
+    /// the length of the encoded header block.
+    pub fn encode<F>(
         self,
         encoder: &mut hpack::Encoder,
         dst: &mut EncodeBuf<'_>,
-    ) -> Option<Continuation> {
+        pad: F,
+    ) -> Option<Continuation>
+    where
+        F: FnOnce(usize) -> Option<u8>,
+    {
         // At this point, the `is_end_headers` flag should always be set
         debug_assert!(self.flags.is_end_headers());
 
         // Get the HEADERS frame head
         let head = self.head();
+        let stream_dep = self.stream_dep;
+
+        let block = self.header_block.into_encoding(encoder);
+        let dep_len = if stream_dep.is_some() { 5 } else { 0 };
+        let pad_len = pad(dep_len + block.hpack.len());
 
-        self.header_block
-            .into_encoding(encoder)
-            .encode(&head, dst, |_| {})
+        block.encode(&head, dst, pad_len, |dst| {
+            if let Some(stream_dep) = stream_dep {
+                stream_dep.encode(dst);
+            }
+        })
     }
 
     fn head(&self) -> Head {
@@ -462,22 +498,29 @@ impl PushPromise {
         self.header_block.is_over_size
     }
 
-    pub fn encode(
+    /// Encodes the frame, padded with the number of octets `pad` returns for
+    /// the length of the promised stream ID and encoded header block.
+    pub fn encode<F>(
         self,
         encoder: &mut hpack::Encoder,
         dst: &mut EncodeBuf<'_>,
-    ) -> Option<Continuation> {
+        pad: F,
+    ) -> Option<Continuation>
+    where
+        F: FnOnce(usize) -> Option<u8>,
+    {
         // At this point, the `is_end_headers` flag should always be set
         debug_assert!(self.flags.is_end_headers());
 
         let head = self.head();
         let promised_id = self.promised_id;
 
-        self.header_block
-            .into_encoding(encoder)
-            .encode(&head, dst, |dst| {
-                dst.put_u32(promised_id.into());
-            })
+        let block = self.header_block.into_encoding(encoder);
+        let pad_len = pad(4 + block.hpack.len());
+
+        block.encode(&head, dst, pad_len, |dst| {
+            dst.put_u32(promised_id.into());
+        })
     }
 
     fn head(&self) -> Head {
@@ -518,14 +561,14 @@ impl Continuation {
         // Get the CONTINUATION frame head
         let head = self.head();
 
-        self.header_block.encode(&head, dst, |_| {})
+        self.header_block.encode(&head, dst, None, |_| {})
     }
 }
 
 // ===== impl Pseudo =====
 
 impl Pseudo {
-    pub fn request(method: Method, uri: Uri) -> Self {
+    pub fn request(method: Method, uri: Uri, protocol: Option<Protocol>) -> Self {
         let parts = uri::Parts::from(uri);
 
         let mut path = parts
@@ -534,6 +577,10 @@ impl Pseudo {
             .unwrap_or(BytesStr::from_static(""));
 
         match method {
+            // An extended CONNECT request always carries a `:path`.
+            Method::CONNECT if protocol.is_some() && path.is_empty() => {
+                path = BytesStr::from_static("/");
+            }
             Method::OPTIONS | Method::CONNECT => {}
             _ if path.is_empty() => {
                 path = BytesStr::from_static("/");
@@ -546,6 +593,7 @@ impl Pseudo {
             scheme: None,
             authority: None,
             path: Some(path).filter(|p| !p.is_empty()),
+            protocol: None,
             status: None,
         };
 
@@ -562,6 +610,11 @@ impl Pseudo {
             pseudo.set_authority(BytesStr::from(authority.as_str()));
         }
 
+        // If the protocol is specified, add it to the pseudo headers
+        if let Some(protocol) = protocol {
+            pseudo.set_protocol(protocol);
+        }
+
         pseudo
     }
 
@@ -571,6 +624,7 @@ impl Pseudo {
             scheme: None,
             authority: None,
             path: None,
+            protocol: None,
             status: Some(status),
         }
     }
@@ -588,6 +642,10 @@ impl Pseudo {
         self.authority = Some(authority);
     }
 
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = Some(protocol);
+    }
+
     /// Whether it has status 1xx
     pub(crate) fn is_informational(&self) -> bool {
         self.status
@@ -598,7 +656,13 @@ impl Pseudo {
 // ===== impl EncodingHeaderBlock =====
 
 impl EncodingHeaderBlock {
-    fn encode<F>(mut self, head: &Head, dst: &mut EncodeBuf<'_>, f: F) -> Option<Continuation>
+    fn encode<F>(
+        mut self,
+        head: &Head,
+        dst: &mut EncodeBuf<'_>,
+        pad_len: Option<u8>,
+        f: F,
+    ) -> Option<Continuation>
     where
         F: FnOnce(&mut EncodeBuf<'_>),
     {
@@ -607,15 +671,31 @@ impl EncodingHeaderBlock {
         // At this point, we don't know how big the h2 frame will be.
         // So, we write the head with length 0, then write the body, and
         // finally write the length once we know the size.
-        head.encode(0, dst);
+        match pad_len {
+            Some(_) => {
+                let head = Head::new(head.kind(), head.flag() | PADDED, head.stream_id());
+                head.encode(0, dst);
+                // The pad length is written once it is known to fit.
+                dst.put_u8(0);
+            }
+            None => head.encode(0, dst),
+        }
 
         let payload_pos = dst.get_ref().len();
 
         f(dst);
 
+        // Only pad when the whole header block fits in this frame, and only
+        // up to the room left in it.
+        let pad_len = pad_len.map(|pad_len| {
+            let room = dst.remaining_mut().saturating_sub(self.hpack.len());
+            cmp::min(pad_len as usize, room) as u8
+        });
+        let rem = dst.remaining_mut() - pad_len.unwrap_or(0) as usize;
+
         // Now, encode the header payload
-        let continuation = if self.hpack.len() > dst.remaining_mut() {
-            dst.put_slice(&self.hpack.split_to(dst.remaining_mut()));
+        let continuation = if self.hpack.len() > rem {
+            dst.put_slice(&self.hpack.split_to(rem));
 
             Some(Continuation {
                 stream_id: head.stream_id(),
@@ -627,6 +707,15 @@ impl EncodingHeaderBlock {
             None
         };
 
+        let payload_pos = match pad_len {
+            Some(pad_len) => {
+                dst.put_slice(&util::PADDING[..pad_len as usize]);
+                dst.get_mut()[payload_pos - 1] = pad_len;
+                payload_pos - 1
+            }
+            None => payload_pos,
+        };
+
         // Compute the header block length
         let payload_len = (dst.get_ref().len() - payload_pos) as u64;
 
@@ -672,6 +761,10 @@ impl Iterator for Iter {
                 return Some(Path(path));
             }
 
+            if let Some(protocol) = pseudo.protocol.take() {
+                return Some(Protocol(protocol));
+            }
+
             if let Some(status) = pseudo.status.take() {
                 return Some(Status(status));
             }
@@ -716,9 +809,18 @@ impl HeadersFlag {
         self.0 & PADDED == PADDED
     }
 
+    #[cfg(feature = "unstable")]
+    pub fn set_padded(&mut self) {
+        self.0 |= PADDED;
+    }
+
     pub fn is_priority(&self) -> bool {
         self.0 & PRIORITY == PRIORITY
     }
+
+    pub fn set_priority(&mut self) {
+        self.0 |= PRIORITY;
+    }
 }
 
 impl Default for HeadersFlag {
@@ -870,6 +972,7 @@ impl HeaderBlock {
                 Method(v) => set_pseudo!(method, v),
                 Scheme(v) => set_pseudo!(scheme, v),
                 Path(v) => set_pseudo!(path, v),
+                Protocol(v) => set_pseudo!(protocol, v),
                 Status(v) => set_pseudo!(status, v),
             }
         });
@@ -924,6 +1027,7 @@ impl HeaderBlock {
             + pseudo_size!(status)
             + pseudo_size!(authority)
             + pseudo_size!(path)
+            + pseudo_size!(protocol)
             + self
                 .fields
                 .iter()
@@ -971,7 +1075,11 @@ mod test {
         );
 
         let continuation = headers
-            .encode(&mut encoder, &mut (&mut dst).limit(frame::HEADER_LEN + 8))
+            .encode(
+                &mut encoder,
+                &mut (&mut dst).limit(frame::HEADER_LEN + 8),
+                |_| None,
+            )
             .unwrap();
 
         assert_eq!(17, dst.len());
@@ -25,31 +25,39 @@ macro_rules! unpack_octets_4 {
     };
 }
 
+mod alt_svc;
 mod data;
 mod go_away;
 mod head;
 mod headers;
+mod origin;
 mod ping;
 mod priority;
+mod priority_update;
 mod reason;
 mod reset;
 mod settings;
 mod stream_id;
+mod unknown;
 mod util;
 mod window_update;
 
+pub use self::alt_svc::AltSvc;
 pub use self::data::Data;
 pub use self::go_away::GoAway;
 pub use self::head::{Head, Kind};
 pub use self::headers::{
     parse_u64, Continuation, Headers, Pseudo, PushPromise, PushPromiseHeaderError,
 };
+pub use self::origin::Origin;
 pub use self::ping::Ping;
 pub use self::priority::{Priority, StreamDependency};
+pub use self::priority_update::PriorityUpdate;
 pub use self::reason::Reason;
 pub use self::reset::Reset;
 pub use self::settings::Settings;
 pub use self::stream_id::{StreamId, StreamIdOverflow};
+pub use self::unknown::Unknown;
 pub use self::window_update::WindowUpdate;
 
 #[cfg(feature = "unstable")]
@@ -71,12 +79,16 @@ pub enum Frame<T = Bytes> {
     Data(Data<T>),
     Headers(Headers),
     Priority(Priority),
+    PriorityUpdate(PriorityUpdate),
     PushPromise(PushPromise),
     Settings(Settings),
     Ping(Ping),
     GoAway(GoAway),
     WindowUpdate(WindowUpdate),
     Reset(Reset),
+    AltSvc(AltSvc),
+    Origin(Origin),
+    Unknown(Unknown),
 }
 
 impl<T> Frame<T> {
@@ -90,12 +102,16 @@ impl<T> Frame<T> {
             Data(frame) => frame.map(f).into(),
             Headers(frame) => frame.into(),
             Priority(frame) => frame.into(),
+            PriorityUpdate(frame) => frame.into(),
             PushPromise(frame) => frame.into(),
             Settings(frame) => frame.into(),
             Ping(frame) => frame.into(),
             GoAway(frame) => frame.into(),
             WindowUpdate(frame) => frame.into(),
             Reset(frame) => frame.into(),
+            AltSvc(frame) => frame.into(),
+            Origin(frame) => frame.into(),
+            Unknown(frame) => frame.into(),
         }
     }
 }
@@ -108,12 +124,16 @@ impl<T> fmt::Debug for Frame<T> {
             Data(ref frame) => fmt::Debug::fmt(frame, fmt),
             Headers(ref frame) => fmt::Debug::fmt(frame, fmt),
             Priority(ref frame) => fmt::Debug::fmt(frame, fmt),
+            PriorityUpdate(ref frame) => fmt::Debug::fmt(frame, fmt),
             PushPromise(ref frame) => fmt::Debug::fmt(frame, fmt),
             Settings(ref frame) => fmt::Debug::fmt(frame, fmt),
             Ping(ref frame) => fmt::Debug::fmt(frame, fmt),
             GoAway(ref frame) => fmt::Debug::fmt(frame, fmt),
             WindowUpdate(ref frame) => fmt::Debug::fmt(frame, fmt),
             Reset(ref frame) => fmt::Debug::fmt(frame, fmt),
+            AltSvc(ref frame) => fmt::Debug::fmt(frame, fmt),
+            Origin(ref frame) => fmt::Debug::fmt(frame, fmt),
+            Unknown(ref frame) => fmt::Debug::fmt(frame, fmt),
         }
     }
 }
@@ -0,0 +1,80 @@
+use std::fmt;
+
+use bytes::{BufMut, Bytes};
+
+use crate::frame::{self, Error, Head, Kind, StreamId};
+
+/// The `ORIGIN` frame, as defined in RFC 8336, section 2.
+///
+/// It is sent on stream 0 by a server to list the origins it is
+/// authoritative for on the connection. Each frame adds its origins to the
+/// ones received before.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct Origin {
+    origins: Vec<Bytes>,
+}
+
+impl Origin {
+    pub fn new(origins: Vec<Bytes>) -> Self {
+        Origin { origins }
+    }
+
+    /// The ASCII serialized origins carried by the frame.
+    pub fn into_origins(self) -> Vec<Bytes> {
+        self.origins
+    }
+
+    pub fn payload_len(&self) -> usize {
+        self.origins.iter().map(|origin| 2 + origin.len()).sum()
+    }
+
+    pub fn load(head: Head, payload: &[u8]) -> Result<Origin, Error> {
+        debug_assert_eq!(head.kind(), Kind::Origin);
+
+        let mut origins = Vec::new();
+        let mut rem = payload;
+
+        while !rem.is_empty() {
+            if rem.len() < 2 {
+                return Err(Error::BadFrameSize);
+            }
+
+            let len = ((rem[0] as usize) << 8) | rem[1] as usize;
+            rem = &rem[2..];
+
+            if len > rem.len() {
+                return Err(Error::BadFrameSize);
+            }
+
+            origins.push(Bytes::copy_from_slice(&rem[..len]));
+            rem = &rem[len..];
+        }
+
+        Ok(Origin { origins })
+    }
+
+    pub fn encode<B: BufMut>(&self, dst: &mut B) {
+        tracing::trace!("encoding ORIGIN; origins={:?}", self.origins);
+        let head = Head::new(Kind::Origin, 0, StreamId::zero());
+        head.encode(self.payload_len(), dst);
+
+        for origin in &self.origins {
+            dst.put_u16(origin.len() as u16);
+            dst.put_slice(origin);
+        }
+    }
+}
+
+impl<B> From<Origin> for frame::Frame<B> {
+    fn from(src: Origin) -> Self {
+        frame::Frame::Origin(src)
+    }
+}
+
+impl fmt::Debug for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Origin")
+            .field("origins", &self.origins)
+            .finish()
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+use bytes::{BufMut, Bytes};
+
+use crate::frame::{self, Error, Head, Kind, StreamId};
+
+/// The `PRIORITY_UPDATE` frame, as defined in RFC 9218, section 7.1.
+///
+/// It is sent on stream 0 by a client to change the priority of a request
+/// stream. The new priority is carried as a `priority` field value.
+#[derive(Clone, Eq, PartialEq)]
+pub struct PriorityUpdate {
+    prioritized_id: StreamId,
+    field_value: Bytes,
+}
+
+impl PriorityUpdate {
+    #[cfg(feature = "unstable")]
+    pub fn new(prioritized_id: StreamId, field_value: Bytes) -> Self {
+        PriorityUpdate {
+            prioritized_id,
+            field_value,
+        }
+    }
+
+    /// The ID of the stream whose priority is updated.
+    pub fn prioritized_id(&self) -> StreamId {
+        self.prioritized_id
+    }
+
+    /// The new priority, as a `priority` header field value.
+    pub fn field_value(&self) -> &[u8] {
+        &self.field_value
+    }
+
+    pub fn load(head: Head, payload: &[u8]) -> Result<PriorityUpdate, Error> {
+        if !head.stream_id().is_zero() {
+            return Err(Error::InvalidStreamId);
+        }
+
+        if payload.len() < 4 {
+            return Err(Error::BadFrameSize);
+        }
+
+        let (prioritized_id, _) = StreamId::parse(&payload[..4]);
+
+        if prioritized_id.is_zero() {
+            return Err(Error::InvalidStreamId);
+        }
+
+        Ok(PriorityUpdate {
+            prioritized_id,
+            field_value: Bytes::copy_from_slice(&payload[4..]),
+        })
+    }
+
+    pub fn encode<B: BufMut>(&self, dst: &mut B) {
+        tracing::trace!(
+            "encoding PRIORITY_UPDATE; id={:?} value={:?}",
+            self.prioritized_id,
+            self.field_value
+        );
+        let head = Head::new(Kind::PriorityUpdate, 0, StreamId::zero());
+        head.encode(4 + self.field_value.len(), dst);
+        dst.put_u32(self.prioritized_id.into());
+        dst.put_slice(&self.field_value);
+    }
+}
+
+impl<B> From<PriorityUpdate> for frame::Frame<B> {
+    fn from(src: PriorityUpdate) -> Self {
+        frame::Frame::PriorityUpdate(src)
+    }
+}
+
+impl fmt::Debug for PriorityUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityUpdate")
+            .field("prioritized_id", &self.prioritized_id)
+            .field("field_value", &self.field_value)
+            .finish()
+    }
+}
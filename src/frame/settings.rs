@@ -1,7 +1,8 @@
-use std::fmt;
+use std::{cmp, fmt};
 
 use crate::frame::{util, Error, Frame, FrameSize, Head, Kind, StreamId};
 use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
 
 #[derive(Clone, Default, Eq, PartialEq)]
 pub struct Settings {
@@ -13,6 +14,10 @@ pub struct Settings {
     initial_window_size: Option<u32>,
     max_frame_size: Option<u32>,
     max_header_list_size: Option<u32>,
+    enable_connect_protocol: Option<u32>,
+    no_rfc7540_priorities: Option<u32>,
+    // Settings that h2 doesn't implement, in the order they were set.
+    unknown: IndexMap<u16, u32>,
 }
 
 /// An enum that lists all valid settings that can be sent in a SETTINGS
@@ -27,6 +32,9 @@ pub enum Setting {
     InitialWindowSize(u32),
     MaxFrameSize(u32),
     MaxHeaderListSize(u32),
+    EnableConnectProtocol(u32),
+    NoRfc7540Priorities(u32),
+    Unknown(u16, u32),
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Default)]
@@ -50,6 +58,10 @@ pub const MAX_INITIAL_WINDOW_SIZE: usize = (1 << 31) - 1;
 /// MAX_FRAME_SIZE upper bound
 pub const MAX_MAX_FRAME_SIZE: FrameSize = (1 << 24) - 1;
 
+/// The maximum number of distinct settings that h2 doesn't implement which
+/// are kept. Received settings beyond this are ignored.
+pub const MAX_UNKNOWN_SETTINGS: usize = 64;
+
 // ===== impl Settings =====
 
 impl Settings {
@@ -72,6 +84,24 @@ impl Settings {
         self.initial_window_size = size;
     }
 
+    /// Caps the initial stream window, so that as many streams as may be
+    /// open at once stay within `budget`.
+    ///
+    /// The window is never capped below one byte, so that streams can still
+    /// make progress.
+    pub(crate) fn cap_initial_window_size(&mut self, budget: u32) {
+        let streams = cmp::max(self.max_concurrent_streams.unwrap_or(1), 1);
+        let share = cmp::max(budget / streams, 1);
+
+        if self
+            .initial_window_size
+            .unwrap_or(DEFAULT_INITIAL_WINDOW_SIZE)
+            > share
+        {
+            self.initial_window_size = Some(share);
+        }
+    }
+
     pub fn max_concurrent_streams(&self) -> Option<u32> {
         self.max_concurrent_streams
     }
@@ -107,10 +137,88 @@ impl Settings {
         self.enable_push = Some(enable as u32);
     }
 
+    pub fn is_extended_connect_protocol_enabled(&self) -> Option<bool> {
+        self.enable_connect_protocol.map(|val| val != 0)
+    }
+
+    pub fn set_enable_connect_protocol(&mut self, val: Option<u32>) {
+        self.enable_connect_protocol = val;
+    }
+
+    pub fn is_rfc7540_priorities_disabled(&self) -> Option<bool> {
+        self.no_rfc7540_priorities.map(|val| val != 0)
+    }
+
+    pub fn set_no_rfc7540_priorities(&mut self, val: Option<u32>) {
+        self.no_rfc7540_priorities = val;
+    }
+
     pub fn header_table_size(&self) -> Option<u32> {
         self.header_table_size
     }
 
+    /// Returns the value of the setting `id`, known or not.
+    pub fn get(&self, id: u16) -> Option<u32> {
+        let mut ret = None;
+        self.for_each(|setting| {
+            if setting.id() == id {
+                ret = Some(setting.value());
+            }
+        });
+        ret
+    }
+
+    /// Sets a setting that h2 doesn't implement.
+    ///
+    /// # Panics
+    ///
+    /// If `id` is the identifier of a setting that h2 implements, or if
+    /// `MAX_UNKNOWN_SETTINGS` distinct settings are already set.
+    pub fn set_unknown_setting(&mut self, id: u16, val: u32) {
+        assert!(
+            Setting::from_id(id, val).is_none(),
+            "setting {} is implemented by h2",
+            id
+        );
+        assert!(
+            self.unknown.len() < MAX_UNKNOWN_SETTINGS || self.unknown.contains_key(&id),
+            "too many custom settings"
+        );
+        self.set_unknown(id, val);
+    }
+
+    fn set_unknown(&mut self, id: u16, val: u32) {
+        if self.unknown.len() >= MAX_UNKNOWN_SETTINGS && !self.unknown.contains_key(&id) {
+            tracing::trace!("ignoring unknown setting; id={}", id);
+            return;
+        }
+
+        self.unknown.insert(id, val);
+    }
+
+    /// Updates these settings with the values set in `other`, such that
+    /// `self` reflects the values in effect after receiving both frames.
+    pub fn merge(&mut self, other: &Settings) {
+        use self::Setting::*;
+
+        other.for_each(|setting| match setting {
+            HeaderTableSize(v) => self.header_table_size = Some(v),
+            EnablePush(v) => self.enable_push = Some(v),
+            MaxConcurrentStreams(v) => self.max_concurrent_streams = Some(v),
+            InitialWindowSize(v) => self.initial_window_size = Some(v),
+            MaxFrameSize(v) => self.max_frame_size = Some(v),
+            MaxHeaderListSize(v) => self.max_header_list_size = Some(v),
+            EnableConnectProtocol(v) => self.enable_connect_protocol = Some(v),
+            NoRfc7540Priorities(v) => self.no_rfc7540_priorities = Some(v),
+            Unknown(id, v) => self.set_unknown(id, v),
+        });
+    }
+
+    /// Calls `f` with the (id, value) pair of every setting that is set.
+    pub fn for_each_raw<F: FnMut(u16, u32)>(&self, mut f: F) {
+        self.for_each(|setting| f(setting.id(), setting.value()));
+    }
+
     /*
     pub fn set_header_table_size(&mut self, size: Option<u32>) {
         self.header_table_size = size;
@@ -150,10 +258,10 @@ impl Settings {
 
         for raw in payload.chunks(6) {
             match Setting::load(raw) {
-                Some(HeaderTableSize(val)) => {
+                HeaderTableSize(val) => {
                     settings.header_table_size = Some(val);
                 }
-                Some(EnablePush(val)) => match val {
+                EnablePush(val) => match val {
                     0 | 1 => {
                         settings.enable_push = Some(val);
                     }
@@ -161,27 +269,45 @@ impl Settings {
                         return Err(Error::InvalidSettingValue);
                     }
                 },
-                Some(MaxConcurrentStreams(val)) => {
+                MaxConcurrentStreams(val) => {
                     settings.max_concurrent_streams = Some(val);
                 }
-                Some(InitialWindowSize(val)) => {
+                InitialWindowSize(val) => {
                     if val as usize > MAX_INITIAL_WINDOW_SIZE {
                         return Err(Error::InvalidSettingValue);
                     } else {
                         settings.initial_window_size = Some(val);
                     }
                 }
-                Some(MaxFrameSize(val)) => {
+                MaxFrameSize(val) => {
                     if val < DEFAULT_MAX_FRAME_SIZE || val > MAX_MAX_FRAME_SIZE {
                         return Err(Error::InvalidSettingValue);
                     } else {
                         settings.max_frame_size = Some(val);
                     }
                 }
-                Some(MaxHeaderListSize(val)) => {
+                MaxHeaderListSize(val) => {
                     settings.max_header_list_size = Some(val);
                 }
-                None => {}
+                EnableConnectProtocol(val) => match val {
+                    0 | 1 => {
+                        settings.enable_connect_protocol = Some(val);
+                    }
+                    _ => {
+                        return Err(Error::InvalidSettingValue);
+                    }
+                },
+                NoRfc7540Priorities(val) => match val {
+                    0 | 1 => {
+                        settings.no_rfc7540_priorities = Some(val);
+                    }
+                    _ => {
+                        return Err(Error::InvalidSettingValue);
+                    }
+                },
+                Unknown(id, val) => {
+                    settings.set_unknown(id, val);
+                }
             }
         }
 
@@ -203,7 +329,12 @@ impl Settings {
 
         head.encode(payload_len, dst);
 
-        // Encode the settings
+        self.encode_payload(dst);
+    }
+
+    /// Encodes the settings without the frame head, as carried by the
+    /// `HTTP2-Settings` header of an HTTP/1.1 upgrade request.
+    pub fn encode_payload(&self, dst: &mut BytesMut) {
         self.for_each(|setting| {
             tracing::trace!("encoding setting; val={:?}", setting);
             setting.encode(dst)
@@ -236,6 +367,18 @@ impl Settings {
         if let Some(v) = self.max_header_list_size {
             f(MaxHeaderListSize(v));
         }
+
+        if let Some(v) = self.enable_connect_protocol {
+            f(EnableConnectProtocol(v));
+        }
+
+        if let Some(v) = self.no_rfc7540_priorities {
+            f(NoRfc7540Priorities(v));
+        }
+
+        for (&id, &v) in &self.unknown {
+            f(Unknown(id, v));
+        }
     }
 }
 
@@ -269,6 +412,15 @@ impl fmt::Debug for Settings {
             Setting::MaxHeaderListSize(v) => {
                 builder.field("max_header_list_size", &v);
             }
+            Setting::EnableConnectProtocol(v) => {
+                builder.field("enable_connect_protocol", &v);
+            }
+            Setting::NoRfc7540Priorities(v) => {
+                builder.field("no_rfc7540_priorities", &v);
+            }
+            Setting::Unknown(id, v) => {
+                builder.field("unknown", &(id, v));
+            }
         });
 
         builder.finish()
@@ -291,6 +443,8 @@ impl Setting {
             4 => Some(InitialWindowSize(val)),
             5 => Some(MaxFrameSize(val)),
             6 => Some(MaxHeaderListSize(val)),
+            8 => Some(EnableConnectProtocol(val)),
+            9 => Some(NoRfc7540Priorities(val)),
             _ => None,
         }
     }
@@ -305,27 +459,52 @@ impl Setting {
     /// # Panics
     ///
     /// If given a buffer shorter than 6 bytes, the function will panic.
-    fn load(raw: &[u8]) -> Option<Setting> {
+    ///
+    /// Settings with an identifier that h2 doesn't implement are returned as
+    /// `Setting::Unknown`.
+    fn load(raw: &[u8]) -> Setting {
         let id: u16 = (u16::from(raw[0]) << 8) | u16::from(raw[1]);
         let val: u32 = unpack_octets_4!(raw, 2, u32);
 
-        Setting::from_id(id, val)
+        Setting::from_id(id, val).unwrap_or(Setting::Unknown(id, val))
     }
 
-    fn encode(&self, dst: &mut BytesMut) {
+    /// Returns the setting identifier, as defined in section 6.5.2.
+    fn id(&self) -> u16 {
         use self::Setting::*;
 
-        let (kind, val) = match *self {
-            HeaderTableSize(v) => (1, v),
-            EnablePush(v) => (2, v),
-            MaxConcurrentStreams(v) => (3, v),
-            InitialWindowSize(v) => (4, v),
-            MaxFrameSize(v) => (5, v),
-            MaxHeaderListSize(v) => (6, v),
-        };
-
-        dst.put_u16(kind);
-        dst.put_u32(val);
+        match *self {
+            HeaderTableSize(_) => 1,
+            EnablePush(_) => 2,
+            MaxConcurrentStreams(_) => 3,
+            InitialWindowSize(_) => 4,
+            MaxFrameSize(_) => 5,
+            MaxHeaderListSize(_) => 6,
+            EnableConnectProtocol(_) => 8,
+            NoRfc7540Priorities(_) => 9,
+            Unknown(id, _) => id,
+        }
+    }
+
+    fn value(&self) -> u32 {
+        use self::Setting::*;
+
+        match *self {
+            HeaderTableSize(v)
+            | EnablePush(v)
+            | MaxConcurrentStreams(v)
+            | InitialWindowSize(v)
+            | MaxFrameSize(v)
+            | MaxHeaderListSize(v)
+            | EnableConnectProtocol(v)
+            | NoRfc7540Priorities(v)
+            | Unknown(_, v) => v,
+        }
+    }
+
+    fn encode(&self, dst: &mut BytesMut) {
+        dst.put_u16(self.id());
+        dst.put_u32(self.value());
     }
 }
 
@@ -0,0 +1,91 @@
+use std::fmt;
+
+use bytes::{BufMut, Bytes};
+
+use crate::frame::{self, Head, StreamId};
+
+/// A frame of a type that is not handled by h2.
+///
+/// Endpoints must ignore frames of an unknown type (section 4.1), unless an
+/// extension negotiated them. Such frames are passed on to the application
+/// as is.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Unknown {
+    stream_id: StreamId,
+    kind: u8,
+    flags: u8,
+    payload: Bytes,
+}
+
+impl Unknown {
+    pub fn new(stream_id: StreamId, kind: u8, flags: u8, payload: Bytes) -> Self {
+        Unknown {
+            stream_id,
+            kind,
+            flags,
+            payload,
+        }
+    }
+
+    pub fn stream_id(&self) -> StreamId {
+        self.stream_id
+    }
+
+    /// The raw frame type.
+    pub fn kind(&self) -> u8 {
+        self.kind
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    pub fn into_payload(self) -> Bytes {
+        self.payload
+    }
+
+    pub fn load(head: Head, kind: u8, payload: Bytes) -> Unknown {
+        Unknown {
+            stream_id: head.stream_id(),
+            kind,
+            flags: head.flag(),
+            payload,
+        }
+    }
+
+    pub fn encode<B: BufMut>(&self, dst: &mut B) {
+        tracing::trace!(
+            "encoding unknown frame; type={} id={:?} len={}",
+            self.kind,
+            self.stream_id,
+            self.payload.len()
+        );
+        // `Head` can't represent the frame type, encode the header by hand.
+        dst.put_uint(self.payload.len() as u64, 3);
+        dst.put_u8(self.kind);
+        dst.put_u8(self.flags);
+        dst.put_u32(self.stream_id.into());
+        dst.put_slice(&self.payload);
+    }
+}
+
+impl<B> From<Unknown> for frame::Frame<B> {
+    fn from(src: Unknown) -> Self {
+        frame::Frame::Unknown(src)
+    }
+}
+
+impl fmt::Debug for Unknown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Unknown")
+            .field("stream_id", &self.stream_id)
+            .field("kind", &self.kind)
+            .field("flags", &self.flags)
+            .field("len", &self.payload.len())
+            .finish()
+    }
+}
@@ -3,6 +3,9 @@ use std::fmt;
 use super::Error;
 use bytes::Bytes;
 
+/// Zeroed octets to pad frames with.
+pub const PADDING: [u8; 255] = [0; 255];
+
 /// Strip padding from the given payload.
 ///
 /// It is assumed that the frame had the padded flag set. This means that the
@@ -114,15 +114,24 @@ mod frame;
 pub mod frame;
 
 pub mod client;
+pub mod ext;
+mod padding;
 pub mod server;
 mod share;
+mod stream_io;
+mod timer;
 
 #[cfg(fuzzing)]
 #[cfg_attr(feature = "unstable", allow(missing_docs))]
 pub mod fuzz_bridge;
 
 pub use crate::error::{Error, Reason};
-pub use crate::share::{FlowControl, Ping, PingPong, Pong, RecvStream, SendStream, StreamId};
+pub use crate::padding::Padding;
+pub use crate::share::{
+    FlowControl, Ping, PingPong, Pong, RecvStream, RemoteSettings, SendStream, StreamId,
+};
+pub use crate::stream_io::StreamIo;
+pub use crate::timer::{Sleep, Timer};
 
 #[cfg(feature = "unstable")]
 pub use codec::{Codec, RecvError, SendError, UserError};
@@ -0,0 +1,154 @@
+use crate::frame;
+use crate::StreamId;
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Padding policy for outgoing `DATA`, `HEADERS` and `PUSH_PROMISE` frames.
+///
+/// Padding obscures the size of the messages exchanged on a connection, as
+/// described in [Section 10.7] of the HTTP/2.0 spec. A padded frame carries a
+/// pad length octet followed by up to 255 padding octets, which are counted
+/// against flow control windows for `DATA` frames.
+///
+/// Padding is never allowed to make a frame exceed the peer's max frame size
+/// or, for `DATA` frames, the available send window. The padding of a frame
+/// is reduced, or left out, to fit when needed. `HEADERS` and `PUSH_PROMISE` frames are
+/// only padded when the header block fits in a single frame.
+///
+/// The default policy does not pad frames.
+///
+/// [Section 10.7]: https://httpwg.org/specs/rfc7540.html#padding
+///
+/// # Examples
+///
+/// ```
+/// # use h2::Padding;
+/// // Pad every frame payload to a multiple of 128 octets.
+/// let padding = Padding::block(128);
+///
+/// // Only pad small frames, up to 256 octets.
+/// let padding = Padding::custom(|_stream_id, len| {
+///     if len < 255 {
+///         Some(255 - len as u8)
+///     } else {
+///         None
+///     }
+/// });
+/// ```
+#[derive(Clone)]
+pub struct Padding {
+    inner: Inner,
+}
+
+#[derive(Clone)]
+enum Inner {
+    None,
+    Fixed(u8),
+    Block(u8),
+    Random(u8, Arc<Random>),
+    Custom(Arc<dyn Fn(StreamId, usize) -> Option<u8> + Send + Sync>),
+}
+
+// Lengths are drawn by hashing a counter with randomly seeded keys.
+struct Random {
+    state: RandomState,
+    count: AtomicU64,
+}
+
+impl Padding {
+    /// Frames are not padded.
+    pub fn none() -> Self {
+        Padding { inner: Inner::None }
+    }
+
+    /// Every frame is padded with `len` octets.
+    pub fn fixed(len: u8) -> Self {
+        Padding {
+            inner: Inner::Fixed(len),
+        }
+    }
+
+    /// Every frame is padded so that its payload, including the pad length
+    /// octet and the padding, is a multiple of `size` octets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn block(size: u8) -> Self {
+        assert!(size > 0, "block size must not be 0");
+        Padding {
+            inner: Inner::Block(size),
+        }
+    }
+
+    /// Every frame is padded with a random number of octets, up to `max`.
+    ///
+    /// The lengths are not drawn from a cryptographically secure generator.
+    pub fn random(max: u8) -> Self {
+        Padding {
+            inner: Inner::Random(
+                max,
+                Arc::new(Random {
+                    state: RandomState::new(),
+                    count: AtomicU64::new(0),
+                }),
+            ),
+        }
+    }
+
+    /// The padding of every frame is decided by `f`.
+    ///
+    /// `f` is called with the stream the frame is sent on and the length of
+    /// the frame payload before padding. It returns the number of padding
+    /// octets, or `None` to send the frame unpadded.
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(StreamId, usize) -> Option<u8> + Send + Sync + 'static,
+    {
+        Padding {
+            inner: Inner::Custom(Arc::new(f)),
+        }
+    }
+
+    /// Returns the number of padding octets for a frame on `stream_id` with a
+    /// payload of `len` octets, or `None` if it must not be padded.
+    pub(crate) fn pad_len(&self, stream_id: frame::StreamId, len: usize) -> Option<u8> {
+        match self.inner {
+            Inner::None => None,
+            Inner::Fixed(pad_len) => Some(pad_len),
+            Inner::Block(size) => {
+                let size = size as usize;
+                Some(((size - (len + 1) % size) % size) as u8)
+            }
+            Inner::Random(max, ref random) => {
+                let mut hasher = random.state.build_hasher();
+                hasher.write_u64(random.count.fetch_add(1, Ordering::Relaxed));
+                hasher.write_usize(len);
+                Some((hasher.finish() % (max as u64 + 1)) as u8)
+            }
+            Inner::Custom(ref f) => f(StreamId::from_internal(stream_id), len),
+        }
+    }
+}
+
+impl Default for Padding {
+    fn default() -> Self {
+        Padding::none()
+    }
+}
+
+impl fmt::Debug for Padding {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.inner {
+            Inner::None => fmt.write_str("Padding::None"),
+            Inner::Fixed(len) => fmt.debug_tuple("Padding::Fixed").field(&len).finish(),
+            Inner::Block(size) => fmt.debug_tuple("Padding::Block").field(&size).finish(),
+            Inner::Random(max, _) => fmt.debug_tuple("Padding::Random").field(&max).finish(),
+            Inner::Custom(_) => fmt.write_str("Padding::Custom(..)"),
+        }
+    }
+}
@@ -1,6 +1,7 @@
-use crate::codec::{RecvError, UserError};
+use crate::codec::{RecvError, SendError, UserError};
 use crate::frame::{Reason, StreamId};
-use crate::{client, frame, proto, server};
+use crate::timer::Time;
+use crate::{client, ext, frame, proto, server, Padding};
 
 use crate::frame::DEFAULT_INITIAL_WINDOW_SIZE;
 use crate::proto::*;
@@ -10,8 +11,9 @@ use futures_core::Stream;
 use std::io;
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncRead, AsyncWrite};
 
 /// An H2 connection
@@ -36,11 +38,12 @@ where
     /// Tracks the connection level state transitions.
     state: State,
 
-    /// An error to report back once complete.
+    /// The GOAWAY frame received from the peer, holding the error to report
+    /// back once complete.
     ///
     /// This exists separately from State in order to support
     /// graceful shutdown.
-    error: Option<Reason>,
+    error: Option<frame::GoAway>,
 
     /// Pending GOAWAY frames to write.
     go_away: GoAway,
@@ -51,6 +54,9 @@ where
     /// Connection settings
     settings: Settings,
 
+    /// Timer used to wake up the connection when a deadline passes
+    time: Time,
+
     /// Stream state handler
     streams: Streams<B, P>,
 
@@ -68,7 +74,7 @@ struct DynConnection<'a, B: Buf = Bytes> {
 
     streams: DynStreams<'a, B>,
 
-    error: &'a mut Option<Reason>,
+    error: &'a mut Option<frame::GoAway>,
 
     ping_pong: &'a mut PingPong,
 }
@@ -80,6 +86,17 @@ pub(crate) struct Config {
     pub reset_stream_duration: Duration,
     pub reset_stream_max: usize,
     pub settings: frame::Settings,
+    pub padding: Padding,
+    pub expect_continue_timeout: Option<Duration>,
+    pub push_cache: Option<(usize, Duration)>,
+    pub push_policy: Option<client::PushPolicy>,
+    pub max_pending_pushed_streams: Option<usize>,
+    pub adaptive_window: Option<WindowSize>,
+    pub auto_release_capacity: bool,
+    pub max_send_buffer_size: usize,
+    pub max_connection_send_buffer_size: usize,
+    pub recv_memory_budget: Option<WindowSize>,
+    pub time: Time,
 }
 
 #[derive(Debug)]
@@ -117,6 +134,24 @@ where
                     .settings
                     .max_concurrent_streams()
                     .map(|max| max as usize),
+                extended_connect_protocol_enabled: config
+                    .settings
+                    .is_extended_connect_protocol_enabled()
+                    .unwrap_or(false),
+                rfc7540_priorities_disabled: config
+                    .settings
+                    .is_rfc7540_priorities_disabled()
+                    .unwrap_or(false),
+                padding: config.padding.clone(),
+                expect_continue_timeout: config.expect_continue_timeout,
+                push_cache: config.push_cache,
+                push_policy: config.push_policy.clone(),
+                max_pending_pushed_streams: config.max_pending_pushed_streams,
+                auto_release_capacity: config.auto_release_capacity,
+                max_send_buffer_size: config.max_send_buffer_size,
+                max_connection_send_buffer_size: config.max_connection_send_buffer_size,
+                recv_memory_budget: config.recv_memory_budget,
+                time: config.time.clone(),
             }
         }
         let streams = Streams::new(streams_config(&config));
@@ -126,8 +161,9 @@ where
                 state: State::Open,
                 error: None,
                 go_away: GoAway::new(),
-                ping_pong: PingPong::new(),
+                ping_pong: PingPong::new(config.adaptive_window),
                 settings: Settings::new(config.settings),
+                time: config.time,
                 streams,
                 span: tracing::debug_span!("Connection", peer = %P::NAME),
                 _phantom: PhantomData,
@@ -147,6 +183,43 @@ where
         self.inner.settings.send_settings(settings)
     }
 
+    /// Returns every setting received from the remote so far.
+    pub(crate) fn remote_settings(&self) -> Arc<frame::Settings> {
+        self.inner.streams.remote_settings()
+    }
+
+    /// Returns the remote settings once the initial SETTINGS exchange has
+    /// completed.
+    pub(crate) fn poll_remote_settings(
+        &mut self,
+        cx: &Context,
+    ) -> Poll<Result<Arc<frame::Settings>, proto::Error>> {
+        self.inner.streams.poll_remote_settings(cx)
+    }
+
+    /// Applies the settings sent in the `HTTP2-Settings` header of an upgrade
+    /// request, as if they had been received in a SETTINGS frame.
+    pub(crate) fn apply_upgrade_settings(
+        &mut self,
+        settings: &frame::Settings,
+    ) -> Result<(), RecvError> {
+        if let Some(val) = settings.header_table_size() {
+            self.codec.set_send_header_table_size(val as usize);
+        }
+
+        if let Some(val) = settings.max_frame_size() {
+            self.codec.set_max_send_frame_size(val as usize);
+        }
+
+        self.inner.streams.apply_remote_settings(settings)
+    }
+
+    /// Opens stream 1 with the request of an upgrade, as if its HEADERS frame
+    /// had been received.
+    pub(crate) fn recv_upgrade_request(&mut self, frame: frame::Headers) -> Result<(), RecvError> {
+        self.inner.streams.as_dyn().recv_headers(frame)
+    }
+
     /// Returns the maximum number of concurrent streams that may be initiated
     /// by this peer.
     pub(crate) fn max_send_streams(&self) -> usize {
@@ -159,6 +232,37 @@ where
         self.inner.streams.max_recv_streams()
     }
 
+    /// Returns the number of streams that are currently open.
+    pub(crate) fn num_open_streams(&self) -> usize {
+        self.inner.streams.num_open_streams()
+    }
+
+    /// Queue an extension frame to be sent on stream 0.
+    pub(crate) fn send_extension_frame(
+        &mut self,
+        frame: ext::ExtensionFrame,
+    ) -> Result<(), SendError> {
+        self.inner.streams.send_extension_frame(frame)
+    }
+
+    /// Queue `ORIGIN` frames to be sent for the given origins.
+    pub(crate) fn send_origin(&mut self, origins: Vec<Bytes>) -> Result<(), SendError> {
+        self.inner.streams.send_origin(origins)
+    }
+
+    /// Queue an `ALTSVC` frame to be sent on stream 0.
+    pub(crate) fn send_alt_svc(&mut self, frame: frame::AltSvc) -> Result<(), SendError> {
+        self.inner.streams.send_alt_svc(frame)
+    }
+
+    /// Poll for the next extension frame received on stream 0.
+    pub(crate) fn poll_extension_frame(
+        &mut self,
+        cx: &Context,
+    ) -> Poll<Result<ext::ExtensionFrame, proto::Error>> {
+        self.inner.streams.poll_extension_frame(cx)
+    }
+
     /// Returns `Ready` when the connection is ready to receive a frame.
     ///
     /// Returns `RecvError` as this may raise errors that are caused by delayed
@@ -187,30 +291,44 @@ where
         self.inner.go_away.send_pending_go_away(cx, &mut self.codec)
     }
 
-    pub fn go_away_from_user(&mut self, e: Reason) {
+    pub fn go_away_from_user(&mut self, e: Reason, debug_data: Bytes) {
+        let debug_data = self.truncate_debug_data(debug_data);
+        self.inner.go_away.set_debug_data(debug_data);
         self.inner.as_dyn().go_away_from_user(e)
     }
 
+    /// Truncates debug data so that GOAWAY frames fit in a frame.
+    fn truncate_debug_data(&self, mut debug_data: Bytes) -> Bytes {
+        let max_len = self.codec.max_send_frame_size() - 8;
+
+        if debug_data.len() > max_len {
+            tracing::debug!(len = debug_data.len(), "truncating GOAWAY debug data");
+            debug_data.truncate(max_len);
+        }
+
+        debug_data
+    }
+
+    /// Returns the last GOAWAY frame received from the remote, if any.
+    pub(crate) fn received_go_away(&self) -> Option<&frame::GoAway> {
+        self.inner.error.as_ref()
+    }
+
     fn take_error(&mut self, ours: Reason) -> Poll<Result<(), proto::Error>> {
-        let reason = if let Some(theirs) = self.inner.error.take() {
-            match (ours, theirs) {
-                // If either side reported an error, return that
-                // to the user.
-                (Reason::NO_ERROR, err) | (err, Reason::NO_ERROR) => err,
-                // If both sides reported an error, give their
-                // error back to th user. We assume our error
-                // was a consequence of their error, and less
-                // important.
-                (_, theirs) => theirs,
+        match self.inner.error {
+            // If the peer reported an error, give it back to the user along
+            // with the debug data and last stream ID of their GOAWAY. If both
+            // sides reported an error, we assume our error was a consequence
+            // of their error, and less important.
+            Some(ref theirs) if theirs.reason() != Reason::NO_ERROR => {
+                Poll::Ready(Err(proto::Error::GoAway(
+                    theirs.clone_debug_data(),
+                    theirs.reason(),
+                    theirs.last_stream_id(),
+                )))
             }
-        } else {
-            ours
-        };
-
-        if reason == Reason::NO_ERROR {
-            Poll::Ready(Ok(()))
-        } else {
-            Poll::Ready(Err(proto::Error::Proto(reason)))
+            _ if ours == Reason::NO_ERROR => Poll::Ready(Ok(())),
+            _ => Poll::Ready(Err(proto::Error::Proto(ours))),
         }
     }
 
@@ -286,6 +404,9 @@ where
         // check and then comparison of the queue possibly multiple times a
         // second (and thus, the clock wouldn't have changed enough to matter).
         self.clear_expired_reset_streams();
+        self.inner.streams.poll_expired_pushes(cx);
+        self.inner.streams.poll_expired_continues(cx);
+        self.abort_if_shutdown_deadline_passed(cx);
 
         loop {
             // First, ensure that the `Connection` is able to receive a frame
@@ -335,6 +456,17 @@ where
     fn clear_expired_reset_streams(&mut self) {
         self.inner.streams.clear_expired_reset_streams();
     }
+
+    fn abort_if_shutdown_deadline_passed(&mut self, cx: &mut Context) {
+        if let Some(reason) = self.inner.go_away.poll_expired_deadline(cx) {
+            tracing::debug!(
+                ?reason,
+                streams = self.inner.streams.num_open_streams(),
+                "graceful shutdown deadline passed; closing remaining streams"
+            );
+            self.inner.as_dyn().go_away_from_user(reason);
+        }
+    }
 }
 
 impl<P, B> ConnectionInner<P, B>
@@ -367,20 +499,20 @@ where
     B: Buf,
 {
     fn go_away(&mut self, id: StreamId, e: Reason) {
-        let frame = frame::GoAway::new(id, e);
+        let frame = self.go_away.frame(id, e);
         self.streams.send_go_away(id);
         self.go_away.go_away(frame);
     }
 
     fn go_away_now(&mut self, e: Reason) {
         let last_processed_id = self.streams.last_processed_id();
-        let frame = frame::GoAway::new(last_processed_id, e);
+        let frame = self.go_away.frame(last_processed_id, e);
         self.go_away.go_away_now(frame);
     }
 
     fn go_away_from_user(&mut self, e: Reason) {
         let last_processed_id = self.streams.last_processed_id();
-        let frame = frame::GoAway::new(last_processed_id, e);
+        let frame = self.go_away.frame(last_processed_id, e);
         self.go_away.go_away_from_user(frame);
 
         // Notify all streams of reason we're abruptly closing.
@@ -450,6 +582,7 @@ where
             }
             Some(Data(frame)) => {
                 tracing::trace!(?frame, "recv DATA");
+                self.ping_pong.recv_data(frame.flow_controlled_len());
                 self.streams.recv_data(frame)?;
             }
             Some(Reset(frame)) => {
@@ -471,7 +604,7 @@ where
                 // until they are all EOS. Once they are, State should
                 // transition to GoAway.
                 self.streams.recv_go_away(&frame)?;
-                *self.error = Some(frame.reason());
+                *self.error = Some(frame);
             }
             Some(Ping(frame)) => {
                 tracing::trace!(?frame, "recv PING");
@@ -484,6 +617,8 @@ where
 
                     let last_processed_id = self.streams.last_processed_id();
                     self.go_away(last_processed_id, Reason::NO_ERROR);
+                } else if let ReceivedPing::GrowWindow(size) = status {
+                    self.streams.grow_recv_windows(size);
                 }
             }
             Some(WindowUpdate(frame)) => {
@@ -492,7 +627,23 @@ where
             }
             Some(Priority(frame)) => {
                 tracing::trace!(?frame, "recv PRIORITY");
-                // TODO: handle
+                self.streams.recv_priority(frame)?;
+            }
+            Some(PriorityUpdate(frame)) => {
+                tracing::trace!(?frame, "recv PRIORITY_UPDATE");
+                self.streams.recv_priority_update(frame)?;
+            }
+            Some(Origin(frame)) => {
+                tracing::trace!(?frame, "recv ORIGIN");
+                self.streams.recv_origin(frame);
+            }
+            Some(AltSvc(frame)) => {
+                tracing::trace!(?frame, "recv ALTSVC");
+                self.streams.recv_alt_svc(frame);
+            }
+            Some(Unknown(frame)) => {
+                tracing::trace!(?frame, "recv unknown frame");
+                self.streams.recv_unknown(frame);
             }
             None => {
                 tracing::trace!("codec closed");
@@ -518,6 +669,21 @@ where
     pub(crate) fn streams(&self) -> &Streams<B, client::Peer> {
         &self.inner.streams
     }
+
+    pub fn go_away_gracefully(&mut self) {
+        if self.inner.go_away.is_going_away() {
+            // No reason to start a new one.
+            return;
+        }
+
+        // Unlike a server, a client doesn't need to wait for a round trip
+        // before picking the last stream ID. The server can only initiate
+        // streams by pushing them on streams the client opened, so the last
+        // pushed stream we accepted is final as soon as GOAWAY is sent.
+        let mut conn = self.inner.as_dyn();
+        let last_processed_id = conn.streams.last_processed_id();
+        conn.go_away(last_processed_id, Reason::NO_ERROR);
+    }
 }
 
 impl<T, B> Connection<T, server::Peer, B>
@@ -529,13 +695,15 @@ where
         self.inner.streams.next_incoming()
     }
 
-    // Graceful shutdown only makes sense for server peers.
-    pub fn go_away_gracefully(&mut self) {
+    pub fn go_away_gracefully(&mut self, debug_data: Bytes) {
         if self.inner.go_away.is_going_away() {
             // No reason to start a new one.
             return;
         }
 
+        let debug_data = self.truncate_debug_data(debug_data);
+        self.inner.go_away.set_debug_data(debug_data);
+
         // According to http://httpwg.org/specs/rfc7540.html#GOAWAY:
         //
         // > A server that is attempting to gracefully shut down a connection
@@ -553,6 +721,13 @@ where
         // for a pong before proceeding.
         self.inner.ping_pong.ping_shutdown();
     }
+
+    /// Aborts a graceful shutdown with `reason` if it hasn't completed by
+    /// `deadline`.
+    pub fn set_shutdown_deadline(&mut self, deadline: Instant, reason: Reason) {
+        let deadline = self.inner.time.deadline(deadline);
+        self.inner.go_away.set_deadline(deadline, reason);
+    }
 }
 
 impl<T, P, B> Drop for Connection<T, P, B>
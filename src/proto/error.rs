@@ -1,12 +1,16 @@
 use crate::codec::{RecvError, SendError};
-use crate::frame::Reason;
+use crate::frame::{Reason, StreamId};
 
+use bytes::Bytes;
 use std::io;
 
 /// Either an H2 reason  or an I/O error
 #[derive(Debug)]
 pub enum Error {
     Proto(Reason),
+    /// The error reason, debug data and last stream ID of a GOAWAY frame
+    /// received from the peer.
+    GoAway(Bytes, Reason, StreamId),
     Io(io::Error),
 }
 
@@ -17,6 +21,9 @@ impl Error {
     pub(super) fn shallow_clone(&self) -> Error {
         match *self {
             Error::Proto(reason) => Error::Proto(reason),
+            Error::GoAway(ref debug_data, reason, last_stream_id) => {
+                Error::GoAway(debug_data.clone(), reason, last_stream_id)
+            }
             Error::Io(ref io) => Error::Io(io::Error::from(io.kind())),
         }
     }
@@ -37,7 +44,7 @@ impl From<io::Error> for Error {
 impl From<Error> for RecvError {
     fn from(src: Error) -> RecvError {
         match src {
-            Error::Proto(reason) => RecvError::Connection(reason),
+            Error::Proto(reason) | Error::GoAway(_, reason, _) => RecvError::Connection(reason),
             Error::Io(e) => RecvError::Io(e),
         }
     }
@@ -46,7 +53,7 @@ impl From<Error> for RecvError {
 impl From<Error> for SendError {
     fn from(src: Error) -> SendError {
         match src {
-            Error::Proto(reason) => SendError::Connection(reason),
+            Error::Proto(reason) | Error::GoAway(_, reason, _) => SendError::Connection(reason),
             Error::Io(e) => SendError::Io(e),
         }
     }
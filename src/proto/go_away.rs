@@ -1,7 +1,8 @@
 use crate::codec::Codec;
 use crate::frame::{self, Reason, StreamId};
+use crate::timer::Deadline;
 
-use bytes::Buf;
+use bytes::{Buf, Bytes};
 use std::io;
 use std::task::{Context, Poll};
 use tokio::io::AsyncWrite;
@@ -17,6 +18,11 @@ pub(super) struct GoAway {
     is_user_initiated: bool,
     /// A GOAWAY frame that must be buffered in the Codec immediately.
     pending: Option<frame::GoAway>,
+    /// Debug data given by the user, sent with every GOAWAY frame.
+    debug_data: Bytes,
+    /// Fires when a graceful shutdown must be cut short, with the reason to
+    /// close the remaining streams with.
+    deadline: Option<(Deadline, Reason)>,
 }
 
 /// Keeps a memory of any GOAWAY frames we've sent before.
@@ -48,9 +54,51 @@ impl GoAway {
             going_away: None,
             is_user_initiated: false,
             pending: None,
+            debug_data: Bytes::new(),
+            deadline: None,
         }
     }
 
+    /// Sets the debug data to send with the GOAWAY frames.
+    pub fn set_debug_data(&mut self, debug_data: Bytes) {
+        self.debug_data = debug_data;
+    }
+
+    /// Sets a deadline for a graceful shutdown to complete.
+    pub fn set_deadline(&mut self, deadline: Deadline, reason: Reason) {
+        self.deadline = Some((deadline, reason));
+    }
+
+    /// Returns the reason to abort the connection with if the graceful
+    /// shutdown deadline has passed, or registers the task to be woken up
+    /// when it does.
+    ///
+    /// This only returns a reason once.
+    pub fn poll_expired_deadline(&mut self, cx: &mut Context) -> Option<Reason> {
+        if self.close_now {
+            // Already closing, nothing left to abort.
+            self.deadline = None;
+            return None;
+        }
+
+        let (deadline, reason) = self.deadline.as_mut()?;
+        let reason = *reason;
+
+        if deadline.poll(cx).is_pending() {
+            return None;
+        }
+
+        self.deadline = None;
+        Some(reason)
+    }
+
+    /// Returns a GOAWAY frame with the debug data given by the user, if any.
+    pub fn frame(&self, last_stream_id: StreamId, reason: Reason) -> frame::GoAway {
+        let mut frame = frame::GoAway::new(last_stream_id, reason);
+        frame.set_debug_data(self.debug_data.clone());
+        frame
+    }
+
     /// Enqueue a GOAWAY frame to be written.
     ///
     /// The connection is expected to continue to run until idle.
@@ -5,6 +5,7 @@ mod peer;
 mod ping_pong;
 mod settings;
 mod streams;
+pub(crate) mod upgrade;
 
 pub(crate) use self::connection::{Config, Connection};
 pub(crate) use self::error::Error;
@@ -16,7 +17,7 @@ pub(crate) use self::streams::{Open, PollReset, Prioritized};
 use crate::codec::Codec;
 
 use self::go_away::GoAway;
-use self::ping_pong::PingPong;
+use self::ping_pong::{PingPong, ReceivedPing};
 use self::settings::Settings;
 
 use crate::frame::{self, Frame};
@@ -1,6 +1,6 @@
 use crate::codec::Codec;
 use crate::frame::Ping;
-use crate::proto::{self, PingPayload};
+use crate::proto::{self, PingPayload, WindowSize};
 
 use bytes::Buf;
 use futures_util::task::AtomicWaker;
@@ -8,6 +8,7 @@ use std::io;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Instant;
 use tokio::io::AsyncWrite;
 
 /// Acknowledges ping requests from the remote.
@@ -16,6 +17,7 @@ pub(crate) struct PingPong {
     pending_ping: Option<PendingPing>,
     pending_pong: Option<PingPayload>,
     user_pings: Option<UserPingsRx>,
+    bdp: Option<Bdp>,
 }
 
 #[derive(Debug)]
@@ -39,12 +41,51 @@ struct PendingPing {
     sent: bool,
 }
 
+/// Estimates the bandwidth-delay product of the connection, to size the
+/// receive windows.
+///
+/// A PING is sent along with the first DATA frame received while no
+/// estimate is in progress. The data received until the PING is acknowledged
+/// approximates the data in flight over one round trip. When that comes
+/// close to the current window while the bandwidth is at its highest yet,
+/// the window is doubled, up to `max_window`.
+#[derive(Debug)]
+struct Bdp {
+    /// The current window size
+    window: WindowSize,
+
+    /// The largest window size to grow to
+    max_window: WindowSize,
+
+    /// Bytes received since the PING was sent
+    bytes: usize,
+
+    /// The highest bandwidth seen, in bytes per second
+    max_bandwidth: f64,
+
+    ping: BdpPing,
+}
+
+#[derive(Debug)]
+enum BdpPing {
+    /// No estimate is in progress
+    Idle,
+
+    /// A PING must be sent
+    Pending,
+
+    /// A PING was sent at this instant
+    Sent(Instant),
+}
+
 /// Status returned from `PingPong::recv_ping`.
 #[derive(Debug)]
 pub(crate) enum ReceivedPing {
     MustAck,
     Unknown,
     Shutdown,
+    /// The estimated bandwidth-delay product grew to this window size.
+    GrowWindow(WindowSize),
 }
 
 /// No user ping pending.
@@ -61,11 +102,14 @@ const USER_STATE_CLOSED: usize = 4;
 // ===== impl PingPong =====
 
 impl PingPong {
-    pub(crate) fn new() -> Self {
+    /// `max_window` enables the bandwidth-delay product estimation, growing
+    /// the windows up to that size.
+    pub(crate) fn new(max_window: Option<WindowSize>) -> Self {
         PingPong {
             pending_ping: None,
             pending_pong: None,
             user_pings: None,
+            bdp: max_window.map(Bdp::new),
         }
     }
 
@@ -93,6 +137,13 @@ impl PingPong {
         });
     }
 
+    /// Records the flow controlled size of a received DATA frame.
+    pub(crate) fn recv_data(&mut self, len: usize) {
+        if let Some(ref mut bdp) = self.bdp {
+            bdp.recv_data(len);
+        }
+    }
+
     /// Process a ping
     pub(crate) fn recv_ping(&mut self, ping: Ping) -> ReceivedPing {
         // The caller should always check that `send_pongs` returns ready before
@@ -115,6 +166,16 @@ impl PingPong {
                 self.pending_ping = Some(pending);
             }
 
+            if let Some(ref mut bdp) = self.bdp {
+                if ping.payload() == &Ping::BDP {
+                    tracing::trace!("recv PING BDP ack");
+                    return match bdp.recv_pong(Instant::now()) {
+                        Some(window) => ReceivedPing::GrowWindow(window),
+                        None => ReceivedPing::Unknown,
+                    };
+                }
+            }
+
             if let Some(ref users) = self.user_pings {
                 if ping.payload() == &Ping::USER && users.receive_pong() {
                     tracing::trace!("recv PING USER ack");
@@ -194,6 +255,18 @@ impl PingPong {
             }
         }
 
+        if let Some(ref mut bdp) = self.bdp {
+            if let BdpPing::Pending = bdp.ping {
+                if !dst.poll_ready(cx)?.is_ready() {
+                    return Poll::Pending;
+                }
+
+                dst.buffer(Ping::new(Ping::BDP).into())
+                    .expect("invalid ping frame");
+                bdp.ping = BdpPing::Sent(Instant::now());
+            }
+        }
+
         Poll::Ready(Ok(()))
     }
 }
@@ -207,6 +280,72 @@ impl ReceivedPing {
     }
 }
 
+// ===== impl Bdp =====
+
+impl Bdp {
+    fn new(max_window: WindowSize) -> Self {
+        Bdp {
+            window: crate::frame::DEFAULT_INITIAL_WINDOW_SIZE,
+            max_window,
+            bytes: 0,
+            max_bandwidth: 0.0,
+            ping: BdpPing::Idle,
+        }
+    }
+
+    fn recv_data(&mut self, len: usize) {
+        match self.ping {
+            BdpPing::Idle => {
+                if self.window < self.max_window {
+                    self.ping = BdpPing::Pending;
+                    self.bytes = len;
+                }
+            }
+            BdpPing::Pending | BdpPing::Sent(_) => self.bytes += len,
+        }
+    }
+
+    /// Completes the estimate, returning the new window size if it grew.
+    fn recv_pong(&mut self, now: Instant) -> Option<WindowSize> {
+        let sent_at = match self.ping {
+            BdpPing::Sent(sent_at) => sent_at,
+            _ => return None,
+        };
+
+        self.ping = BdpPing::Idle;
+
+        let rtt = now.duration_since(sent_at).as_secs_f64();
+        let bandwidth = self.bytes as f64 / rtt.max(0.001);
+        tracing::trace!(
+            bytes = self.bytes,
+            rtt,
+            bandwidth,
+            window = self.window,
+            "BDP estimate"
+        );
+
+        // A lower bandwidth means the round trip grew for another reason,
+        // like queuing, rather than the window being too small.
+        if bandwidth < self.max_bandwidth {
+            return None;
+        }
+        self.max_bandwidth = bandwidth;
+
+        if self.bytes < self.window as usize * 2 / 3 {
+            return None;
+        }
+
+        let window = std::cmp::min(self.bytes * 2, self.max_window as usize) as WindowSize;
+        if window <= self.window {
+            return None;
+        }
+
+        tracing::debug!(window, "growing windows from BDP estimate");
+        self.window = window;
+        Some(window)
+    }
+}
+
 // ===== impl UserPings =====
 
 impl UserPings {
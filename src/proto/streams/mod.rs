@@ -2,6 +2,8 @@ mod buffer;
 mod counts;
 mod flow_control;
 mod prioritize;
+mod priority;
+mod push_cache;
 mod recv;
 mod send;
 mod state;
@@ -18,6 +20,7 @@ use self::buffer::Buffer;
 use self::counts::Counts;
 use self::flow_control::FlowControl;
 use self::prioritize::Prioritize;
+use self::push_cache::PushCache;
 use self::recv::Recv;
 use self::send::Send;
 use self::state::State;
@@ -26,6 +29,8 @@ use self::stream::Stream;
 
 use crate::frame::{StreamId, StreamIdOverflow};
 use crate::proto::*;
+use crate::timer::Time;
+use crate::Padding;
 
 use bytes::Bytes;
 use std::time::Duration;
@@ -58,4 +63,44 @@ pub struct Config {
 
     /// Maximum number of remote initiated streams
     pub remote_max_initiated: Option<usize>,
+
+    /// If the extended connect protocol is enabled locally
+    pub extended_connect_protocol_enabled: bool,
+
+    /// If the local peer ignores RFC 7540 priority signals
+    pub rfc7540_priorities_disabled: bool,
+
+    /// Padding policy for DATA frames
+    pub padding: Padding,
+
+    /// How long to hold back the body of a request sent with
+    /// `Expect: 100-continue`, if at all
+    pub expect_continue_timeout: Option<Duration>,
+
+    /// Maximum number of pushed streams to cache and how long to keep them,
+    /// if pushed streams are cached
+    pub push_cache: Option<(usize, Duration)>,
+
+    /// Decides which pushed streams are accepted, if set
+    pub push_policy: Option<crate::client::PushPolicy>,
+
+    /// Maximum number of pushed streams not yet claimed by the application
+    pub max_pending_pushed_streams: Option<usize>,
+
+    /// Whether capacity is released as soon as data is handed to the
+    /// application
+    pub auto_release_capacity: bool,
+
+    /// Maximum amount of data buffered on a stream, waiting to be sent
+    pub max_send_buffer_size: usize,
+
+    /// Maximum amount of data buffered on the connection beyond the capacity
+    /// assigned to its streams
+    pub max_connection_send_buffer_size: usize,
+
+    /// Maximum amount of received data held by the connection, if limited
+    pub recv_memory_budget: Option<WindowSize>,
+
+    /// Timer used to wake up the connection when a deadline passes
+    pub time: Time,
 }
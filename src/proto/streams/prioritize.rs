@@ -1,3 +1,4 @@
+use super::priority::PriorityTree;
 use super::store::Resolve;
 use super::*;
 
@@ -6,11 +7,21 @@ use crate::frame::{Reason, StreamId};
 use crate::codec::UserError;
 use crate::codec::UserError::*;
 
+use crate::ext;
+use crate::timer::{Deadline, Time};
+
 use bytes::buf::{Buf, Take};
+use indexmap::IndexMap;
+use std::collections::VecDeque;
 use std::io;
 use std::task::{Context, Poll, Waker};
+use std::time::Instant;
 use std::{cmp, fmt, mem};
 
+/// Maximum number of `PRIORITY_UPDATE` frames buffered for streams that are
+/// not open yet. Once reached, the oldest one is dropped.
+const MAX_PENDING_PRIORITY_UPDATES: usize = 64;
+
 /// # Warning
 ///
 /// Queued streams are ordered by stream ID, as we need to ensure that
@@ -25,6 +36,10 @@ pub(super) struct Prioritize {
     /// Queue of streams waiting for socket capacity to send a frame.
     pending_send: store::Queue<stream::NextSend>,
 
+    /// Streams taken from `pending_send` with a frame other than DATA to
+    /// send, once DATA is scheduled by priority.
+    pending_control: store::Queue<stream::NextSend>,
+
     /// Queue of streams waiting for window capacity to produce data.
     pending_capacity: store::Queue<stream::NextSendCapacity>,
 
@@ -43,6 +58,16 @@ pub(super) struct Prioritize {
     /// futures library.
     pending_open: store::Queue<stream::NextOpen>,
 
+    /// Streams whose body is held back for `Expect: 100-continue`, in the
+    /// order of their deadlines.
+    pending_continue: IndexMap<StreamId, Instant>,
+
+    /// Fires at the deadline of the first stream in `pending_continue`.
+    continue_timer: Option<Deadline>,
+
+    /// Timer used to wake up the connection at `continue_timer`
+    time: Time,
+
     /// Connection level flow control governing sent data
     flow: FlowControl,
 
@@ -51,6 +76,45 @@ pub(super) struct Prioritize {
 
     /// What `DATA` frame is currently being sent in the codec.
     in_flight_data_frame: InFlightData,
+
+    /// Stream dependencies signaled by the remote, used to pick which stream
+    /// sends DATA next.
+    tree: PriorityTree,
+
+    /// Streams taken from `pending_send` with DATA to send, once DATA is
+    /// scheduled by priority. They are marked as ready in `tree` unless
+    /// extensible priorities are in use.
+    ready: IndexMap<StreamId, store::Key>,
+
+    /// Set when RFC 7540 priority signals are ignored, either because the
+    /// local or the remote peer sent `SETTINGS_NO_RFC7540_PRIORITIES`.
+    is_rfc7540_disabled: bool,
+
+    /// Set once any stream had its extensible priority (RFC 9218) signaled.
+    /// From then on, DATA is scheduled by urgency, and the dependency tree
+    /// is not used anymore.
+    is_extensible: bool,
+
+    /// `PRIORITY_UPDATE` frames received for streams that are not open yet.
+    pending_priority_updates: IndexMap<StreamId, ext::Priority>,
+
+    /// Extension frames, such as `ALTSVC`, `ORIGIN` or frames of unknown
+    /// types, to send on stream 0, and `PRIORITY` frames, which are sent
+    /// regardless of the state of their stream.
+    pending_extension: VecDeque<Frame>,
+
+    /// Padding policy for DATA frames.
+    padding: Padding,
+
+    /// Maximum amount of data buffered on a stream, waiting to be sent.
+    max_buffer_size: usize,
+
+    /// Maximum amount of data buffered on the whole connection beyond the
+    /// capacity assigned to its streams.
+    max_connection_buffer_size: usize,
+
+    /// Amount of data buffered on all streams, waiting to be sent.
+    buffered_send_data: usize,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -88,11 +152,83 @@ impl Prioritize {
 
         Prioritize {
             pending_send: store::Queue::new(),
+            pending_control: store::Queue::new(),
             pending_capacity: store::Queue::new(),
             pending_open: store::Queue::new(),
+            pending_continue: IndexMap::new(),
+            continue_timer: None,
+            time: config.time.clone(),
             flow,
             last_opened_id: StreamId::ZERO,
             in_flight_data_frame: InFlightData::Nothing,
+            tree: PriorityTree::new(),
+            ready: IndexMap::new(),
+            is_rfc7540_disabled: config.rfc7540_priorities_disabled,
+            is_extensible: false,
+            pending_priority_updates: IndexMap::new(),
+            pending_extension: VecDeque::new(),
+            padding: config.padding.clone(),
+            max_buffer_size: config.max_send_buffer_size,
+            max_connection_buffer_size: config.max_connection_send_buffer_size,
+            buffered_send_data: 0,
+        }
+    }
+
+    pub fn disable_rfc7540_priorities(&mut self) {
+        self.is_rfc7540_disabled = true;
+    }
+
+    /// Apply a stream dependency received in a PRIORITY or HEADERS frame.
+    pub fn recv_priority(
+        &mut self,
+        id: StreamId,
+        dependency: &frame::StreamDependency,
+        store: &Store,
+    ) {
+        tracing::trace!(?id, ?dependency, "recv_priority");
+
+        if self.is_rfc7540_disabled {
+            return;
+        }
+
+        self.tree.reprioritize(id, dependency);
+        self.tree.prune(|id| store.contains_id(&id));
+    }
+
+    /// Set the extensible priority of a stream.
+    pub fn set_priority(&mut self, stream: &mut Stream, priority: ext::Priority) {
+        tracing::trace!(?stream.id, ?priority, "set_priority");
+
+        stream.priority = priority;
+        self.is_extensible = true;
+    }
+
+    /// Buffer a priority received for a stream that is not open yet, so that
+    /// it is applied once the stream opens.
+    pub fn buffer_priority_update(&mut self, id: StreamId, priority: ext::Priority) {
+        self.pending_priority_updates.insert(id, priority);
+
+        if self.pending_priority_updates.len() > MAX_PENDING_PRIORITY_UPDATES {
+            self.pending_priority_updates.shift_remove_index(0);
+        }
+    }
+
+    /// Returns the maximum amount of data buffered on a stream.
+    pub fn max_buffer_size(&self) -> usize {
+        self.max_buffer_size
+    }
+
+    /// Take the priority buffered for the stream, if any.
+    pub fn take_priority_update(&mut self, id: StreamId) -> Option<ext::Priority> {
+        self.pending_priority_updates.shift_remove(&id)
+    }
+
+    /// Queue a frame to be sent ahead of the frames queued on streams.
+    pub fn queue_extension_frame(&mut self, frame: Frame, task: &mut Option<Waker>) {
+        self.pending_extension.push_back(frame);
+
+        if let Some(task) = task.take() {
+            task.wake();
         }
     }
 
@@ -157,8 +293,39 @@ impl Prioritize {
             }
         }
 
-        // Update the buffered data counter
+        // A chunk larger than the limit is accepted when nothing is buffered,
+        // otherwise it could never be sent.
+        if stream.buffered_send_data > 0
+            && stream.buffered_send_data as usize + sz as usize > self.max_buffer_size
+        {
+            tracing::debug!(
+                ?stream.id,
+                sz,
+                buffered = stream.buffered_send_data,
+                "send_data; send buffer full"
+            );
+            return Err(UserError::SendBufferFull);
+        }
+
+        // Data within the capacity assigned to the stream is bounded by the
+        // flow control windows, so only data beyond it counts against the
+        // connection wide limit.
+        if self.buffered_send_data > 0
+            && sz > stream.capacity(self.max_buffer_size)
+            && self.buffered_send_data + sz as usize > self.max_connection_buffer_size
+        {
+            tracing::debug!(
+                ?stream.id,
+                sz,
+                buffered = self.buffered_send_data,
+                "send_data; connection send buffer full"
+            );
+            return Err(UserError::SendBufferFull);
+        }
+
+        // Update the buffered data counters
         stream.buffered_send_data += sz;
+        self.buffered_send_data += sz as usize;
 
         let span =
             tracing::trace_span!("send_data", sz, requested = stream.requested_send_capacity);
@@ -371,8 +538,93 @@ impl Prioritize {
         }
     }
 
+    /// Holds back the body of a request sent with `Expect: 100-continue`
+    /// until `deadline`, unless the server responds first.
+    pub fn hold_continue(&mut self, deadline: Instant, stream: &mut store::Ptr) {
+        // All streams are held back for the same duration, so the queue stays
+        // ordered by deadline.
+        stream.continue_deadline = Some(deadline);
+        self.pending_continue.insert(stream.id, deadline);
+    }
+
+    /// Stops holding back the body of a request sent with
+    /// `Expect: 100-continue`, assigning it the capacity it requested.
+    pub fn release_continue(&mut self, stream: &mut store::Ptr, task: &mut Option<Waker>) {
+        if stream.continue_deadline.take().is_none() {
+            return;
+        }
+
+        self.pending_continue.shift_remove(&stream.id);
+
+        tracing::trace!(?stream.id, "releasing request body");
+        self.try_assign_capacity(stream);
+
+        // Buffered data may be ready to be sent now.
+        if let Some(task) = task.take() {
+            task.wake();
+        }
+    }
+
+    /// Releases the bodies held back for `Expect: 100-continue` whose deadline
+    /// has passed, and registers `cx` to be woken at the next deadline.
+    pub fn poll_expired_continues(
+        &mut self,
+        cx: &mut Context,
+        store: &mut Store,
+        task: &mut Option<Waker>,
+    ) {
+        while let Some((&id, &deadline)) = self.pending_continue.get_index(0) {
+            // Only start a new timer when the first deadline changes.
+            if matches!(self.continue_timer, Some(ref timer) if timer.at() != deadline) {
+                self.continue_timer = None;
+            }
+
+            let time = &self.time;
+            let timer = self
+                .continue_timer
+                .get_or_insert_with(|| time.deadline(deadline));
+
+            if timer.poll(cx).is_pending() {
+                return;
+            }
+
+            self.continue_timer = None;
+
+            match store.find_mut(&id) {
+                Some(mut stream) => {
+                    tracing::debug!(
+                        ?stream.id,
+                        "no response to `Expect: 100-continue` in time"
+                    );
+                    self.release_continue(&mut stream, task);
+                }
+                None => {
+                    self.pending_continue.shift_remove_index(0);
+                }
+            }
+        }
+
+        self.continue_timer = None;
+    }
+
+    pub fn clear_pending_continue(&mut self, store: &mut Store) {
+        for (id, _) in self.pending_continue.drain(..) {
+            if let Some(mut stream) = store.find_mut(&id) {
+                stream.continue_deadline = None;
+            }
+        }
+        self.continue_timer = None;
+    }
+
     /// Request capacity to send data
     fn try_assign_capacity(&mut self, stream: &mut store::Ptr) {
+        if stream.continue_deadline.is_some() {
+            // The body is held back until the server responds to
+            // `Expect: 100-continue`.
+            tracing::trace!(?stream.id, "try_assign_capacity; awaiting 100-continue");
+            return;
+        }
+
         let total_requested = stream.requested_send_capacity;
 
         // Total requested should never go below actual assigned
@@ -422,7 +674,7 @@ impl Prioritize {
             tracing::trace!(capacity = assign, "assigning");
 
             // Assign the capacity to the stream
-            stream.assign_capacity(assign);
+            stream.assign_capacity(assign, self.max_buffer_size);
 
             // Claim the capacity from the connection
             self.flow.claim_capacity(assign);
@@ -490,6 +742,23 @@ impl Prioritize {
         tracing::trace!("poll_complete");
 
         loop {
+            // Connection level extension frames and PRIORITY frames go first
+            if let Some(frame) = self.pending_extension.pop_front() {
+                if is_oversized_extension(&frame, max_frame_len) {
+                    tracing::debug!(?frame, "extension frame too big; dropping");
+                    continue;
+                }
+
+                tracing::trace!(?frame, "writing");
+                let frame = frame.map(|_| {
+                    unreachable!("DATA frames are not queued ahead of streams");
+                });
+                dst.buffer(frame).expect("invalid frame");
+
+                ready!(dst.poll_ready(cx))?;
+                continue;
+            }
+
             self.schedule_pending_open(store, counts);
 
             match self.pop_frame(buffer, store, max_frame_len, counts) {
@@ -628,8 +897,22 @@ impl Prioritize {
             tracing::trace!(?frame, "dropping");
         }
 
+        self.buffered_send_data -= stream.buffered_send_data as usize;
         stream.buffered_send_data = 0;
         stream.requested_send_capacity = 0;
+
+        // Let `pop_frame` release the stream, as if it was still queued.
+        if self.take_ready(stream.id).is_some() {
+            stream.is_pending_send = false;
+            self.pending_control.push(stream);
+        }
+
+        self.tree.remove(stream.id);
+
+        // The body will never be sent, so stop holding it back.
+        if stream.continue_deadline.take().is_some() {
+            self.pending_continue.shift_remove(&stream.id);
+        }
         if let InFlightData::DataFrame(key) = self.in_flight_data_frame {
             if stream.key() == key {
                 // This stream could get cleaned up now - don't allow the buffered frame to get reclaimed.
@@ -643,6 +926,21 @@ impl Prioritize {
             let is_pending_reset = stream.is_pending_reset_expiration();
             counts.transition_after(stream, is_pending_reset);
         }
+
+        while let Some(stream) = self.pending_control.pop(store) {
+            let is_pending_reset = stream.is_pending_reset_expiration();
+            counts.transition_after(stream, is_pending_reset);
+        }
+
+        for (id, key) in self.ready.drain(..) {
+            self.tree.unset_ready(id);
+
+            let mut stream = store.resolve(key);
+            stream.is_pending_send = false;
+
+            let is_pending_reset = stream.is_pending_reset_expiration();
+            counts.transition_after(stream, is_pending_reset);
+        }
     }
 
     pub fn clear_pending_open(&mut self, store: &mut Store, counts: &mut Counts) {
@@ -666,7 +964,7 @@ impl Prioritize {
         let _e = span.enter();
 
         loop {
-            match self.pending_send.pop(store) {
+            match self.pop_pending_send(buffer, store) {
                 Some(mut stream) => {
                     let span = tracing::trace_span!("popped", ?stream.id, ?stream.state);
                     let _e = span.enter();
@@ -717,37 +1015,59 @@ impl Prioritize {
                                 continue;
                             }
 
-                            // Only send up to the max frame length
+                            // Only send up to the max frame length and the
+                            // stream's window capacity
                             let len = cmp::min(sz, max_len);
+                            let len = cmp::min(len, stream_capacity.as_size() as usize);
+
+                            // Padding is counted against flow control too. It
+                            // only uses window that is assigned to neither the
+                            // stream nor any other stream, so that the
+                            // capacity reserved by the user is left intact.
+                            let unassigned = cmp::min(
+                                stream
+                                    .send_flow
+                                    .window_size()
+                                    .saturating_sub(stream_capacity.as_size()),
+                                self.flow.available().as_size(),
+                            );
+                            let limit = cmp::min(max_len, len + unassigned as usize);
 
-                            // Only send up to the stream's window capacity
-                            let len =
-                                cmp::min(len, stream_capacity.as_size() as usize) as WindowSize;
+                            let pad_len = self.pad_data(stream.id, len, limit);
+                            frame.set_padding(pad_len);
+
+                            let padding = pad_len.map_or(0, |pad_len| pad_len as WindowSize + 1);
+
+                            if padding > 0 {
+                                self.flow.claim_capacity(padding);
+                                stream.send_flow.assign_capacity(padding);
+                            }
+
+                            let len = len as WindowSize;
+                            let flow_len = len + padding;
 
                             // There *must* be be enough connection level
                             // capacity at this point.
-                            debug_assert!(len <= self.flow.window_size());
+                            debug_assert!(flow_len <= self.flow.window_size());
+
+                            tracing::trace!(len, ?pad_len, "sending data frame");
 
-                            tracing::trace!(len, "sending data frame");
+                            self.tree.sent(stream.id, flow_len as usize);
 
                             // Update the flow control
                             tracing::trace_span!("updating stream flow").in_scope(|| {
-                                stream.send_flow.send_data(len);
-
-                                // Decrement the stream's buffered data counter
-                                debug_assert!(stream.buffered_send_data >= len);
-                                stream.buffered_send_data -= len;
-                                stream.requested_send_capacity -= len;
+                                stream.send_data(len, flow_len, self.max_buffer_size);
+                                self.buffered_send_data -= len as usize;
 
                                 // Assign the capacity back to the connection that
                                 // was just consumed from the stream in the previous
                                 // line.
-                                self.flow.assign_capacity(len);
+                                self.flow.assign_capacity(flow_len);
                             });
 
                             let (eos, len) = tracing::trace_span!("updating connection flow")
                                 .in_scope(|| {
-                                    self.flow.send_data(len);
+                                    self.flow.send_data(flow_len);
 
                                     // Wrap the frame's data payload to ensure that the
                                     // correct amount of data gets written.
@@ -767,6 +1087,17 @@ impl Prioritize {
                                 stream: stream.key(),
                             }))
                         }
+                        Some(Frame::Headers(mut frame)) => {
+                            if stream.is_pending_headers {
+                                stream.is_pending_headers = false;
+
+                                if let Some(dependency) = stream.pending_dependency.take() {
+                                    frame.set_stream_dep(dependency);
+                                }
+                            }
+
+                            Frame::Headers(frame)
+                        }
                         Some(Frame::PushPromise(pp)) => {
                             let mut pushed =
                                 stream.store_mut().find_mut(&pp.promised_id()).unwrap();
@@ -783,6 +1114,18 @@ impl Prioritize {
                             }
                             Frame::PushPromise(pp)
                         }
+                        Some(frame) if is_oversized_extension(&frame, max_len) => {
+                            // The remote lowered its max frame size since the
+                            // frame was queued.
+                            tracing::debug!(?frame, "extension frame too big; dropping");
+
+                            if !stream.pending_send.is_empty() {
+                                self.pending_send.push(&mut stream);
+                            }
+
+                            counts.transition_after(stream, is_pending_reset);
+                            continue;
+                        }
                         Some(frame) => frame.map(|_| {
                             unreachable!(
                                 "Frame::map closure will only be called \
@@ -823,6 +1166,10 @@ impl Prioritize {
                         // frame is a data frame and the stream does not have
                         // any more capacity.
                         self.pending_send.push(&mut stream);
+                    } else if stream.state.is_send_closed() {
+                        // The stream won't send DATA anymore, so its node is
+                        // not needed to schedule it.
+                        self.tree.remove(stream.id);
                     }
 
                     counts.transition_after(stream, is_pending_reset);
@@ -834,6 +1181,104 @@ impl Prioritize {
         }
     }
 
+    /// Returns the padding of a DATA frame on `id` carrying `len` octets of
+    /// data, so that the frame payload does not exceed `limit` octets.
+    fn pad_data(&self, id: StreamId, len: usize, limit: usize) -> Option<u8> {
+        let pad_len = self.padding.pad_len(id, len)? as usize;
+
+        // The frame is sent unpadded if there is no room for the pad length
+        // octet.
+        if len >= limit {
+            return None;
+        }
+
+        Some(cmp::min(pad_len, limit - 1 - len) as u8)
+    }
+
+    /// Pop the next stream to send a frame from `pending_send`.
+    ///
+    /// Streams with a frame other than DATA at the head of their queue are
+    /// served first, in queue order, so that HEADERS for new streams are
+    /// still sent in stream ID order. Among the streams with DATA to send,
+    /// the next one is picked by urgency once extensible priorities are in
+    /// use, or using the dependency tree once the remote has signaled RFC
+    /// 7540 priorities.
+    fn pop_pending_send<'a, B>(
+        &mut self,
+        buffer: &Buffer<Frame<B>>,
+        store: &'a mut Store,
+    ) -> Option<store::Ptr<'a>> {
+        if !self.is_extensible && !self.tree.is_prioritized() {
+            return self.pending_send.pop(store);
+        }
+
+        // Sort the streams queued since the last call. Streams waiting in
+        // `ready` are still flagged as queued, so that they are not pushed
+        // to `pending_send` again.
+        while let Some(mut stream) = self.pending_send.pop(store) {
+            if matches!(stream.pending_send.peek_front(buffer), Some(Frame::Data(_))) {
+                stream.is_pending_send = true;
+                self.ready.insert(stream.id, stream.key());
+
+                if !self.is_extensible {
+                    self.tree.set_ready(stream.id);
+                }
+            } else {
+                self.pending_control.push(&mut stream);
+            }
+        }
+
+        if !self.pending_control.is_empty() {
+            return self.pending_control.pop(store);
+        }
+
+        let id = if self.is_extensible {
+            let mut first = None;
+
+            for (&id, &key) in &self.ready {
+                // Lowest urgency first. At the same urgency, non-incremental
+                // streams go one at a time, in stream ID order, before the
+                // incremental ones. Incremental streams are taken in queue
+                // order, which is round-robin since a stream that sent DATA
+                // is queued again at the back.
+                let priority = store.resolve(key).priority;
+                let order = if priority.is_incremental() {
+                    0
+                } else {
+                    u32::from(id)
+                };
+                let rank = (priority.urgency(), priority.is_incremental(), order);
+
+                match first {
+                    Some((_, first_rank)) if first_rank <= rank => {}
+                    _ => first = Some((id, rank)),
+                }
+            }
+
+            first?.0
+        } else {
+            self.tree.select()?
+        };
+
+        let key = self.take_ready(id)?;
+        let mut stream = store.resolve(key);
+        stream.is_pending_send = false;
+
+        Some(stream)
+    }
+
+    /// Removes stream `id` from the streams with DATA ready to be sent.
+    fn take_ready(&mut self, id: StreamId) -> Option<store::Key> {
+        self.tree.unset_ready(id);
+
+        // Keep the queue order, which is used with extensible priorities.
+        if self.is_extensible {
+            self.ready.shift_remove(&id)
+        } else {
+            self.ready.swap_remove(&id)
+        }
+    }
+
     fn schedule_pending_open(&mut self, store: &mut Store, counts: &mut Counts) {
         tracing::trace!("schedule_pending_open");
         // check for any pending open streams
@@ -851,6 +1296,18 @@ impl Prioritize {
     }
 }
 
+/// Returns true if `frame` was queued by the user and does not fit in a frame
+/// of `max_len` bytes anymore, which happens if the remote lowered its max
+/// frame size since.
+fn is_oversized_extension<B>(frame: &Frame<B>, max_len: usize) -> bool {
+    match frame {
+        Frame::AltSvc(frame) => frame.payload_len() > max_len,
+        Frame::Origin(frame) => frame.payload_len() > max_len,
+        Frame::Unknown(frame) => frame.payload().len() > max_len,
+        _ => false,
+    }
+}
+
 // ===== impl Prioritized =====
 
 impl<B> Buf for Prioritized<B>
@@ -0,0 +1,345 @@
+use crate::frame::{StreamDependency, StreamId};
+
+use indexmap::IndexMap;
+
+use std::cmp;
+
+/// The default weight assigned to a stream, stored as `weight - 1` (see
+/// section 5.3.5).
+const DEFAULT_WEIGHT: u8 = 15;
+
+/// Maximum number of nodes kept in the tree for streams that are not active.
+///
+/// The remote may create nodes for idle streams with PRIORITY frames. Once
+/// the limit is reached, the oldest inactive nodes are removed.
+const MAX_INACTIVE_NODES: usize = 256;
+
+/// The stream dependency tree, as described in section 5.3.
+///
+/// The tree is built from the PRIORITY frames and the priority information
+/// in HEADERS frames received from the remote. It is used to decide which
+/// stream with buffered DATA gets to send next.
+///
+/// Streams are scheduled top down: a stream that is able to send is always
+/// served before its dependents. Siblings share the capacity in proportion
+/// to their weight, which is tracked with a virtual "finish time" per node
+/// that grows with the amount of data sent by the subtree divided by the
+/// weight.
+///
+/// Streams with DATA ready to be sent are marked in the tree, and every node
+/// counts the ready streams in its subtree, so that picking the next stream
+/// only visits the branches that lead to one.
+#[derive(Debug)]
+pub(super) struct PriorityTree {
+    /// All nodes in the tree, in insertion order. The root (stream 0) is
+    /// always present.
+    nodes: IndexMap<StreamId, Node>,
+
+    /// Set once the remote has sent any priority information. Until then,
+    /// all streams are equal siblings of the root, and streams are served in
+    /// the order they are queued.
+    is_prioritized: bool,
+}
+
+#[derive(Debug)]
+struct Node {
+    parent: StreamId,
+    weight: u8,
+    children: Vec<StreamId>,
+    /// Whether the stream has DATA ready to be sent.
+    is_ready: bool,
+    /// Number of ready streams in this subtree, including this one.
+    num_ready: usize,
+    /// Virtual time consumed by this subtree, relative to its siblings.
+    vtime: u64,
+    /// Virtual time of the child that was most recently scheduled. New
+    /// children start from here, so they don't starve their siblings.
+    clock: u64,
+}
+
+// ===== impl PriorityTree =====
+
+impl PriorityTree {
+    pub fn new() -> Self {
+        let mut nodes = IndexMap::new();
+        nodes.insert(StreamId::ZERO, Node::new(StreamId::ZERO, DEFAULT_WEIGHT, 0));
+
+        PriorityTree {
+            nodes,
+            is_prioritized: false,
+        }
+    }
+
+    pub fn is_prioritized(&self) -> bool {
+        self.is_prioritized
+    }
+
+    /// Applies the dependency received for stream `id`, as described in
+    /// section 5.3.3.
+    pub fn reprioritize(&mut self, id: StreamId, dep: &StreamDependency) {
+        debug_assert!(!id.is_zero());
+
+        let parent = dep.dependency_id();
+
+        if parent == id {
+            // This is rejected when the frame is decoded.
+            return;
+        }
+
+        self.is_prioritized = true;
+
+        self.ensure(id);
+        self.ensure(parent);
+
+        // If the new parent depends on the stream being moved, the parent is
+        // first moved to depend on the stream's former parent, keeping its
+        // weight.
+        if self.is_descendant(parent, id) {
+            let former_parent = self.nodes[&id].parent;
+            let weight = self.nodes[&parent].weight;
+            self.detach(parent);
+            self.attach(parent, former_parent, weight, false);
+        }
+
+        self.detach(id);
+        self.attach(id, parent, dep.weight(), dep.is_exclusive());
+    }
+
+    /// Marks stream `id` as having DATA ready to be sent.
+    ///
+    /// Streams missing from the tree are added as dependents of the root
+    /// with the default weight.
+    pub fn set_ready(&mut self, id: StreamId) {
+        self.ensure(id);
+
+        let node = &mut self.nodes[&id];
+
+        if !node.is_ready {
+            node.is_ready = true;
+            self.add_ready(id, 1);
+        }
+    }
+
+    /// Clears the mark set by `set_ready`.
+    pub fn unset_ready(&mut self, id: StreamId) {
+        match self.nodes.get_mut(&id) {
+            Some(node) if node.is_ready => node.is_ready = false,
+            _ => return,
+        }
+
+        self.sub_ready(id, 1);
+    }
+
+    /// Picks the ready stream that should send DATA next.
+    pub fn select(&self) -> Option<StreamId> {
+        let mut curr = StreamId::ZERO;
+
+        loop {
+            let node = &self.nodes[&curr];
+
+            if node.is_ready {
+                return Some(curr);
+            }
+
+            // Only reached at the root if nothing is ready.
+            curr = node
+                .children
+                .iter()
+                .filter(|id| self.nodes[*id].num_ready > 0)
+                .min_by_key(|id| self.nodes[*id].vtime)
+                .copied()?;
+        }
+    }
+
+    /// Charges `len` bytes of DATA sent on stream `id` to the stream and all
+    /// of its ancestors.
+    pub fn sent(&mut self, id: StreamId, len: usize) {
+        if !self.nodes.contains_key(&id) {
+            return;
+        }
+
+        let mut curr = id;
+
+        while !curr.is_zero() {
+            let (parent, vtime) = {
+                let node = &mut self.nodes[&curr];
+                let vtime = node.vtime;
+                node.vtime += (len as u64 * 256) / (u64::from(node.weight) + 1);
+                (node.parent, vtime)
+            };
+
+            self.nodes[&parent].clock = vtime;
+            curr = parent;
+        }
+    }
+
+    /// Removes the oldest nodes for streams that are not in use anymore once
+    /// the tree holds too many of them.
+    pub fn prune<F>(&mut self, is_active: F)
+    where
+        F: Fn(StreamId) -> bool,
+    {
+        let inactive = self
+            .nodes
+            .keys()
+            .filter(|id| !id.is_zero() && !is_active(**id))
+            .count();
+
+        if inactive <= MAX_INACTIVE_NODES {
+            return;
+        }
+
+        let remove: Vec<_> = self
+            .nodes
+            .keys()
+            .filter(|id| !id.is_zero() && !is_active(**id))
+            .take(inactive - MAX_INACTIVE_NODES)
+            .copied()
+            .collect();
+
+        for id in remove {
+            self.remove(id);
+        }
+    }
+
+    /// Removes a stream from the tree. Its dependents become dependents of
+    /// its parent, sharing its weight (section 5.3.4).
+    pub fn remove(&mut self, id: StreamId) {
+        let node = match self.nodes.shift_remove(&id) {
+            Some(node) => node,
+            None => return,
+        };
+
+        self.nodes[&node.parent]
+            .children
+            .retain(|child| *child != id);
+
+        // The dependents stay in the subtree of the parent.
+        if node.is_ready {
+            self.sub_ready(node.parent, 1);
+        }
+
+        let total: u32 = node
+            .children
+            .iter()
+            .map(|child| u32::from(self.nodes[child].weight) + 1)
+            .sum();
+
+        for child in node.children {
+            let weight = {
+                let child = &mut self.nodes[&child];
+                let share = (u32::from(node.weight) + 1) * (u32::from(child.weight) + 1) / total;
+                child.parent = node.parent;
+                (cmp::max(share, 1) - 1) as u8
+            };
+
+            self.nodes[&child].weight = weight;
+            self.nodes[&node.parent].children.push(child);
+        }
+    }
+
+    /// Inserts a node for `id` with the default priority, if missing.
+    fn ensure(&mut self, id: StreamId) {
+        if !self.nodes.contains_key(&id) {
+            let clock = self.nodes[&StreamId::ZERO].clock;
+            self.nodes
+                .insert(id, Node::new(StreamId::ZERO, DEFAULT_WEIGHT, clock));
+            self.nodes[&StreamId::ZERO].children.push(id);
+        }
+    }
+
+    /// Returns true if `id` depends, directly or not, on `ancestor`.
+    fn is_descendant(&self, id: StreamId, ancestor: StreamId) -> bool {
+        let mut curr = id;
+
+        while !curr.is_zero() {
+            curr = self.nodes[&curr].parent;
+
+            if curr == ancestor {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn detach(&mut self, id: StreamId) {
+        let parent = self.nodes[&id].parent;
+        self.nodes[&parent].children.retain(|child| *child != id);
+        self.sub_ready(parent, self.nodes[&id].num_ready);
+    }
+
+    fn attach(&mut self, id: StreamId, parent: StreamId, weight: u8, is_exclusive: bool) {
+        if is_exclusive {
+            let children = std::mem::take(&mut self.nodes[&parent].children);
+            let mut num_ready = 0;
+
+            for child in &children {
+                let child = &mut self.nodes[child];
+                child.parent = id;
+                num_ready += child.num_ready;
+            }
+
+            self.nodes[&id].children.extend(children);
+            self.nodes[&id].num_ready += num_ready;
+            self.sub_ready(parent, num_ready);
+        }
+
+        let clock = self.nodes[&parent].clock;
+        self.nodes[&parent].children.push(id);
+        self.add_ready(parent, self.nodes[&id].num_ready);
+
+        let node = &mut self.nodes[&id];
+        node.parent = parent;
+        node.weight = weight;
+        node.vtime = clock;
+    }
+
+    /// Adds `n` ready streams to the subtree of `id` and its ancestors.
+    fn add_ready(&mut self, id: StreamId, n: usize) {
+        let mut curr = id;
+
+        loop {
+            let node = &mut self.nodes[&curr];
+            node.num_ready += n;
+
+            if curr.is_zero() {
+                return;
+            }
+
+            curr = node.parent;
+        }
+    }
+
+    /// Removes `n` ready streams from the subtree of `id` and its ancestors.
+    fn sub_ready(&mut self, id: StreamId, n: usize) {
+        let mut curr = id;
+
+        loop {
+            let node = &mut self.nodes[&curr];
+            node.num_ready -= n;
+
+            if curr.is_zero() {
+                return;
+            }
+
+            curr = node.parent;
+        }
+    }
+}
+
+// ===== impl Node =====
+
+impl Node {
+    fn new(parent: StreamId, weight: u8, vtime: u64) -> Self {
+        Node {
+            parent,
+            weight,
+            children: Vec::new(),
+            is_ready: false,
+            num_ready: 0,
+            vtime,
+            clock: 0,
+        }
+    }
+}
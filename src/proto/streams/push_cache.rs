@@ -0,0 +1,178 @@
+use super::store;
+use crate::timer::{Deadline, Time};
+
+use http::header::{HeaderMap, HeaderName, VARY};
+use http::Request;
+
+use std::collections::VecDeque;
+use std::task::Context;
+use std::time::{Duration, Instant};
+
+/// Promised requests that have not been claimed by the application yet.
+///
+/// Entries are kept in the order they were pushed, which is also the order
+/// in which they expire.
+#[derive(Debug)]
+pub(super) struct PushCache {
+    /// Maximum number of pushed streams to hold at a time
+    max_entries: usize,
+
+    /// How long a pushed stream may wait to be claimed
+    max_age: Duration,
+
+    entries: VecDeque<Entry>,
+
+    /// Fires when the oldest entry expires
+    timer: Option<Deadline>,
+
+    /// Timer used to wake up the connection at `timer`
+    time: Time,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// The pushed stream
+    key: store::Key,
+
+    /// The request promised by the server
+    request: Request<()>,
+
+    /// When the entry stops being offered to new requests
+    expires_at: Instant,
+}
+
+impl PushCache {
+    pub fn new(max_entries: usize, max_age: Duration, time: Time) -> Self {
+        PushCache {
+            max_entries,
+            max_age,
+            entries: VecDeque::new(),
+            timer: None,
+            time,
+        }
+    }
+
+    /// Adds a pushed stream to the cache.
+    ///
+    /// If the cache is full, the oldest entry is evicted and its stream is
+    /// returned so that it can be canceled.
+    pub fn insert(&mut self, key: store::Key, request: Request<()>) -> Option<store::Key> {
+        let evicted = if self.entries.len() >= self.max_entries {
+            self.entries.pop_front().map(|entry| entry.key)
+        } else {
+            None
+        };
+
+        if self.max_entries > 0 {
+            self.entries.push_back(Entry {
+                key,
+                request,
+                expires_at: Instant::now() + self.max_age,
+            });
+            evicted
+        } else {
+            // Nothing can be cached, so the new stream goes straight back.
+            Some(key)
+        }
+    }
+
+    /// Removes and returns the next entry that has expired, if any.
+    ///
+    /// Otherwise, `cx` is registered to be woken up when the oldest entry
+    /// expires.
+    pub fn poll_expired(&mut self, cx: &mut Context) -> Option<store::Key> {
+        let expires_at = match self.entries.front() {
+            Some(entry) => entry.expires_at,
+            None => {
+                self.timer = None;
+                return None;
+            }
+        };
+
+        // Only start a new timer when the oldest entry changes.
+        if matches!(self.timer, Some(ref timer) if timer.at() != expires_at) {
+            self.timer = None;
+        }
+
+        let time = &self.time;
+        let timer = self.timer.get_or_insert_with(|| time.deadline(expires_at));
+
+        if timer.poll(cx).is_pending() {
+            return None;
+        }
+
+        self.timer = None;
+        self.entries.pop_front().map(|entry| entry.key)
+    }
+
+    /// Removes and returns every remaining entry.
+    pub fn drain(&mut self) -> impl Iterator<Item = store::Key> + '_ {
+        self.entries.drain(..).map(|entry| entry.key)
+    }
+
+    /// Finds a pushed stream that can be used to answer `request`.
+    ///
+    /// `response_headers` returns the headers of the pushed response, if it
+    /// has been received yet, so that its `Vary` header can be honored.
+    pub fn take_match<'a, F>(
+        &mut self,
+        request: &Request<()>,
+        mut response_headers: F,
+    ) -> Option<store::Key>
+    where
+        F: FnMut(store::Key) -> Option<&'a HeaderMap>,
+    {
+        let pos = self.entries.iter().position(|entry| {
+            let promised = &entry.request;
+
+            // A push answers requests for the same `:method`, `:authority`
+            // and `:path`, whatever their scheme.
+            if promised.method() != request.method()
+                || promised.uri().authority() != request.uri().authority()
+                || promised.uri().path_and_query() != request.uri().path_and_query()
+            {
+                return false;
+            }
+
+            match response_headers(entry.key) {
+                Some(headers) => vary_matches(headers, promised.headers(), request.headers()),
+                None => true,
+            }
+        })?;
+
+        self.entries.remove(pos).map(|entry| entry.key)
+    }
+}
+
+/// Returns true if every header named by the response's `Vary` header has
+/// the same values in both requests.
+fn vary_matches(response: &HeaderMap, promised: &HeaderMap, request: &HeaderMap) -> bool {
+    for value in response.get_all(VARY) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => return false,
+        };
+
+        for name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            // A `Vary` of `*` means the response can never be reused.
+            if name == "*" {
+                return false;
+            }
+
+            let name = match HeaderName::from_bytes(name.as_bytes()) {
+                Ok(name) => name,
+                Err(_) => return false,
+            };
+
+            if !promised
+                .get_all(&name)
+                .iter()
+                .eq(request.get_all(&name).iter())
+            {
+                return false;
+            }
+        }
+    }
+
+    true
+}
@@ -1,20 +1,46 @@
 use super::*;
 use crate::codec::{RecvError, UserError};
 use crate::frame::{PushPromiseHeaderError, Reason, DEFAULT_INITIAL_WINDOW_SIZE};
-use crate::{frame, proto};
+use crate::{client, frame, proto};
 use std::task::Context;
 
 use http::{HeaderMap, Request, Response};
 
+use std::cmp;
+use std::collections::VecDeque;
 use std::io;
 use std::task::{Poll, Waker};
 use std::time::{Duration, Instant};
 
+/// Maximum number of origins kept from the `ORIGIN` frames received.
+const MAX_ORIGIN_SET_LEN: usize = 1024;
+
+/// Maximum number of extension frames buffered, per stream and for the
+/// connection, until the application reads them. Frames received beyond
+/// that are dropped. The same limit applies to `ALTSVC` frames.
+const MAX_PENDING_EXTENSION_FRAMES: usize = 16;
+
+/// Maximum number of informational responses buffered on a stream until the
+/// application reads them. Responses received beyond that are dropped.
+const MAX_PENDING_INFORMATIONAL: usize = 16;
+
 #[derive(Debug)]
 pub(super) struct Recv {
     /// Initial window size of remote initiated streams
     init_window_sz: WindowSize,
 
+    /// Window size streams are grown to, as estimated from the
+    /// bandwidth-delay product
+    target_stream_window: WindowSize,
+
+    /// Whether streams release capacity as soon as data is handed to the
+    /// application, unless set otherwise on the stream
+    auto_release_capacity: bool,
+
+    /// Maximum amount of received data the connection may have to hold,
+    /// shared fairly by the streams
+    memory_budget: Option<WindowSize>,
+
     /// Connection level flow control governing received data
     flow: FlowControl,
 
@@ -56,11 +82,42 @@ pub(super) struct Recv {
 
     /// If push promises are allowed to be received.
     is_push_enabled: bool,
+
+    /// If extended connect protocol is enabled.
+    is_extended_connect_protocol_enabled: bool,
+
+    /// Extension frames received on stream 0, waiting to be read
+    pending_extension: VecDeque<frame::Unknown>,
+
+    /// Task tracking receiving connection level extension frames
+    extension_task: Option<Waker>,
+
+    /// Origins received in `ORIGIN` frames, `None` until the first one
+    origin_set: Option<Vec<String>>,
+
+    /// `ALTSVC` frames received, waiting to be read
+    pending_alt_svc: VecDeque<frame::AltSvc>,
+
+    /// Task tracking receiving `ALTSVC` frames
+    alt_svc_task: Option<Waker>,
+
+    /// Pushed streams waiting to be claimed by a matching request
+    push_cache: Option<PushCache>,
+
+    /// Decides which pushed streams are accepted
+    push_policy: Option<client::PushPolicy>,
+
+    /// Number of pushed streams not yet claimed by the application
+    num_pending_pushed: usize,
+
+    /// Maximum number of pushed streams not yet claimed by the application
+    max_pending_pushed: Option<usize>,
 }
 
 #[derive(Debug)]
 pub(super) enum Event {
     Headers(peer::PollMessage),
+    InformationalHeaders(peer::PollMessage),
     Data(Bytes),
     Trailers(HeaderMap),
 }
@@ -95,8 +152,19 @@ impl Recv {
             .expect("invalid initial remote window size");
         flow.assign_capacity(DEFAULT_INITIAL_WINDOW_SIZE);
 
+        // The window cannot start lower, but no more of it is given back than
+        // the memory budget allows.
+        if let Some(budget) = config.recv_memory_budget {
+            if budget < DEFAULT_INITIAL_WINDOW_SIZE {
+                flow.claim_capacity(DEFAULT_INITIAL_WINDOW_SIZE - budget);
+            }
+        }
+
         Recv {
             init_window_sz: config.local_init_window_sz,
+            target_stream_window: 0,
+            auto_release_capacity: config.auto_release_capacity,
+            memory_budget: config.recv_memory_budget,
             flow,
             in_flight_data: 0 as WindowSize,
             next_stream_id: Ok(next_stream_id.into()),
@@ -109,6 +177,18 @@ impl Recv {
             buffer: Buffer::new(),
             refused: None,
             is_push_enabled: config.local_push_enabled,
+            is_extended_connect_protocol_enabled: config.extended_connect_protocol_enabled,
+            pending_extension: VecDeque::new(),
+            extension_task: None,
+            origin_set: None,
+            pending_alt_svc: VecDeque::new(),
+            alt_svc_task: None,
+            push_cache: config.push_cache.map(|(max_entries, max_age)| {
+                PushCache::new(max_entries, max_age, config.time.clone())
+            }),
+            push_policy: config.push_policy.clone(),
+            num_pending_pushed: 0,
+            max_pending_pushed: config.max_pending_pushed_streams,
         }
     }
 
@@ -161,6 +241,16 @@ impl Recv {
         counts: &mut Counts,
     ) -> Result<(), RecvHeaderBlockError<Option<frame::Headers>>> {
         tracing::trace!("opening stream; init_window={}", self.init_window_sz);
+
+        if frame.is_informational() && frame.is_end_stream() {
+            proto_err!(stream: "recv_headers: informational response with END_STREAM; stream={:?}", stream.id);
+            return Err(RecvError::Stream {
+                id: stream.id,
+                reason: Reason::PROTOCOL_ERROR,
+            }
+            .into());
+        }
+
         let is_initial = stream.state.recv_open(&frame)?;
 
         if is_initial {
@@ -173,7 +263,7 @@ impl Recv {
             counts.inc_num_recv_streams(stream);
         }
 
-        if !stream.content_length.is_head() {
+        if !stream.content_length.is_head() && !frame.is_informational() {
             use super::stream::ContentLength;
             use http::header;
 
@@ -226,18 +316,41 @@ impl Recv {
 
         let stream_id = frame.stream_id();
         let (pseudo, fields) = frame.into_parts();
-        if !pseudo.is_informational() {
-            let message = counts
-                .peer()
-                .convert_poll_message(pseudo, fields, stream_id)?;
 
-            // Push the frame onto the stream's recv buffer
-            stream
-                .pending_recv
-                .push_back(&mut self.buffer, Event::Headers(message));
-            stream.notify_recv();
+        if pseudo.protocol.is_some()
+            && counts.peer().is_server()
+            && !self.is_extended_connect_protocol_enabled
+        {
+            proto_err!(stream: "cannot use :protocol if extended connect protocol is disabled; stream={:?}", stream.id);
+            return Err(RecvError::Stream {
+                id: stream.id,
+                reason: Reason::PROTOCOL_ERROR,
+            }
+            .into());
         }
 
+        let is_informational = pseudo.is_informational();
+        let message = counts
+            .peer()
+            .convert_poll_message(pseudo, fields, stream_id)?;
+
+        // Push the frame onto the stream's recv buffer. Informational
+        // responses stay in front of the final response, so that they can be
+        // yielded in order before it.
+        let event = if is_informational {
+            if stream.num_pending_informational >= MAX_PENDING_INFORMATIONAL {
+                tracing::debug!("too many buffered informational responses; dropping");
+                return Ok(());
+            }
+
+            stream.num_pending_informational += 1;
+            Event::InformationalHeaders(message)
+        } else {
+            Event::Headers(message)
+        };
+        stream.pending_recv.push_back(&mut self.buffer, event);
+        stream.notify_recv();
+
         // Only servers can receive a headers frame that initiates the stream.
         // This is verified in `Streams` before calling this function.
         if counts.peer().is_server() {
@@ -259,6 +372,84 @@ impl Recv {
         }
     }
 
+    /// Returns true if pushed streams are kept in the push cache rather than
+    /// handed to the application.
+    pub fn is_push_cache_enabled(&self) -> bool {
+        self.push_cache.is_some()
+    }
+
+    /// Adds a pushed stream to the push cache, returning a stream that was
+    /// evicted to make room for it.
+    pub fn cache_push(&mut self, key: store::Key, request: Request<()>) -> Option<store::Key> {
+        let evicted = self
+            .push_cache
+            .as_mut()
+            .and_then(|cache| cache.insert(key, request));
+
+        self.inc_num_pending_pushed();
+        if evicted.is_some() {
+            self.dec_num_pending_pushed();
+        }
+
+        evicted
+    }
+
+    /// Returns the next pushed stream whose cache entry has expired.
+    pub fn poll_expired_push(&mut self, cx: &mut Context) -> Option<store::Key> {
+        let expired = self
+            .push_cache
+            .as_mut()
+            .and_then(|cache| cache.poll_expired(cx));
+
+        if expired.is_some() {
+            self.dec_num_pending_pushed();
+        }
+
+        expired
+    }
+
+    /// Removes every pushed stream from the push cache.
+    pub fn drain_push_cache(&mut self) -> Vec<store::Key> {
+        let drained: Vec<_> = match self.push_cache {
+            Some(ref mut cache) => cache.drain().collect(),
+            None => Vec::new(),
+        };
+
+        for _ in &drained {
+            self.dec_num_pending_pushed();
+        }
+        drained
+    }
+
+    /// Finds a cached pushed stream that answers `request`.
+    pub fn take_cached_push(&mut self, request: &Request<()>, store: &Store) -> Option<store::Key> {
+        use super::peer::PollMessage::*;
+
+        let cache = self.push_cache.as_mut()?;
+        let buffer = &self.buffer;
+
+        let key = cache.take_match(request, |key| {
+            match store[key].pending_recv.peek_front(buffer) {
+                Some(Event::Headers(Client(response))) => Some(response.headers()),
+                _ => None,
+            }
+        })?;
+
+        self.dec_num_pending_pushed();
+        Some(key)
+    }
+
+    /// Called when a pushed stream is queued for the application.
+    pub fn inc_num_pending_pushed(&mut self) {
+        self.num_pending_pushed += 1;
+    }
+
+    /// Called when a queued pushed stream is claimed or dropped.
+    pub fn dec_num_pending_pushed(&mut self) {
+        assert!(self.num_pending_pushed > 0);
+        self.num_pending_pushed -= 1;
+    }
+
     /// Called by the client to get pushed response
     pub fn poll_pushed(
         &mut self,
@@ -278,6 +469,7 @@ impl Recv {
         });
         stream.pending_push_promises = ppp;
         if let Some(p) = pushed {
+            self.dec_num_pending_pushed();
             Poll::Ready(Some(Ok(p)))
         } else {
             let is_open = stream.state.ensure_recv_open()?;
@@ -299,17 +491,55 @@ impl Recv {
     ) -> Poll<Result<Response<()>, proto::Error>> {
         use super::peer::PollMessage::*;
 
+        // Until the final response is received, informational responses are
+        // left for `poll_informational`, which may run on another task.
+        if stream.state.is_recv_headers() {
+            stream.recv_task = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
         // If the buffer is not empty, then the first frame must be a HEADERS
-        // frame or the user violated the contract.
-        match stream.pending_recv.pop_front(&mut self.buffer) {
-            Some(Event::Headers(Client(response))) => Poll::Ready(Ok(response)),
-            Some(_) => panic!("poll_response called after response returned"),
-            None => {
-                stream.state.ensure_recv_open()?;
+        // frame or the user violated the contract. Informational responses
+        // that weren't consumed by `poll_informational` are skipped.
+        loop {
+            match self.pop_recv_event(stream) {
+                Some(Event::Headers(Client(response))) => return Poll::Ready(Ok(response)),
+                Some(Event::InformationalHeaders(_)) => continue,
+                Some(_) => panic!("poll_response called after response returned"),
+                None => {
+                    stream.state.ensure_recv_open()?;
+
+                    stream.recv_task = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
 
-                stream.recv_task = Some(cx.waker().clone());
+    /// Called by the client to get the next informational (1xx) response.
+    ///
+    /// Returns `None` once the final response has been received.
+    pub fn poll_informational(
+        &mut self,
+        cx: &Context,
+        stream: &mut store::Ptr,
+    ) -> Poll<Option<Response<()>>> {
+        use super::peer::PollMessage::*;
+
+        match self.pop_recv_event(stream) {
+            Some(Event::InformationalHeaders(Client(response))) => Poll::Ready(Some(response)),
+            Some(event) => {
+                // The final response is next; leave it for `poll_response`.
+                stream.pending_recv.push_front(&mut self.buffer, event);
+                Poll::Ready(None)
+            }
+            None if stream.state.is_recv_headers() => {
+                stream.informational_task = Some(cx.waker().clone());
                 Poll::Pending
             }
+            // Either the final response was already taken, or the stream was
+            // reset, which the response future reports.
+            None => Poll::Ready(None),
         }
     }
 
@@ -375,6 +605,25 @@ impl Recv {
             return Err(UserError::ReleaseCapacityTooBig);
         }
 
+        // When capacity is released automatically, the application may only
+        // release the data it took before that was enabled.
+        if self.is_auto_release(stream) && capacity > stream.unreleased_recv_data {
+            return Err(UserError::ReleaseCapacityTooBig);
+        }
+
+        stream.unreleased_recv_data = stream.unreleased_recv_data.saturating_sub(capacity);
+        self.release_in_flight(capacity, stream, task);
+
+        Ok(())
+    }
+
+    /// Releases in-flight data back to the connection & stream
+    fn release_in_flight(
+        &mut self,
+        capacity: WindowSize,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) {
         self.release_connection_capacity(capacity, task);
 
         // Decrement in-flight data
@@ -383,7 +632,9 @@ impl Recv {
         // Assign capacity to stream
         stream.recv_flow.assign_capacity(capacity);
 
-        if stream.recv_flow.unclaimed_capacity().is_some() {
+        // A stream that is no longer receiving data has no use for a
+        // WINDOW_UPDATE frame.
+        if stream.state.is_recv_streaming() && stream.recv_flow.unclaimed_capacity().is_some() {
             // Queue the stream for sending the WINDOW_UPDATE frame.
             self.pending_window_updates.push(stream);
 
@@ -391,8 +642,18 @@ impl Recv {
                 task.wake();
             }
         }
+    }
 
-        Ok(())
+    /// Sets whether capacity is released as soon as data is handed to the
+    /// application.
+    pub fn set_auto_release_capacity(&mut self, stream: &mut store::Ptr, enabled: bool) {
+        stream.auto_release_capacity = Some(enabled);
+    }
+
+    fn is_auto_release(&self, stream: &Stream) -> bool {
+        stream
+            .auto_release_capacity
+            .unwrap_or(self.auto_release_capacity)
     }
 
     /// Release any unclaimed capacity for a closed stream.
@@ -428,6 +689,13 @@ impl Recv {
     /// The `task` is an optional parked task for the `Connection` that might
     /// be blocked on needing more window capacity.
     pub fn set_target_connection_window(&mut self, target: WindowSize, task: &mut Option<Waker>) {
+        // Data received on the connection is bounded by its window, so the
+        // window never grows past the memory budget.
+        let target = match self.memory_budget {
+            Some(budget) => cmp::min(target, budget),
+            None => target,
+        };
+
         tracing::trace!(
             "set_target_connection_window; target={}; available={}, reserved={}",
             target,
@@ -457,6 +725,121 @@ impl Recv {
         }
     }
 
+    /// Grows the connection window, and the windows of the streams, to
+    /// `target`, as estimated from the bandwidth-delay product.
+    ///
+    /// Windows are never shrunk. Streams opened later are grown when data is
+    /// first received on them.
+    pub fn grow_target_windows(
+        &mut self,
+        target: WindowSize,
+        store: &mut Store,
+        task: &mut Option<Waker>,
+    ) {
+        let current = (self.flow.available() + self.in_flight_data).checked_size();
+        if target > current {
+            self.set_target_connection_window(target, task);
+        }
+
+        if target <= self.target_stream_window {
+            return;
+        }
+
+        tracing::trace!("grow_target_windows; stream target={}", target);
+        self.target_stream_window = target;
+
+        store
+            .for_each(|mut stream| {
+                self.grow_stream_window(&mut stream);
+                Ok::<_, ()>(())
+            })
+            .expect("grow_stream_window");
+
+        if !self.pending_window_updates.is_empty() {
+            if let Some(task) = task.take() {
+                task.wake();
+            }
+        }
+    }
+
+    /// Gives the stream enough capacity to reach the stream window target,
+    /// scheduling a WINDOW_UPDATE if needed.
+    fn grow_stream_window(&mut self, stream: &mut store::Ptr) {
+        if self.target_stream_window <= self.init_window_sz
+            || !stream.state.is_recv_streaming()
+            || stream.is_recv_window_set
+        {
+            return;
+        }
+
+        // As for the connection, the current target of the stream window is
+        // its available capacity plus any in-flight data.
+        let current = (stream.recv_flow.available() + stream.in_flight_recv_data).checked_size();
+        if self.target_stream_window <= current {
+            return;
+        }
+
+        stream
+            .recv_flow
+            .assign_capacity(self.target_stream_window - current);
+
+        if stream.recv_flow.unclaimed_capacity().is_some() {
+            self.pending_window_updates.push(stream);
+        }
+    }
+
+    /// Sets the target window of a single stream, in place of the initial
+    /// window size setting.
+    ///
+    /// Growing the window makes the difference available to be sent in a
+    /// `WINDOW_UPDATE` frame. Shrinking it holds back window updates until
+    /// the data in flight drops below the target.
+    pub fn set_stream_target_window(&mut self, target: WindowSize, stream: &mut store::Ptr) {
+        tracing::trace!(?stream.id, target, "set_stream_target_window");
+
+        // As for the connection, the current target of the stream window is
+        // its available capacity plus any in-flight data.
+        let current = (stream.recv_flow.available() + stream.in_flight_recv_data).checked_size();
+        if target > current {
+            stream.recv_flow.assign_capacity(target - current);
+        } else {
+            stream.recv_flow.claim_capacity(current - target);
+        }
+
+        stream.is_recv_window_set = true;
+    }
+
+    /// Queues the stream for sending a `WINDOW_UPDATE` frame, if its window
+    /// has grown enough.
+    pub fn schedule_stream_window_update(
+        &mut self,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) {
+        if stream.state.is_recv_streaming() && stream.recv_flow.unclaimed_capacity().is_some() {
+            self.pending_window_updates.push(stream);
+
+            if let Some(task) = task.take() {
+                task.wake();
+            }
+        }
+    }
+
+    /// Takes the `WINDOW_UPDATE` frame of a stream that is not open yet.
+    ///
+    /// The frame must be queued on the stream, after its `HEADERS` frame, as
+    /// the peer does not know about the stream before then.
+    pub fn take_stream_window_update(
+        &mut self,
+        stream: &mut store::Ptr,
+        counts: &Counts,
+    ) -> Option<frame::WindowUpdate> {
+        let share = self.stream_window_share(counts);
+        let incr = claim_stream_window_update(share, stream)?;
+
+        Some(frame::WindowUpdate::new(stream.id, incr))
+    }
+
     pub(crate) fn apply_local_settings(
         &mut self,
         settings: &frame::Settings,
@@ -530,14 +913,18 @@ impl Recv {
         &mut self,
         frame: frame::Data,
         stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
     ) -> Result<(), RecvError> {
-        let sz = frame.payload().len();
+        // The padding, including the pad length octet, counts against the
+        // flow control windows.
+        let sz = frame.flow_controlled_len();
 
         // This should have been enforced at the codec::FramedRead layer, so
         // this is just a sanity check.
         assert!(sz <= MAX_WINDOW_SIZE as usize);
 
         let sz = sz as WindowSize;
+        let padding = sz - frame.payload().len() as WindowSize;
 
         let is_ignoring_frame = stream.state.is_local_reset();
 
@@ -622,6 +1009,19 @@ impl Recv {
         // Track the data as in-flight
         stream.in_flight_recv_data += sz;
 
+        // The padding is never handed to the user, so it cannot be released
+        // by them. Release it right away.
+        if padding > 0 {
+            tracing::trace!(
+                "recv_data; releasing padding; size={}; stream={:?}",
+                padding,
+                stream.id
+            );
+            self.release_in_flight(padding, stream, task);
+        }
+
+        self.grow_stream_window(stream);
+
         let event = Event::Data(frame.into_payload());
 
         // Push the frame onto the recv buffer
@@ -720,6 +1120,40 @@ impl Recv {
             });
         }
 
+        if let Some(max) = self.max_pending_pushed {
+            if self.num_pending_pushed >= max {
+                tracing::debug!(
+                    "stream error REFUSED_STREAM -- recv_push_promise: \
+                     too many pending pushed streams; promised_id={:?};",
+                    promised_id,
+                );
+                return Err(RecvError::Stream {
+                    id: promised_id,
+                    reason: Reason::REFUSED_STREAM,
+                });
+            }
+        }
+
+        if let Some(ref policy) = self.push_policy {
+            if let Err(reason) = policy.check(&req) {
+                tracing::debug!(
+                    "recv_push_promise; refused by push policy; promised_id={:?}; reason={:?}",
+                    promised_id,
+                    reason,
+                );
+                return Err(RecvError::Stream {
+                    id: promised_id,
+                    reason,
+                });
+            }
+        }
+
+        // Accepted pushed streams count as processed, so that a GOAWAY sent
+        // by a client covers them.
+        if promised_id > self.last_processed_id {
+            self.last_processed_id = promised_id;
+        }
+
         use super::peer::PollMessage::*;
         stream
             .pending_recv
@@ -777,11 +1211,23 @@ impl Recv {
     }
 
     pub(super) fn clear_recv_buffer(&mut self, stream: &mut Stream) {
-        while let Some(_) = stream.pending_recv.pop_front(&mut self.buffer) {
+        while let Some(_) = self.pop_recv_event(stream) {
             // drop it
         }
     }
 
+    /// Pops the next event received on `stream`, keeping track of the
+    /// number of informational responses still buffered.
+    fn pop_recv_event(&mut self, stream: &mut Stream) -> Option<Event> {
+        let event = stream.pending_recv.pop_front(&mut self.buffer);
+
+        if let Some(Event::InformationalHeaders(_)) = event {
+            stream.num_pending_informational -= 1;
+        }
+
+        event
+    }
+
     /// Get the max ID of streams we can receive.
     ///
     /// This gets lowered if we send a GOAWAY frame.
@@ -984,6 +1430,8 @@ impl Recv {
                 None => return Poll::Ready(Ok(())),
             };
 
+            let share = self.stream_window_share(counts);
+
             counts.transition(stream, |_, stream| {
                 tracing::trace!("pending_window_updates -- pop; stream={:?}", stream.id);
                 debug_assert!(!stream.is_pending_window_update);
@@ -998,25 +1446,27 @@ impl Recv {
                     return;
                 }
 
-                // TODO: de-dup
-                if let Some(incr) = stream.recv_flow.unclaimed_capacity() {
+                if let Some(incr) = claim_stream_window_update(share, stream) {
                     // Create the WINDOW_UPDATE frame
                     let frame = frame::WindowUpdate::new(stream.id, incr);
 
                     // Buffer it
                     dst.buffer(frame.into())
                         .expect("invalid WINDOW_UPDATE frame");
-
-                    // Update flow control
-                    stream
-                        .recv_flow
-                        .inc_window(incr)
-                        .expect("unexpected flow control state");
                 }
             })
         }
     }
 
+    /// Returns how large the window of a stream, plus the data it has not
+    /// released yet, may be with the current number of open streams.
+    fn stream_window_share(&self, counts: &Counts) -> WindowSize {
+        match self.memory_budget {
+            Some(budget) => budget / cmp::max(counts.num_open_streams(), 1) as WindowSize,
+            None => MAX_WINDOW_SIZE,
+        }
+    }
+
     pub fn next_incoming(&mut self, store: &mut Store) -> Option<store::Key> {
         self.pending_accept.pop(store).map(|ptr| ptr.key())
     }
@@ -1024,11 +1474,27 @@ impl Recv {
     pub fn poll_data(
         &mut self,
         cx: &Context,
-        stream: &mut Stream,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
     ) -> Poll<Option<Result<Bytes, proto::Error>>> {
         // TODO: Return error when the stream is reset
         match stream.pending_recv.pop_front(&mut self.buffer) {
-            Some(Event::Data(payload)) => Poll::Ready(Some(Ok(payload))),
+            Some(Event::Data(payload)) => {
+                if self.is_auto_release(stream) {
+                    // The window updates are only sent once enough capacity
+                    // has been released, so releasing every chunk is cheap.
+                    let sz = cmp::min(payload.len() as WindowSize, stream.in_flight_recv_data);
+                    if sz > 0 {
+                        self.release_in_flight(sz, stream, task);
+                    }
+                } else {
+                    stream.unreleased_recv_data = stream
+                        .unreleased_recv_data
+                        .saturating_add(payload.len() as WindowSize);
+                }
+
+                Poll::Ready(Some(Ok(payload)))
+            }
             Some(event) => {
                 // Frame is trailer
                 stream.pending_recv.push_front(&mut self.buffer, event);
@@ -1067,6 +1533,127 @@ impl Recv {
         }
     }
 
+    /// Handle an extension frame received on stream 0.
+    pub fn recv_extension_frame(&mut self, frame: frame::Unknown) {
+        if self.pending_extension.len() >= MAX_PENDING_EXTENSION_FRAMES {
+            tracing::debug!(?frame, "too many buffered extension frames; dropping");
+            return;
+        }
+
+        self.pending_extension.push_back(frame);
+        self.notify_extension_task();
+    }
+
+    /// Handle an extension frame received on a stream.
+    pub fn recv_stream_extension_frame(&mut self, frame: frame::Unknown, stream: &mut Stream) {
+        if stream.state.is_recv_closed() {
+            tracing::trace!(?frame, "ignoring extension frame on closed stream");
+            return;
+        }
+
+        if stream.pending_recv_extension.len() >= MAX_PENDING_EXTENSION_FRAMES {
+            tracing::debug!(?frame, "too many buffered extension frames; dropping");
+            return;
+        }
+
+        stream.pending_recv_extension.push_back(frame);
+        stream.notify_recv();
+    }
+
+    pub fn poll_extension_frame(&mut self, cx: &Context) -> Poll<frame::Unknown> {
+        match self.pending_extension.pop_front() {
+            Some(frame) => Poll::Ready(frame),
+            None => {
+                self.extension_task = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    pub fn poll_stream_extension_frame(
+        &mut self,
+        cx: &Context,
+        stream: &mut Stream,
+    ) -> Poll<Option<Result<frame::Unknown, proto::Error>>> {
+        if let Some(frame) = stream.pending_recv_extension.pop_front() {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+
+        if stream.state.ensure_recv_open()? {
+            stream.recv_extension_task = Some(cx.waker().clone());
+            Poll::Pending
+        } else {
+            Poll::Ready(None)
+        }
+    }
+
+    /// Handle an `ALTSVC` frame that applies to the connection or one of our
+    /// streams.
+    pub fn recv_alt_svc(&mut self, frame: frame::AltSvc) {
+        if self.pending_alt_svc.len() >= MAX_PENDING_EXTENSION_FRAMES {
+            tracing::debug!(?frame, "too many buffered ALTSVC frames; dropping");
+            return;
+        }
+
+        self.pending_alt_svc.push_back(frame);
+
+        if let Some(task) = self.alt_svc_task.take() {
+            task.wake();
+        }
+    }
+
+    /// Handle an `ORIGIN` frame, adding its origins to the origin set.
+    pub fn recv_origin(&mut self, frame: frame::Origin) {
+        let origin_set = self.origin_set.get_or_insert_with(Vec::new);
+
+        for origin in frame.into_origins() {
+            let origin = match String::from_utf8(origin.to_vec()) {
+                Ok(origin) if origin.is_ascii() && !origin.is_empty() => origin,
+                _ => {
+                    tracing::debug!(?origin, "ignoring invalid origin");
+                    continue;
+                }
+            };
+
+            if origin_set.contains(&origin) {
+                continue;
+            }
+
+            if origin_set.len() >= MAX_ORIGIN_SET_LEN {
+                tracing::debug!("origin set is full; ignoring remaining origins");
+                return;
+            }
+
+            origin_set.push(origin);
+        }
+    }
+
+    pub fn origin_set(&self) -> Option<&[String]> {
+        self.origin_set.as_deref()
+    }
+
+    pub fn poll_alt_svc(&mut self, cx: &Context) -> Poll<frame::AltSvc> {
+        match self.pending_alt_svc.pop_front() {
+            Some(frame) => Poll::Ready(frame),
+            None => {
+                self.alt_svc_task = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Wakes the tasks waiting for connection level extension frames, e.g.
+    /// once the connection is closed.
+    pub fn notify_extension_task(&mut self) {
+        if let Some(task) = self.extension_task.take() {
+            task.wake();
+        }
+
+        if let Some(task) = self.alt_svc_task.take() {
+            task.wake();
+        }
+    }
+
     fn schedule_recv<T>(
         &mut self,
         cx: &Context,
@@ -1083,6 +1670,32 @@ impl Recv {
     }
 }
 
+/// Returns the increment of the next `WINDOW_UPDATE` frame for `stream`, and
+/// grows its window accordingly.
+///
+/// The stream window, and the data received but not released yet, stay
+/// within `share`, the stream's part of the memory budget. The rest of the
+/// capacity is handed out once the stream releases data.
+fn claim_stream_window_update(share: WindowSize, stream: &mut Stream) -> Option<WindowSize> {
+    let incr = stream.recv_flow.unclaimed_capacity()?;
+
+    let used = stream.recv_flow.window_size() + stream.in_flight_recv_data;
+    let incr = cmp::min(incr, share.saturating_sub(used));
+
+    if incr == 0 {
+        tracing::trace!(?stream.id, share, used, "window update over budget");
+        return None;
+    }
+
+    // Update flow control
+    stream
+        .recv_flow
+        .inc_window(incr)
+        .expect("unexpected flow control state");
+
+    Some(incr)
+}
+
 // ===== impl Open =====
 
 impl Open {
@@ -3,14 +3,17 @@ use super::{
     StreamIdOverflow, WindowSize,
 };
 use crate::codec::{RecvError, UserError};
-use crate::frame::{self, Reason};
+use crate::ext;
+use crate::frame::{self, FrameSize, Reason};
 
-use bytes::Buf;
+use bytes::{Buf, Bytes};
 use http;
+use std::sync::Arc;
 use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 use tokio::io::AsyncWrite;
 
-use std::io;
+use std::{io, mem};
 
 /// Manages state transitions related to outbound frames.
 #[derive(Debug)]
@@ -34,6 +37,19 @@ pub(super) struct Send {
     prioritize: Prioritize,
 
     is_push_enabled: bool,
+
+    /// If extended connect protocol is enabled.
+    is_extended_connect_protocol_enabled: bool,
+
+    /// The largest frame payload the remote accepts.
+    max_frame_size: FrameSize,
+
+    /// Every setting received from the remote so far.
+    remote_settings: Arc<frame::Settings>,
+
+    /// How long to hold back the body of a request sent with
+    /// `Expect: 100-continue`, if at all.
+    expect_continue_timeout: Option<Duration>,
 }
 
 /// A value to detect which public API has called `poll_reset`.
@@ -52,9 +68,52 @@ impl Send {
             next_stream_id: Ok(config.local_next_stream_id),
             prioritize: Prioritize::new(config),
             is_push_enabled: true,
+            is_extended_connect_protocol_enabled: false,
+            max_frame_size: frame::DEFAULT_MAX_FRAME_SIZE,
+            remote_settings: Arc::default(),
+            expect_continue_timeout: config.expect_continue_timeout,
+        }
+    }
+
+    /// Returns when to release the body of a request, if it must be held
+    /// back until the server responds to `Expect: 100-continue`.
+    pub fn continue_deadline(&self, request: &http::Request<()>) -> Option<Instant> {
+        let timeout = self.expect_continue_timeout?;
+        let expects_continue = match request.headers().get(http::header::EXPECT) {
+            Some(value) => value.as_bytes().eq_ignore_ascii_case(b"100-continue"),
+            None => false,
+        };
+
+        if expects_continue {
+            Some(Instant::now() + timeout)
+        } else {
+            None
         }
     }
 
+    /// Holds back the body of a request sent with `Expect: 100-continue`
+    /// until `deadline`, unless the server responds first.
+    pub fn hold_continue(&mut self, deadline: Instant, stream: &mut store::Ptr) {
+        self.prioritize.hold_continue(deadline, stream)
+    }
+
+    /// Releases the bodies held back for `Expect: 100-continue` whose deadline
+    /// has passed.
+    pub fn poll_expired_continues(
+        &mut self,
+        cx: &mut Context,
+        store: &mut Store,
+        task: &mut Option<Waker>,
+    ) {
+        self.prioritize.poll_expired_continues(cx, store, task)
+    }
+
+    /// Stops holding back the body of a request sent with
+    /// `Expect: 100-continue`.
+    pub fn release_continue(&mut self, stream: &mut store::Ptr, task: &mut Option<Waker>) {
+        self.prioritize.release_continue(stream, task)
+    }
+
     /// Returns the initial send window size
     pub fn init_window_sz(&self) -> WindowSize {
         self.init_window_sz
@@ -150,6 +209,8 @@ impl Send {
             }
         }
 
+        stream.is_pending_headers = true;
+
         // Queue the frame for sending
         self.prioritize
             .queue_frame(frame.into(), buffer, stream, task);
@@ -157,6 +218,27 @@ impl Send {
         Ok(())
     }
 
+    /// Send an informational (1xx) response, which doesn't change the state
+    /// of the stream.
+    pub fn send_informational<B>(
+        &mut self,
+        frame: frame::Headers,
+        buffer: &mut Buffer<Frame<B>>,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        tracing::trace!("send_informational; frame={:?}", frame);
+
+        Self::check_headers(frame.fields())?;
+
+        stream.state.ensure_can_send_informational()?;
+
+        self.prioritize
+            .queue_frame(frame.into(), buffer, stream, task);
+
+        Ok(())
+    }
+
     /// Send an explicit RST_STREAM frame
     pub fn send_reset<B>(
         &mut self,
@@ -278,6 +360,153 @@ impl Send {
         Ok(())
     }
 
+    /// Queues a `WINDOW_UPDATE` frame behind the frames already queued on
+    /// the stream.
+    pub fn send_window_update<B>(
+        &mut self,
+        frame: frame::WindowUpdate,
+        buffer: &mut Buffer<Frame<B>>,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) {
+        tracing::trace!("send_window_update -- queuing; frame={:?}", frame);
+        self.prioritize
+            .queue_frame(frame.into(), buffer, stream, task);
+    }
+
+    pub fn send_extension_frame<B>(
+        &mut self,
+        frame: frame::Unknown,
+        buffer: &mut Buffer<Frame<B>>,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        if frame.payload().len() > self.max_frame_size as usize {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        if stream.state.is_send_closed() {
+            return Err(UserError::InactiveStreamId);
+        }
+
+        tracing::trace!("send_extension_frame -- queuing; frame={:?}", frame);
+        self.prioritize
+            .queue_frame(frame.into(), buffer, stream, task);
+
+        Ok(())
+    }
+
+    pub fn send_connection_extension_frame(
+        &mut self,
+        frame: frame::Unknown,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        if frame.payload().len() > self.max_frame_size as usize {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        self.prioritize.queue_extension_frame(frame.into(), task);
+
+        Ok(())
+    }
+
+    pub fn send_priority(
+        &mut self,
+        frame: frame::Priority,
+        stream: &mut Stream,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        if frame.dependency().dependency_id() == frame.stream_id() {
+            return Err(UserError::SelfDependency);
+        }
+
+        // A PRIORITY frame sent ahead of the HEADERS frame would be undone by
+        // it, so the dependency is sent in the HEADERS frame instead.
+        if stream.is_pending_headers {
+            tracing::trace!("send_priority -- updating HEADERS; frame={:?}", frame);
+            stream.pending_dependency = Some(frame.dependency().clone());
+            return Ok(());
+        }
+
+        // PRIORITY frames may be sent in any stream state, so they are not
+        // queued behind the other frames of the stream.
+        tracing::trace!("send_priority -- queuing; frame={:?}", frame);
+        self.prioritize.queue_extension_frame(frame.into(), task);
+
+        Ok(())
+    }
+
+    pub fn send_alt_svc<B>(
+        &mut self,
+        frame: frame::AltSvc,
+        buffer: &mut Buffer<Frame<B>>,
+        stream: &mut store::Ptr,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        if frame.payload_len() > self.max_frame_size as usize {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        if stream.state.is_send_closed() {
+            return Err(UserError::InactiveStreamId);
+        }
+
+        tracing::trace!("send_alt_svc -- queuing; frame={:?}", frame);
+        self.prioritize
+            .queue_frame(frame.into(), buffer, stream, task);
+
+        Ok(())
+    }
+
+    pub fn send_connection_alt_svc(
+        &mut self,
+        frame: frame::AltSvc,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        if frame.payload_len() > self.max_frame_size as usize {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        self.prioritize.queue_extension_frame(frame.into(), task);
+
+        Ok(())
+    }
+
+    /// Queue `ORIGIN` frames advertising `origins`, using as many frames as
+    /// needed to stay within the max frame size.
+    pub fn send_origin(
+        &mut self,
+        origins: Vec<Bytes>,
+        task: &mut Option<Waker>,
+    ) -> Result<(), UserError> {
+        let max_len = self.max_frame_size as usize;
+
+        if origins.iter().any(|origin| 2 + origin.len() > max_len) {
+            return Err(UserError::PayloadTooBig);
+        }
+
+        let mut batch = Vec::new();
+        let mut len = 0;
+
+        for origin in origins {
+            if len + 2 + origin.len() > max_len {
+                let frame = frame::Origin::new(mem::take(&mut batch));
+                self.prioritize.queue_extension_frame(frame.into(), task);
+                len = 0;
+            }
+
+            len += 2 + origin.len();
+            batch.push(origin);
+        }
+
+        if !batch.is_empty() {
+            let frame = frame::Origin::new(batch);
+            self.prioritize.queue_extension_frame(frame.into(), task);
+        }
+
+        Ok(())
+    }
+
     pub fn poll_complete<T, B>(
         &mut self,
         cx: &mut Context,
@@ -325,14 +554,7 @@ impl Send {
 
     /// Current available stream send capacity
     pub fn capacity(&self, stream: &mut store::Ptr) -> WindowSize {
-        let available = stream.send_flow.available().as_size();
-        let buffered = stream.buffered_send_data;
-
-        if available <= buffered {
-            0
-        } else {
-            available - buffered
-        }
+        stream.capacity(self.prioritize.max_buffer_size())
     }
 
     pub fn poll_reset(
@@ -379,6 +601,27 @@ impl Send {
         Ok(())
     }
 
+    pub fn recv_priority(
+        &mut self,
+        id: StreamId,
+        dependency: &frame::StreamDependency,
+        store: &Store,
+    ) {
+        self.prioritize.recv_priority(id, dependency, store);
+    }
+
+    pub fn set_priority(&mut self, stream: &mut Stream, priority: ext::Priority) {
+        self.prioritize.set_priority(stream, priority);
+    }
+
+    pub fn buffer_priority_update(&mut self, id: StreamId, priority: ext::Priority) {
+        self.prioritize.buffer_priority_update(id, priority);
+    }
+
+    pub fn take_priority_update(&mut self, id: StreamId) -> Option<ext::Priority> {
+        self.prioritize.take_priority_update(id)
+    }
+
     pub(super) fn recv_go_away(&mut self, last_stream_id: StreamId) -> Result<(), RecvError> {
         if last_stream_id > self.max_stream_id {
             // The remote endpoint sent a `GOAWAY` frame indicating a stream
@@ -499,6 +742,20 @@ impl Send {
             self.is_push_enabled = val
         }
 
+        if let Some(val) = settings.is_extended_connect_protocol_enabled() {
+            self.is_extended_connect_protocol_enabled = val;
+        }
+
+        if let Some(val) = settings.max_frame_size() {
+            self.max_frame_size = val;
+        }
+
+        if let Some(true) = settings.is_rfc7540_priorities_disabled() {
+            self.prioritize.disable_rfc7540_priorities();
+        }
+
+        Arc::make_mut(&mut self.remote_settings).merge(settings);
+
         Ok(())
     }
 
@@ -506,6 +763,7 @@ impl Send {
         self.prioritize.clear_pending_capacity(store, counts);
         self.prioritize.clear_pending_send(store, counts);
         self.prioritize.clear_pending_open(store, counts);
+        self.prioritize.clear_pending_continue(store);
     }
 
     pub fn ensure_not_idle(&self, id: StreamId) -> Result<(), Reason> {
@@ -524,6 +782,14 @@ impl Send {
             .map_err(|_| UserError::OverflowedStreamId)
     }
 
+    pub(crate) fn remote_settings(&self) -> &Arc<frame::Settings> {
+        &self.remote_settings
+    }
+
+    pub(crate) fn is_extended_connect_protocol_enabled(&self) -> bool {
+        self.is_extended_connect_protocol_enabled
+    }
+
     pub fn may_have_created_stream(&self, id: StreamId) -> bool {
         if let Ok(next_id) = self.next_stream_id {
             // Peer::is_local_init should have been called beforehand
@@ -130,6 +130,19 @@ impl State {
         Ok(())
     }
 
+    /// Returns `Ok` if an informational (1xx) response may be sent, which is
+    /// only the case before the final response headers.
+    pub fn ensure_can_send_informational(&self) -> Result<(), UserError> {
+        match self.inner {
+            Open {
+                local: AwaitingHeaders,
+                ..
+            }
+            | HalfClosedRemote(AwaitingHeaders) => Ok(()),
+            _ => Err(UnexpectedFrameType),
+        }
+    }
+
     /// Opens the receive-half of the stream when a HEADERS frame is received.
     ///
     /// Returns true if this transitions the state to Open.
@@ -296,7 +309,7 @@ impl State {
             _ => {
                 tracing::trace!("recv_err; err={:?}", err);
                 self.inner = Closed(match *err {
-                    Proto(reason) => Cause::LocallyReset(reason),
+                    Proto(reason) | GoAway(_, reason, _) => Cause::LocallyReset(reason),
                     Io(..) => Cause::Io,
                 });
             }
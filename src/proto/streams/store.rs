@@ -103,6 +103,10 @@ impl Store {
         })
     }
 
+    pub fn contains_id(&self, id: &StreamId) -> bool {
+        self.ids.contains_key(id)
+    }
+
     pub fn insert(&mut self, id: StreamId, val: Stream) -> Ptr {
         let index = SlabIndex(self.slab.insert(val) as u32);
         assert!(self.ids.insert(id, index).is_none());
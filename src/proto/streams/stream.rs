@@ -1,5 +1,11 @@
 use super::*;
 
+use crate::ext;
+use crate::frame;
+
+use std::cmp;
+use std::collections::VecDeque;
+
 use std::task::{Context, Waker};
 use std::time::Instant;
 use std::usize;
@@ -69,9 +75,23 @@ pub(super) struct Stream {
     /// Set to true when the stream is pending to be opened
     pub is_pending_open: bool,
 
+    /// Set while the body of a request sent with `Expect: 100-continue` is
+    /// held back, to when it should be sent anyway.
+    pub continue_deadline: Option<Instant>,
+
     /// Set to true when a push is pending for this stream
     pub is_pending_push: bool,
 
+    /// Set to true while the HEADERS frame opening the stream is queued
+    pub is_pending_headers: bool,
+
+    /// Dependency to send in the queued HEADERS frame, set when the stream is
+    /// reprioritized before its HEADERS frame is written
+    pub pending_dependency: Option<frame::StreamDependency>,
+
+    /// The extensible priority of the stream, used to schedule sending DATA
+    pub priority: ext::Priority,
+
     // ===== Fields related to receiving =====
     /// Next node in the accept linked list
     pub next_pending_accept: Option<store::Key>,
@@ -84,6 +104,17 @@ pub(super) struct Stream {
 
     pub in_flight_recv_data: WindowSize,
 
+    /// Whether capacity is released as soon as data is handed to the
+    /// application, overriding the connection default if set
+    pub auto_release_capacity: Option<bool>,
+
+    /// Data handed to the application whose capacity it has not released
+    /// yet, while capacity was not released automatically
+    pub unreleased_recv_data: WindowSize,
+
+    /// Set when the application chose the receive window of this stream
+    pub is_recv_window_set: bool,
+
     /// Next node in the linked list of streams waiting to send window updates.
     pub next_window_update: Option<store::Key>,
 
@@ -102,6 +133,18 @@ pub(super) struct Stream {
     /// Task tracking receiving frames
     pub recv_task: Option<Waker>,
 
+    /// Task tracking receiving informational responses
+    pub informational_task: Option<Waker>,
+
+    /// Number of informational responses in `pending_recv`
+    pub num_pending_informational: usize,
+
+    /// Extension frames received on this stream, waiting to be read
+    pub pending_recv_extension: VecDeque<frame::Unknown>,
+
+    /// Task tracking receiving extension frames
+    pub recv_extension_task: Option<Waker>,
+
     /// The stream's pending push promises
     pub pending_push_promises: store::Queue<NextAccept>,
 
@@ -167,20 +210,31 @@ impl Stream {
             next_pending_send_capacity: None,
             send_capacity_inc: false,
             is_pending_open: false,
+            continue_deadline: None,
+            is_pending_headers: false,
+            pending_dependency: None,
             next_open: None,
             is_pending_push: false,
+            priority: ext::Priority::default(),
 
             // ===== Fields related to receiving =====
             next_pending_accept: None,
             is_pending_accept: false,
             recv_flow,
             in_flight_recv_data: 0,
+            auto_release_capacity: None,
+            unreleased_recv_data: 0,
+            is_recv_window_set: false,
             next_window_update: None,
             is_pending_window_update: false,
             reset_at: None,
             next_reset_expire: None,
             pending_recv: buffer::Deque::new(),
             recv_task: None,
+            informational_task: None,
+            num_pending_informational: 0,
+            pending_recv_extension: VecDeque::new(),
+            recv_extension_task: None,
             pending_push_promises: store::Queue::new(),
             content_length: ContentLength::Omitted,
         }
@@ -260,8 +314,19 @@ impl Stream {
         self.ref_count == 0 && !self.state.is_closed()
     }
 
-    pub fn assign_capacity(&mut self, capacity: WindowSize) {
+    /// Returns the amount of data that can be sent on the stream without
+    /// exceeding either its window or `max_buffer_size`.
+    pub fn capacity(&self, max_buffer_size: usize) -> WindowSize {
+        let available = self.send_flow.available().as_size() as usize;
+        let buffered = self.buffered_send_data as usize;
+
+        cmp::min(available, max_buffer_size).saturating_sub(buffered) as WindowSize
+    }
+
+    pub fn assign_capacity(&mut self, capacity: WindowSize, max_buffer_size: usize) {
         debug_assert!(capacity > 0);
+        let prev_capacity = self.capacity(max_buffer_size);
+
         self.send_capacity_inc = true;
         self.send_flow.assign_capacity(capacity);
 
@@ -272,13 +337,32 @@ impl Stream {
             self.id
         );
 
-        // Only notify if the capacity exceeds the amount of buffered data
-        if self.send_flow.available() > self.buffered_send_data {
+        // Only notify if the stream can send more than it could before
+        if self.capacity(max_buffer_size) > prev_capacity {
             tracing::trace!("  notifying task");
             self.notify_send();
         }
     }
 
+    /// Accounts for `len` bytes of buffered data, and `flow_len` bytes of
+    /// window, being sent.
+    pub fn send_data(&mut self, len: WindowSize, flow_len: WindowSize, max_buffer_size: usize) {
+        let prev_capacity = self.capacity(max_buffer_size);
+
+        self.send_flow.send_data(flow_len);
+
+        // Decrement the stream's buffered data counter
+        debug_assert!(self.buffered_send_data >= len);
+        self.buffered_send_data -= len;
+        self.requested_send_capacity -= len;
+
+        // Sending frees room in the buffer, which may have been the limit
+        if self.capacity(max_buffer_size) > prev_capacity {
+            self.send_capacity_inc = true;
+            self.notify_send();
+        }
+    }
+
     /// Returns `Err` when the decrement cannot be completed due to overflow.
     pub fn dec_content_length(&mut self, len: usize) -> Result<(), ()> {
         match self.content_length {
@@ -319,6 +403,14 @@ impl Stream {
         if let Some(task) = self.recv_task.take() {
             task.wake();
         }
+
+        if let Some(task) = self.informational_task.take() {
+            task.wake();
+        }
+
+        if let Some(task) = self.recv_extension_task.take() {
+            task.wake();
+        }
     }
 }
 
@@ -2,6 +2,7 @@ use super::recv::RecvHeaderBlockError;
 use super::store::{self, Entry, Resolve, Store};
 use super::{Buffer, Config, Counts, Prioritized, Recv, Send, Stream, StreamId};
 use crate::codec::{Codec, RecvError, SendError, UserError};
+use crate::ext::{self, Protocol};
 use crate::frame::{self, Frame, Reason};
 use crate::proto::{peer, Open, Peer, WindowSize};
 use crate::{client, proto, server};
@@ -93,6 +94,15 @@ struct Actions {
 
     /// If the connection errors, a copy is kept for any StreamRefs.
     conn_error: Option<proto::Error>,
+
+    /// Set once the first SETTINGS frame from the remote has been applied.
+    is_remote_settings_received: bool,
+
+    /// Set once the remote has acknowledged our first SETTINGS frame.
+    is_local_settings_acked: bool,
+
+    /// Task waiting for the initial SETTINGS exchange to complete.
+    settings_task: Option<Waker>,
 }
 
 /// Contains the buffer of frames to be written to the wire.
@@ -168,6 +178,28 @@ where
             .clear_expired_reset_streams(&mut me.store, &mut me.counts);
     }
 
+    /// Releases request bodies held back for `Expect: 100-continue` when the
+    /// server did not respond in time.
+    pub fn poll_expired_continues(&mut self, cx: &mut Context) {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+        me.actions
+            .send
+            .poll_expired_continues(cx, &mut me.store, &mut me.actions.task);
+    }
+
+    /// Cancels cached pushed streams that were not claimed in time, and
+    /// registers `cx` to be woken up when the next one expires.
+    pub fn poll_expired_pushes(&mut self, cx: &mut Context) {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        while let Some(key) = me.actions.recv.poll_expired_push(cx) {
+            tracing::trace!("push cache entry expired; stream={:?}", key);
+            me.evict_push(key);
+        }
+    }
+
     pub fn poll_complete<T>(
         &mut self,
         cx: &mut Context,
@@ -195,14 +227,24 @@ where
             &mut me.store,
             &mut me.counts,
             &mut me.actions.task,
-        )
+        )?;
+
+        me.actions.is_remote_settings_received = true;
+        me.actions.notify_settings_task();
+
+        Ok(())
     }
 
     pub fn apply_local_settings(&mut self, frame: &frame::Settings) -> Result<(), RecvError> {
         let mut me = self.inner.lock().unwrap();
         let me = &mut *me;
 
-        me.actions.recv.apply_local_settings(frame, &mut me.store)
+        me.actions.recv.apply_local_settings(frame, &mut me.store)?;
+
+        me.actions.is_local_settings_acked = true;
+        me.actions.notify_settings_task();
+
+        Ok(())
     }
 
     pub fn send_request(
@@ -214,6 +256,10 @@ where
         use super::stream::ContentLength;
         use http::Method;
 
+        let protocol = request.extensions_mut().remove::<Protocol>();
+        let dependency = request.extensions_mut().remove::<ext::StreamDependency>();
+        let window = request.extensions_mut().remove::<ext::InitialWindowSize>();
+
         // Clear before taking lock, incase extensions contain a StreamRef.
         request.extensions_mut().clear();
 
@@ -248,6 +294,33 @@ where
             return Err(UserError::UnexpectedFrameType.into());
         }
 
+        // A request without a body may be answered by a stream the server
+        // already pushed, in which case no new stream is opened.
+        if end_of_stream && protocol.is_none() {
+            if let Some(key) = me.actions.recv.take_cached_push(&request, &me.store) {
+                let mut stream = me.store.resolve(key);
+                tracing::trace!("send_request; using pushed stream={:?}", stream.id);
+
+                // The reference held by the cache is handed over to the
+                // returned `StreamRef`.
+                let opaque = OpaqueStreamRef::new(self.inner.clone(), &mut stream);
+                stream.ref_dec();
+                me.refs += 1;
+
+                return Ok(StreamRef {
+                    opaque,
+                    send_buffer: self.send_buffer.clone(),
+                });
+            }
+        }
+
+        // The `:protocol` pseudo-header may only be sent once the peer has
+        // advertised SETTINGS_ENABLE_CONNECT_PROTOCOL.
+        if protocol.is_some() && !me.actions.send.is_extended_connect_protocol_enabled() {
+            tracing::debug!("send_request; extended CONNECT protocol is not enabled by peer");
+            return Err(UserError::MalformedHeaders.into());
+        }
+
         let stream_id = me.actions.send.open()?;
 
         let mut stream = Stream::new(
@@ -260,8 +333,19 @@ where
             stream.content_length = ContentLength::Head;
         }
 
+        let continue_deadline = if end_of_stream {
+            None
+        } else {
+            me.actions.send.continue_deadline(&request)
+        };
+
         // Convert the message
-        let headers = client::Peer::convert_send_message(stream_id, request, end_of_stream)?;
+        let mut headers =
+            client::Peer::convert_send_message(stream_id, request, protocol, end_of_stream)?;
+
+        if let Some(dependency) = dependency {
+            headers.set_stream_dep(dependency.to_frame());
+        }
 
         let mut stream = me.store.insert(stream.id, stream);
 
@@ -285,6 +369,29 @@ where
         // closed state.
         debug_assert!(!stream.state.is_closed());
 
+        if let Some(deadline) = continue_deadline {
+            me.actions.send.hold_continue(deadline, &mut stream);
+        }
+
+        if let Some(window) = window {
+            me.actions
+                .recv
+                .set_stream_target_window(window.size(), &mut stream);
+
+            if let Some(frame) = me
+                .actions
+                .recv
+                .take_stream_window_update(&mut stream, &me.counts)
+            {
+                me.actions.send.send_window_update(
+                    frame,
+                    send_buffer,
+                    &mut stream,
+                    &mut me.actions.task,
+                );
+            }
+        }
+
         // TODO: ideally, OpaqueStreamRefs::new would do this, but we're holding
         // the lock, so it can't.
         me.refs += 1;
@@ -311,7 +418,7 @@ impl<B> DynStreams<'_, B> {
     pub fn recv_reset(&mut self, frame: frame::Reset) -> Result<(), RecvError> {
         let mut me = self.inner.lock().unwrap();
 
-        me.recv_reset(&self.send_buffer, frame)
+        me.recv_reset(self.peer, &self.send_buffer, frame)
     }
 
     /// Handle a received error and return the ID of the last processed stream.
@@ -329,6 +436,16 @@ impl<B> DynStreams<'_, B> {
         self.inner.lock().unwrap().actions.recv.last_processed_id()
     }
 
+    /// Grows the receive windows to `size`, as estimated from the
+    /// bandwidth-delay product.
+    pub fn grow_recv_windows(&mut self, size: WindowSize) {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+        me.actions
+            .recv
+            .grow_target_windows(size, &mut me.store, &mut me.actions.task);
+    }
+
     pub fn recv_window_update(&mut self, frame: frame::WindowUpdate) -> Result<(), RecvError> {
         let mut me = self.inner.lock().unwrap();
         me.recv_window_update(&self.send_buffer, frame)
@@ -339,6 +456,38 @@ impl<B> DynStreams<'_, B> {
         me.recv_push_promise(&self.send_buffer, frame)
     }
 
+    pub fn recv_priority(&mut self, frame: frame::Priority) -> Result<(), RecvError> {
+        let mut me = self.inner.lock().unwrap();
+        me.recv_priority(frame)
+    }
+
+    pub fn recv_priority_update(&mut self, frame: frame::PriorityUpdate) -> Result<(), RecvError> {
+        let mut me = self.inner.lock().unwrap();
+        me.recv_priority_update(self.peer, frame)
+    }
+
+    pub fn recv_origin(&mut self, frame: frame::Origin) {
+        let mut me = self.inner.lock().unwrap();
+
+        // Only clients make use of the origin set (RFC 8336, section 2.1).
+        if self.peer.is_server() {
+            tracing::trace!(?frame, "ignoring ORIGIN frame received by server");
+            return;
+        }
+
+        me.actions.recv.recv_origin(frame);
+    }
+
+    pub fn recv_alt_svc(&mut self, frame: frame::AltSvc) {
+        let mut me = self.inner.lock().unwrap();
+        me.recv_alt_svc(self.peer, frame)
+    }
+
+    pub fn recv_unknown(&mut self, frame: frame::Unknown) {
+        let mut me = self.inner.lock().unwrap();
+        me.recv_unknown(frame)
+    }
+
     pub fn recv_eof(&mut self, clear_pending_accept: bool) -> Result<(), ()> {
         let mut me = self.inner.lock().map_err(|_| ())?;
         me.recv_eof(&self.send_buffer, clear_pending_accept)
@@ -364,6 +513,9 @@ impl Inner {
                 send: Send::new(&config),
                 task: None,
                 conn_error: None,
+                is_remote_settings_received: false,
+                is_local_settings_acked: false,
+                settings_task: None,
             },
             store: Store::new(),
             refs: 1,
@@ -378,9 +530,9 @@ impl Inner {
     ) -> Result<(), RecvError> {
         let id = frame.stream_id();
 
-        // The GOAWAY process has begun. All streams with a greater ID than
-        // specified as part of GOAWAY should be ignored.
-        if id > self.actions.recv.max_stream_id() {
+        // The GOAWAY process has begun. All streams initiated by the peer with
+        // a greater ID than specified as part of GOAWAY should be ignored.
+        if id > self.actions.recv.max_stream_id() && !peer.is_local_init(id) {
             tracing::trace!(
                 "id ({:?}) > max_stream_id ({:?}), ignoring HEADERS",
                 id,
@@ -418,12 +570,29 @@ impl Inner {
                     .open(id, Open::Headers, &mut self.counts)?
                 {
                     Some(stream_id) => {
-                        let stream = Stream::new(
+                        let mut stream = Stream::new(
                             stream_id,
                             self.actions.send.init_window_sz(),
                             self.actions.recv.init_window_sz(),
                         );
 
+                        // A `PRIORITY_UPDATE` received before the request
+                        // takes precedence over the `priority` header.
+                        let priority =
+                            self.actions
+                                .send
+                                .take_priority_update(stream_id)
+                                .or_else(|| {
+                                    frame
+                                        .fields()
+                                        .get("priority")
+                                        .map(|value| ext::Priority::parse(value.as_bytes()))
+                                });
+
+                        if let Some(priority) = priority {
+                            self.actions.send.set_priority(&mut stream, priority);
+                        }
+
                         e.insert(stream)
                     }
                     None => return Ok(()),
@@ -431,6 +600,10 @@ impl Inner {
             }
         };
 
+        if let Some(dependency) = frame.stream_dep() {
+            self.actions.send.recv_priority(id, dependency, &self.store);
+        }
+
         let stream = self.store.resolve(key);
 
         if stream.state.is_local_reset() {
@@ -453,6 +626,12 @@ impl Inner {
             );
 
             let res = if stream.state.is_recv_headers() {
+                // A `100 Continue` or a final response releases a request
+                // body held back for `Expect: 100-continue`.
+                if frame.is_continue() || !frame.is_informational() {
+                    actions.send.release_continue(stream, &mut actions.task);
+                }
+
                 match actions.recv.recv_headers(frame, stream, counts) {
                     Ok(()) => Ok(()),
                     Err(RecvHeaderBlockError::Oversize(resp)) => {
@@ -508,9 +687,10 @@ impl Inner {
         let stream = match self.store.find_mut(&id) {
             Some(stream) => stream,
             None => {
-                // The GOAWAY process has begun. All streams with a greater ID
-                // than specified as part of GOAWAY should be ignored.
-                if id > self.actions.recv.max_stream_id() {
+                // The GOAWAY process has begun. All streams initiated by the
+                // peer with a greater ID than specified as part of GOAWAY
+                // should be ignored.
+                if id > self.actions.recv.max_stream_id() && !peer.is_local_init(id) {
                     tracing::trace!(
                         "id ({:?}) > max_stream_id ({:?}), ignoring DATA",
                         id,
@@ -522,7 +702,7 @@ impl Inner {
                 if self.actions.may_have_forgotten_stream(peer, id) {
                     tracing::debug!("recv_data for old stream={:?}, sending STREAM_CLOSED", id,);
 
-                    let sz = frame.payload().len();
+                    let sz = frame.flow_controlled_len();
                     // This should have been enforced at the codec::FramedRead layer, so
                     // this is just a sanity check.
                     assert!(sz <= super::MAX_WINDOW_SIZE as usize);
@@ -545,8 +725,8 @@ impl Inner {
         let send_buffer = &mut *send_buffer;
 
         self.counts.transition(stream, |counts, stream| {
-            let sz = frame.payload().len();
-            let res = actions.recv.recv_data(frame, stream);
+            let sz = frame.flow_controlled_len();
+            let res = actions.recv.recv_data(frame, stream, &mut actions.task);
 
             // Any stream error after receiving a DATA frame means
             // we won't give the data to the user, and so they can't
@@ -562,6 +742,7 @@ impl Inner {
 
     fn recv_reset<B>(
         &mut self,
+        peer: peer::Dyn,
         send_buffer: &SendBuffer<B>,
         frame: frame::Reset,
     ) -> Result<(), RecvError> {
@@ -572,9 +753,9 @@ impl Inner {
             return Err(RecvError::Connection(Reason::PROTOCOL_ERROR));
         }
 
-        // The GOAWAY process has begun. All streams with a greater ID than
-        // specified as part of GOAWAY should be ignored.
-        if id > self.actions.recv.max_stream_id() {
+        // The GOAWAY process has begun. All streams initiated by the peer with
+        // a greater ID than specified as part of GOAWAY should be ignored.
+        if id > self.actions.recv.max_stream_id() && !peer.is_local_init(id) {
             tracing::trace!(
                 "id ({:?}) > max_stream_id ({:?}), ignoring RST_STREAM",
                 id,
@@ -647,6 +828,94 @@ impl Inner {
         Ok(())
     }
 
+    fn recv_priority(&mut self, frame: frame::Priority) -> Result<(), RecvError> {
+        let id = frame.stream_id();
+
+        if id.is_zero() {
+            proto_err!(conn: "recv_priority: invalid stream ID 0");
+            return Err(RecvError::Connection(Reason::PROTOCOL_ERROR));
+        }
+
+        // PRIORITY frames may be received for a stream in any state,
+        // including idle streams, so the stream is not looked up.
+        self.actions
+            .send
+            .recv_priority(id, frame.dependency(), &self.store);
+
+        Ok(())
+    }
+
+    fn recv_priority_update(
+        &mut self,
+        peer: peer::Dyn,
+        frame: frame::PriorityUpdate,
+    ) -> Result<(), RecvError> {
+        if !peer.is_server() {
+            proto_err!(conn: "recv_priority_update: received PRIORITY_UPDATE as a client");
+            return Err(RecvError::Connection(Reason::PROTOCOL_ERROR));
+        }
+
+        let id = frame.prioritized_id();
+        let priority = ext::Priority::parse(frame.field_value());
+
+        if let Some(mut stream) = self.store.find_mut(&id) {
+            self.actions.send.set_priority(&mut stream, priority);
+        } else if !peer.is_local_init(id) && !self.actions.recv.may_have_created_stream(id) {
+            // The request has not been received yet.
+            self.actions.send.buffer_priority_update(id, priority);
+        }
+
+        // Otherwise the stream is closed, and the frame is ignored.
+        Ok(())
+    }
+
+    fn recv_alt_svc(&mut self, peer: peer::Dyn, frame: frame::AltSvc) {
+        // ALTSVC frames are meant for clients (RFC 7838, section 4), and must
+        // carry an origin if and only if they are sent on stream 0.
+        if peer.is_server() {
+            tracing::trace!(?frame, "ignoring ALTSVC frame received by server");
+            return;
+        }
+
+        let id = frame.stream_id();
+
+        if id.is_zero() == frame.origin().is_empty() {
+            tracing::trace!(?frame, "ignoring ALTSVC frame; invalid origin");
+            return;
+        }
+
+        if !id.is_zero() {
+            let may_have_created_stream = if peer.is_local_init(id) {
+                self.actions.send.may_have_created_stream(id)
+            } else {
+                self.actions.recv.may_have_created_stream(id)
+            };
+
+            if !may_have_created_stream {
+                tracing::trace!(?frame, "ignoring ALTSVC frame on idle stream");
+                return;
+            }
+        }
+
+        self.actions.recv.recv_alt_svc(frame);
+    }
+
+    fn recv_unknown(&mut self, frame: frame::Unknown) {
+        let id = frame.stream_id();
+
+        if id.is_zero() {
+            self.actions.recv.recv_extension_frame(frame);
+        } else if let Some(mut stream) = self.store.find_mut(&id) {
+            self.actions
+                .recv
+                .recv_stream_extension_frame(frame, &mut stream);
+        } else {
+            // Unknown frames must be ignored in any stream state, including
+            // idle and closed streams.
+            tracing::trace!(?frame, "ignoring extension frame; stream not found");
+        }
+    }
+
     fn recv_err<B>(&mut self, send_buffer: &SendBuffer<B>, err: &proto::Error) -> StreamId {
         let actions = &mut self.actions;
         let counts = &mut self.counts;
@@ -666,6 +935,7 @@ impl Inner {
             .unwrap();
 
         actions.conn_error = Some(err.shallow_clone());
+        actions.notify_connection_tasks();
 
         last_processed_id
     }
@@ -684,7 +954,7 @@ impl Inner {
 
         actions.send.recv_go_away(last_stream_id)?;
 
-        let err = frame.reason().into();
+        let err = proto::Error::GoAway(frame.clone_debug_data(), frame.reason(), last_stream_id);
 
         self.store
             .for_each(|stream| {
@@ -701,6 +971,7 @@ impl Inner {
             .unwrap();
 
         actions.conn_error = Some(err);
+        actions.notify_connection_tasks();
 
         Ok(())
     }
@@ -716,12 +987,13 @@ impl Inner {
         // First, ensure that the initiating stream is still in a valid state.
         let parent_key = match self.store.find_mut(&id) {
             Some(stream) => {
-                // The GOAWAY process has begun. All streams with a greater ID
-                // than specified as part of GOAWAY should be ignored.
-                if id > self.actions.recv.max_stream_id() {
+                // The GOAWAY process has begun. All pushed streams with a
+                // greater ID than specified as part of GOAWAY should be
+                // ignored.
+                if promised_id > self.actions.recv.max_stream_id() {
                     tracing::trace!(
-                        "id ({:?}) > max_stream_id ({:?}), ignoring PUSH_PROMISE",
-                        id,
+                        "promised_id ({:?}) > max_stream_id ({:?}), ignoring PUSH_PROMISE",
+                        promised_id,
                         self.actions.recv.max_stream_id()
                     );
                     return Ok(());
@@ -792,8 +1064,25 @@ impl Inner {
         };
         // If we're successful, push the headers and stream...
         if let Some(child) = child_key {
+            if self.actions.recv.is_push_cache_enabled() {
+                let mut stream = self.store.resolve(child);
+                let request = self.actions.recv.take_request(&mut stream);
+
+                // The cache keeps the stream alive until it is claimed or
+                // evicted.
+                stream.ref_inc();
+
+                if let Some(evicted) = self.actions.recv.cache_push(child, request) {
+                    tracing::trace!("push cache full; evicting stream={:?}", evicted);
+                    self.evict_push(evicted);
+                }
+
+                return Ok(());
+            }
+
             let mut ppp = self.store[parent_key].pending_push_promises.take();
             ppp.push(&mut self.store.resolve(child));
+            self.actions.recv.inc_num_pending_pushed();
 
             let parent = &mut self.store.resolve(parent_key);
             parent.pending_push_promises = ppp;
@@ -803,6 +1092,25 @@ impl Inner {
         Ok(())
     }
 
+    /// Drops the push cache's reference to a pushed stream, canceling it if
+    /// it is still open.
+    fn evict_push(&mut self, key: store::Key) {
+        let mut stream = self.store.resolve(key);
+        stream.ref_dec();
+
+        let actions = &mut self.actions;
+
+        self.counts.transition(stream, |counts, stream| {
+            maybe_cancel(stream, actions, counts);
+
+            if stream.ref_count == 0 {
+                actions
+                    .recv
+                    .release_closed_capacity(stream, &mut actions.task);
+            }
+        });
+    }
+
     fn recv_eof<B>(
         &mut self,
         send_buffer: &SendBuffer<B>,
@@ -817,6 +1125,8 @@ impl Inner {
             actions.conn_error = Some(io::Error::from(io::ErrorKind::BrokenPipe).into());
         }
 
+        actions.notify_connection_tasks();
+
         tracing::trace!("Streams::recv_eof");
 
         self.store
@@ -833,6 +1143,15 @@ impl Inner {
             .expect("recv_eof");
 
         actions.clear_queues(clear_pending_accept, &mut self.store, counts);
+
+        // Nothing can claim the cached pushed streams once the connection is
+        // gone.
+        if clear_pending_accept {
+            for key in self.actions.recv.drain_push_cache() {
+                self.evict_push(key);
+            }
+        }
+
         Ok(())
     }
 
@@ -916,6 +1235,33 @@ where
         }
         Poll::Ready(Ok(()))
     }
+
+    /// Opens the first stream in the half-closed (local) state, standing for
+    /// the request that was sent over HTTP/1.1 to upgrade the connection.
+    pub fn open_upgrade_stream(&mut self) -> Result<OpaqueStreamRef, SendError> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        if !me.counts.can_inc_num_send_streams() {
+            return Err(UserError::Rejected.into());
+        }
+
+        let stream_id = me.actions.send.open()?;
+
+        let stream = Stream::new(
+            stream_id,
+            me.actions.send.init_window_sz(),
+            me.actions.recv.init_window_sz(),
+        );
+
+        let mut stream = me.store.insert(stream.id, stream);
+        stream.state.send_open(true)?;
+        me.counts.inc_num_send_streams(&mut stream);
+
+        me.refs += 1;
+
+        Ok(OpaqueStreamRef::new(self.inner.clone(), &mut stream))
+    }
 }
 
 impl<B, P> Streams<B, P>
@@ -942,6 +1288,45 @@ where
         self.as_dyn().recv_eof(clear_pending_accept)
     }
 
+    pub(crate) fn is_extended_connect_protocol_enabled(&self) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .actions
+            .send
+            .is_extended_connect_protocol_enabled()
+    }
+
+    pub(crate) fn remote_settings(&self) -> Arc<frame::Settings> {
+        let me = self.inner.lock().unwrap();
+        me.actions.send.remote_settings().clone()
+    }
+
+    /// Returns the remote settings once the initial SETTINGS exchange has
+    /// completed: the remote's first SETTINGS frame has been received and ours
+    /// has been acknowledged.
+    pub(crate) fn poll_remote_settings(
+        &mut self,
+        cx: &Context,
+    ) -> Poll<Result<Arc<frame::Settings>, proto::Error>> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        if me.actions.is_remote_settings_received && me.actions.is_local_settings_acked {
+            return Poll::Ready(Ok(me.actions.send.remote_settings().clone()));
+        }
+
+        me.actions.ensure_no_conn_error()?;
+        me.actions.settings_task = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+
+    pub(crate) fn origin_set(&self) -> Option<Vec<String>> {
+        let me = self.inner.lock().unwrap();
+        me.actions.recv.origin_set().map(<[_]>::to_vec)
+    }
+
     pub(crate) fn max_send_streams(&self) -> usize {
         self.inner.lock().unwrap().counts.max_send_streams()
     }
@@ -961,6 +1346,78 @@ where
         me.counts.has_streams()
     }
 
+    pub(crate) fn num_open_streams(&self) -> usize {
+        self.inner.lock().unwrap().counts.num_open_streams()
+    }
+
+    pub fn send_extension_frame(&mut self, frame: ext::ExtensionFrame) -> Result<(), SendError> {
+        if frame.is_known_kind() {
+            return Err(UserError::UnexpectedFrameType.into());
+        }
+
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        me.actions.ensure_no_conn_error()?;
+        me.actions.send.send_connection_extension_frame(
+            frame.into_frame(StreamId::ZERO),
+            &mut me.actions.task,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn send_alt_svc(&mut self, frame: frame::AltSvc) -> Result<(), SendError> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        me.actions.ensure_no_conn_error()?;
+        me.actions
+            .send
+            .send_connection_alt_svc(frame, &mut me.actions.task)?;
+
+        Ok(())
+    }
+
+    pub fn send_origin(&mut self, origins: Vec<Bytes>) -> Result<(), SendError> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        me.actions.ensure_no_conn_error()?;
+        me.actions.send.send_origin(origins, &mut me.actions.task)?;
+
+        Ok(())
+    }
+
+    pub fn poll_alt_svc(&mut self, cx: &Context) -> Poll<Result<ext::AltSvc, proto::Error>> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        if let Poll::Ready(frame) = me.actions.recv.poll_alt_svc(cx) {
+            return Poll::Ready(Ok(frame.into()));
+        }
+
+        me.actions.ensure_no_conn_error()?;
+
+        Poll::Pending
+    }
+
+    pub fn poll_extension_frame(
+        &mut self,
+        cx: &Context,
+    ) -> Poll<Result<ext::ExtensionFrame, proto::Error>> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        if let Poll::Ready(frame) = me.actions.recv.poll_extension_frame(cx) {
+            return Poll::Ready(Ok(frame.into()));
+        }
+
+        me.actions.ensure_no_conn_error()?;
+
+        Poll::Pending
+    }
+
     pub fn has_streams_or_other_references(&self) -> bool {
         let me = self.inner.lock().unwrap();
         me.counts.has_streams() || me.refs > 1
@@ -1031,6 +1488,42 @@ impl<B> StreamRef<B> {
         })
     }
 
+    pub fn send_extension_frame(&mut self, frame: ext::ExtensionFrame) -> Result<(), UserError> {
+        if frame.is_known_kind() {
+            return Err(UserError::UnexpectedFrameType);
+        }
+
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+        let actions = &mut me.actions;
+        let mut send_buffer = self.send_buffer.inner.lock().unwrap();
+        let send_buffer = &mut *send_buffer;
+
+        let frame = frame.into_frame(stream.id);
+
+        actions
+            .send
+            .send_extension_frame(frame, send_buffer, &mut stream, &mut actions.task)
+    }
+
+    pub fn send_alt_svc(&mut self, value: Bytes) -> Result<(), UserError> {
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+        let actions = &mut me.actions;
+        let mut send_buffer = self.send_buffer.inner.lock().unwrap();
+        let send_buffer = &mut *send_buffer;
+
+        let frame = frame::AltSvc::new(stream.id, Bytes::new(), value);
+
+        actions
+            .send
+            .send_alt_svc(frame, send_buffer, &mut stream, &mut actions.task)
+    }
+
     pub fn send_trailers(&mut self, trailers: HeaderMap) -> Result<(), UserError> {
         let mut me = self.opaque.inner.lock().unwrap();
         let me = &mut *me;
@@ -1087,6 +1580,29 @@ impl<B> StreamRef<B> {
         })
     }
 
+    pub fn send_informational(&mut self, mut response: Response<()>) -> Result<(), UserError> {
+        let status = response.status();
+        if !status.is_informational() || status == http::StatusCode::SWITCHING_PROTOCOLS {
+            return Err(UserError::InvalidInformationalStatusCode);
+        }
+
+        // Clear before taking lock, incase extensions contain a StreamRef.
+        response.extensions_mut().clear();
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+        let actions = &mut me.actions;
+        let mut send_buffer = self.send_buffer.inner.lock().unwrap();
+        let send_buffer = &mut *send_buffer;
+
+        let frame = server::Peer::convert_send_message(stream.id, response, false);
+
+        actions
+            .send
+            .send_informational(frame, send_buffer, &mut stream, &mut actions.task)
+    }
+
     pub fn send_push_promise(
         &mut self,
         mut request: Request<()>,
@@ -1213,6 +1729,22 @@ impl<B> StreamRef<B> {
             .map_err(From::from)
     }
 
+    /// Returns the stream's current extensible priority.
+    pub fn priority(&self) -> ext::Priority {
+        let mut me = self.opaque.inner.lock().unwrap();
+        me.store.resolve(self.opaque.key).priority
+    }
+
+    /// Changes the stream's extensible priority.
+    pub fn set_priority(&mut self, priority: ext::Priority) {
+        let mut me = self.opaque.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.opaque.key);
+
+        me.actions.send.set_priority(&mut stream, priority)
+    }
+
     pub fn clone_to_opaque(&self) -> OpaqueStreamRef
     where
         B: 'static,
@@ -1253,6 +1785,16 @@ impl OpaqueStreamRef {
 
         me.actions.recv.poll_response(cx, &mut stream)
     }
+
+    /// Called by a client to check for a received informational response.
+    pub fn poll_informational(&mut self, cx: &Context) -> Poll<Option<Response<()>>> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.key);
+
+        me.actions.recv.poll_informational(cx, &mut stream)
+    }
     /// Called by a client to check for a pushed request.
     pub fn poll_pushed(
         &mut self,
@@ -1288,7 +1830,9 @@ impl OpaqueStreamRef {
 
         let mut stream = me.store.resolve(self.key);
 
-        me.actions.recv.poll_data(cx, &mut stream)
+        me.actions
+            .recv
+            .poll_data(cx, &mut stream, &mut me.actions.task)
     }
 
     pub fn poll_trailers(&mut self, cx: &Context) -> Poll<Option<Result<HeaderMap, proto::Error>>> {
@@ -1300,6 +1844,34 @@ impl OpaqueStreamRef {
         me.actions.recv.poll_trailers(cx, &mut stream)
     }
 
+    pub fn poll_extension_frame(
+        &mut self,
+        cx: &Context,
+    ) -> Poll<Option<Result<ext::ExtensionFrame, proto::Error>>> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.key);
+
+        me.actions
+            .recv
+            .poll_stream_extension_frame(cx, &mut stream)
+            .map_ok_(Into::into)
+    }
+
+    /// Sends a PRIORITY frame changing the dependency of the stream.
+    pub fn send_priority(&mut self, dependency: frame::StreamDependency) -> Result<(), UserError> {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.key);
+        let frame = frame::Priority::new(stream.id, dependency);
+
+        me.actions
+            .send
+            .send_priority(frame, &mut stream, &mut me.actions.task)
+    }
+
     pub(crate) fn available_recv_capacity(&self) -> isize {
         let me = self.inner.lock().unwrap();
         let me = &*me;
@@ -1329,6 +1901,29 @@ impl OpaqueStreamRef {
             .release_capacity(capacity, &mut stream, &mut me.actions.task)
     }
 
+    pub fn set_target_window_size(&mut self, size: WindowSize) {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.key);
+
+        me.actions.recv.set_stream_target_window(size, &mut stream);
+        me.actions
+            .recv
+            .schedule_stream_window_update(&mut stream, &mut me.actions.task);
+    }
+
+    pub fn set_auto_release_capacity(&mut self, enabled: bool) {
+        let mut me = self.inner.lock().unwrap();
+        let me = &mut *me;
+
+        let mut stream = me.store.resolve(self.key);
+
+        me.actions
+            .recv
+            .set_auto_release_capacity(&mut stream, enabled)
+    }
+
     pub(crate) fn clear_recv_buffer(&mut self) {
         let mut me = self.inner.lock().unwrap();
         let me = &mut *me;
@@ -1435,6 +2030,7 @@ fn drop_stream_ref(inner: &Mutex<Inner>, key: store::Key) {
             // We won't be able to reach our push promises anymore
             let mut ppp = stream.pending_push_promises.take();
             while let Some(promise) = ppp.pop(stream.store_mut()) {
+                actions.recv.dec_num_pending_pushed();
                 counts.transition(promise, |counts, stream| {
                     maybe_cancel(stream, actions, counts);
                 });
@@ -1464,6 +2060,19 @@ impl<B> SendBuffer<B> {
 // ===== impl Actions =====
 
 impl Actions {
+    /// Wakes the tasks waiting on connection level events, e.g. once the
+    /// connection is closed.
+    fn notify_connection_tasks(&mut self) {
+        self.recv.notify_extension_task();
+        self.notify_settings_task();
+    }
+
+    fn notify_settings_task(&mut self) {
+        if let Some(task) = self.settings_task.take() {
+            task.wake();
+        }
+    }
+
     fn send_reset<B>(
         &mut self,
         stream: store::Ptr,
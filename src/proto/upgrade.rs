@@ -0,0 +1,113 @@
+//! Support for the `HTTP2-Settings` header of an HTTP/1.1 `Upgrade: h2c`
+//! request, as defined in RFC 7540, section 3.2.1.
+//!
+//! The header carries the payload of a SETTINGS frame, encoded with the
+//! URL and filename safe base64 alphabet, trailing `=` omitted.
+
+use crate::frame::{self, Head, Kind, StreamId};
+
+use bytes::BytesMut;
+use http::header::HeaderValue;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes `settings` as the value of an `HTTP2-Settings` header.
+pub(crate) fn encode_settings(settings: &frame::Settings) -> HeaderValue {
+    let mut payload = BytesMut::new();
+    settings.encode_payload(&mut payload);
+
+    let mut dst = Vec::with_capacity((payload.len() * 4).div_ceil(3));
+
+    for chunk in payload.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+
+        for i in 0..=chunk.len() {
+            dst.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize]);
+        }
+    }
+
+    HeaderValue::from_bytes(&dst).expect("base64url is a valid header value")
+}
+
+/// Decodes the value of an `HTTP2-Settings` header.
+///
+/// Returns `None` if the value is not valid base64url or does not hold a
+/// valid SETTINGS payload.
+pub(crate) fn decode_settings(value: &HeaderValue) -> Option<frame::Settings> {
+    let src = value.as_bytes();
+    // Be lenient with peers that do not omit the padding.
+    let end = src.iter().rposition(|&b| b != b'=').map_or(0, |i| i + 1);
+    let src = &src[..end];
+
+    if src.len() % 4 == 1 {
+        tracing::debug!("invalid HTTP2-Settings length; len={}", src.len());
+        return None;
+    }
+
+    let mut payload = Vec::with_capacity(src.len() * 3 / 4);
+
+    for chunk in src.chunks(4) {
+        let mut n = 0u32;
+
+        for (i, &b) in chunk.iter().enumerate() {
+            let v = match ALPHABET.iter().position(|&c| c == b) {
+                Some(v) => v as u32,
+                None => {
+                    tracing::debug!("invalid HTTP2-Settings character; byte={:?}", b);
+                    return None;
+                }
+            };
+            n |= v << (18 - 6 * i);
+        }
+
+        for i in 0..chunk.len() - 1 {
+            payload.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    let head = Head::new(Kind::Settings, 0, StreamId::zero());
+
+    match frame::Settings::load(head, &payload) {
+        Ok(settings) => Some(settings),
+        Err(e) => {
+            tracing::debug!("invalid HTTP2-Settings payload; err={:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut settings = frame::Settings::default();
+        settings.set_enable_push(false);
+        settings.set_initial_window_size(Some(1_000));
+        settings.set_max_concurrent_streams(Some(100));
+
+        let value = encode_settings(&settings);
+        assert_eq!(value, "AAIAAAAAAAMAAABkAAQAAAPo");
+        assert_eq!(decode_settings(&value), Some(settings));
+    }
+
+    #[test]
+    fn decode_empty() {
+        let value = HeaderValue::from_static("");
+        assert_eq!(decode_settings(&value), Some(frame::Settings::default()));
+    }
+
+    #[test]
+    fn decode_invalid() {
+        // Not base64url
+        assert!(decode_settings(&HeaderValue::from_static("AAIAAAAA+AA=")).is_none());
+        // Not a multiple of 6 bytes
+        assert!(decode_settings(&HeaderValue::from_static("AAIAAAA")).is_none());
+        // ENABLE_PUSH must be 0 or 1
+        assert!(decode_settings(&HeaderValue::from_static("AAIAAAAC")).is_none());
+    }
+}
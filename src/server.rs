@@ -116,16 +116,19 @@
 //! [`TcpListener`]: https://docs.rs/tokio-core/0.1/tokio_core/net/struct.TcpListener.html
 
 use crate::codec::{Codec, RecvError, UserError};
+use crate::ext::{ExtensionFrame, GoAway, Priority};
 use crate::frame::{self, Pseudo, PushPromiseHeaderError, Reason, Settings, StreamId};
+use crate::hpack::BytesStr;
 use crate::proto::{self, Config, Prioritized};
-use crate::{FlowControl, PingPong, RecvStream, SendStream};
+use crate::timer::Time;
+use crate::{FlowControl, Padding, PingPong, RecvStream, RemoteSettings, SendStream, Timer};
 
 use bytes::{Buf, Bytes};
-use http::{HeaderMap, Method, Request, Response};
+use http::{HeaderMap, HeaderValue, Method, Request, Response};
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{convert, fmt, io, mem};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tracing::instrument::{Instrument, Instrumented};
@@ -150,10 +153,62 @@ pub struct Handshake<T, B: Buf = Bytes> {
     builder: Builder,
     /// The current state of the handshake.
     state: Handshaking<T, B>,
+    /// The client settings and request of an HTTP/1.1 upgrade, if any.
+    upgrade: Option<(Settings, frame::Headers)>,
+    /// Whether the client preface was already read while sniffing.
+    preface_read: bool,
     /// Span tracking the handshake
     span: tracing::Span,
 }
 
+/// In progress HTTP/2.0 handshake that first checks whether the client speaks
+/// HTTP/2.0 at all.
+///
+/// Nothing is written to the I/O resource until the client connection preface
+/// was read. If the client sends something else, the handshake stops as soon
+/// as a byte does not match the preface, and the I/O resource is handed back
+/// along with the bytes read from it, so that they can be passed to another
+/// protocol implementation, usually HTTP/1.1.
+///
+/// See [`Builder::sniff_handshake`] for more details.
+///
+/// [`Builder::sniff_handshake`]: struct.Builder.html#method.sniff_handshake
+#[must_use = "futures do nothing unless polled"]
+pub struct SniffHandshake<T, B: Buf = Bytes> {
+    /// The config to pass to the handshake once the preface is read.
+    builder: Builder,
+    /// The I/O resource, until the preface is read.
+    io: Option<T>,
+    /// The bytes read so far.
+    buf: [u8; 24],
+    pos: usize,
+    /// The handshake, once the preface is read.
+    handshake: Option<Handshake<T, B>>,
+}
+
+/// The outcome of a [`SniffHandshake`].
+///
+/// [`SniffHandshake`]: struct.SniffHandshake.html
+#[derive(Debug)]
+pub enum Sniffed<T, B: Buf = Bytes> {
+    /// The client sent the HTTP/2.0 connection preface and the handshake
+    /// completed.
+    ///
+    /// The connection is boxed, as it is much larger than the other variant.
+    Http2(Box<Connection<T, B>>),
+    /// The client did not send the HTTP/2.0 connection preface, or closed
+    /// the connection before sending all of it.
+    ///
+    /// `read` holds the bytes that were read from `io`, at most 24 of them.
+    /// They must be processed before anything else read from `io`.
+    Other {
+        /// The I/O resource.
+        io: T,
+        /// The bytes read from `io`.
+        read: Bytes,
+    },
+}
+
 /// Accepts inbound HTTP/2.0 streams on a connection.
 ///
 /// A `Connection` is backed by an I/O resource (usually a TCP socket) and
@@ -245,6 +300,31 @@ pub struct Builder {
 
     /// Initial target window size for new connections.
     initial_target_connection_window_size: Option<u32>,
+
+    /// Maximum window size reached by adaptive flow control, if enabled.
+    adaptive_window: Option<u32>,
+
+    /// Whether received data releases its capacity automatically.
+    auto_release_capacity: bool,
+
+    /// Maximum amount of data buffered on each stream, waiting to be sent.
+    max_send_buffer_size: usize,
+
+    /// Maximum amount of data buffered on the connection beyond the capacity
+    /// of its streams.
+    max_connection_send_buffer_size: usize,
+
+    /// Maximum amount of received data held by the connection, if limited.
+    recv_memory_budget: Option<u32>,
+
+    /// Origins to advertise in `ORIGIN` frames once connected.
+    origin_set: Vec<Bytes>,
+
+    /// Padding policy for outgoing frames.
+    padding: Padding,
+
+    /// Timer used to wake up connections when a deadline passes.
+    time: Time,
 }
 
 /// Send a response back to the client
@@ -378,6 +458,8 @@ where
             codec.set_max_recv_header_list_size(max as usize);
         }
 
+        codec.set_padding(builder.padding.clone());
+
         // Send initial settings frame.
         codec
             .buffer(builder.settings.clone().into())
@@ -391,6 +473,8 @@ where
         Handshake {
             builder,
             state,
+            upgrade: None,
+            preface_read: false,
             span,
         }
     }
@@ -506,7 +590,31 @@ where
     ///
     /// For graceful shutdowns, see [`graceful_shutdown`](Connection::graceful_shutdown).
     pub fn abrupt_shutdown(&mut self, reason: Reason) {
-        self.connection.go_away_from_user(reason);
+        self.abrupt_shutdown_with_debug_data(reason, Bytes::new());
+    }
+
+    /// Sets the connection to a GOAWAY state, sending `debug_data` with the
+    /// GOAWAY frame.
+    ///
+    /// The debug data is meant to help diagnose why the connection is closed;
+    /// it is truncated to fit in a single frame. See
+    /// [`abrupt_shutdown`](Connection::abrupt_shutdown) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::Reason;
+    /// # use h2::server::Connection;
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(connection: &mut Connection<T, bytes::Bytes>) {
+    /// connection.abrupt_shutdown_with_debug_data(
+    ///     Reason::INTERNAL_ERROR,
+    ///     "database unavailable".into(),
+    /// );
+    /// # }
+    /// ```
+    pub fn abrupt_shutdown_with_debug_data(&mut self, reason: Reason, debug_data: Bytes) {
+        self.connection.go_away_from_user(reason, debug_data);
     }
 
     /// Starts a [graceful shutdown][1] process.
@@ -520,7 +628,64 @@ where
     ///
     /// [1]: http://httpwg.org/specs/rfc7540.html#GOAWAY
     pub fn graceful_shutdown(&mut self) {
-        self.connection.go_away_gracefully();
+        self.graceful_shutdown_with_debug_data(Bytes::new());
+    }
+
+    /// Starts a graceful shutdown process, sending `debug_data` with the
+    /// GOAWAY frames.
+    ///
+    /// The debug data is meant to help diagnose why the connection is closed;
+    /// it is truncated to fit in a single frame. See
+    /// [`graceful_shutdown`](Connection::graceful_shutdown) for details.
+    pub fn graceful_shutdown_with_debug_data(&mut self, debug_data: Bytes) {
+        self.connection.go_away_gracefully(debug_data);
+    }
+
+    /// Starts a graceful shutdown process that must complete by `deadline`.
+    ///
+    /// This behaves like [`graceful_shutdown`], except that if streams are
+    /// still open once `deadline` has passed, the connection is closed with a
+    /// `GOAWAY` frame carrying `reason`, and the handles of the remaining
+    /// streams return an error with `reason` as well. Use [`num_open_streams`] to track how many
+    /// streams are left to drain.
+    ///
+    /// If a graceful shutdown was already started, this only sets the
+    /// deadline.
+    ///
+    /// The deadline is checked whenever the connection is polled. To close
+    /// the connection on time even if it is idle, set a [`timer`] on the
+    /// `Builder`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::Reason;
+    /// # use h2::server::Connection;
+    /// # use std::time::{Duration, Instant};
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(connection: &mut Connection<T, bytes::Bytes>) {
+    /// connection.graceful_shutdown_with_deadline(
+    ///     Instant::now() + Duration::from_secs(30),
+    ///     Reason::CANCEL,
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`graceful_shutdown`]: #method.graceful_shutdown
+    /// [`num_open_streams`]: #method.num_open_streams
+    /// [`timer`]: struct.Builder.html#method.timer
+    pub fn graceful_shutdown_with_deadline(&mut self, deadline: Instant, reason: Reason) {
+        self.connection.go_away_gracefully(Bytes::new());
+        self.connection.set_shutdown_deadline(deadline, reason);
+    }
+
+    /// Returns the number of streams that are currently open.
+    ///
+    /// This includes streams opened by the client and streams pushed by the
+    /// server. During a graceful shutdown, these are the streams that still
+    /// have to complete before the connection closes.
+    pub fn num_open_streams(&self) -> usize {
+        self.connection.num_open_streams()
     }
 
     /// Takes a `PingPong` instance from the connection.
@@ -532,6 +697,95 @@ where
         self.connection.take_user_pings().map(PingPong::new)
     }
 
+    /// Advertises alternative services for `origin` with an `ALTSVC` frame
+    /// sent on the connection (stream 0).
+    ///
+    /// `origin` is the ASCII serialization of the origin the services apply
+    /// to, e.g. `https://example.com`, and `value` has the syntax of the
+    /// `Alt-Svc` header field (see [RFC 7838]). To advertise services for the
+    /// origin of a request, use [`SendResponse::send_alt_svc`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `origin` is empty or longer than 65 535 bytes, if
+    /// the frame is larger than the maximum frame size advertised by the
+    /// client, or if the connection has already failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::server;
+    /// # use http::HeaderValue;
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T) {
+    /// let mut connection = server::handshake(my_io).await.unwrap();
+    ///
+    /// connection
+    ///     .send_alt_svc(
+    ///         "https://example.com",
+    ///         HeaderValue::from_static("h3=\":443\"; ma=86400"),
+    ///     )
+    ///     .unwrap();
+    /// # }
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// [RFC 7838]: https://datatracker.ietf.org/doc/html/rfc7838
+    /// [`SendResponse::send_alt_svc`]: struct.SendResponse.html#method.send_alt_svc
+    pub fn send_alt_svc(&mut self, origin: &str, value: HeaderValue) -> Result<(), crate::Error> {
+        if origin.is_empty() || origin.len() > u16::MAX as usize {
+            return Err(UserError::InvalidOrigin.into());
+        }
+
+        let frame = frame::AltSvc::new(
+            StreamId::zero(),
+            Bytes::copy_from_slice(origin.as_bytes()),
+            Bytes::copy_from_slice(value.as_bytes()),
+        );
+
+        self.connection.send_alt_svc(frame).map_err(Into::into)
+    }
+
+    /// Sends an extension frame on the connection (stream 0).
+    ///
+    /// The frame is queued and written the next time the connection is
+    /// polled, ahead of any pending stream frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame type is one that h2 implements, if the
+    /// payload is larger than the maximum frame size advertised by the
+    /// client, or if the connection has already failed.
+    pub fn send_extension_frame(&mut self, frame: ExtensionFrame) -> Result<(), crate::Error> {
+        self.connection
+            .send_extension_frame(frame)
+            .map_err(Into::into)
+    }
+
+    /// Polls for the next extension frame received on the connection (stream
+    /// 0).
+    ///
+    /// The connection must also be driven with [`poll_accept`] or
+    /// [`poll_closed`] for frames to be read.
+    ///
+    /// Frames of unknown types that are received on a stream are yielded by
+    /// [`RecvStream::poll_extension_frame`] instead.
+    ///
+    /// # Errors
+    ///
+    /// Once the connection has failed and no more frames are buffered, this
+    /// returns the connection error.
+    ///
+    /// [`poll_accept`]: #method.poll_accept
+    /// [`poll_closed`]: #method.poll_closed
+    /// [`RecvStream::poll_extension_frame`]: ../struct.RecvStream.html#method.poll_extension_frame
+    pub fn poll_extension_frame(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<ExtensionFrame, crate::Error>> {
+        self.connection.poll_extension_frame(cx).map_err(Into::into)
+    }
+
     /// Returns the maximum number of concurrent streams that may be initiated
     /// by the server on this connection.
     ///
@@ -559,6 +813,44 @@ where
     pub fn max_concurrent_recv_streams(&self) -> usize {
         self.connection.max_recv_streams()
     }
+
+    /// Returns the settings received from the client so far, including the
+    /// ones that h2 doesn't implement.
+    pub fn remote_settings(&self) -> RemoteSettings {
+        RemoteSettings::new(self.connection.remote_settings())
+    }
+
+    /// Returns the last `GOAWAY` frame received from the client, if any.
+    ///
+    /// A `GOAWAY` frame with `NO_ERROR` is not reported as an error when the
+    /// connection completes; its last stream ID and debug data can be read
+    /// here instead.
+    pub fn received_go_away(&self) -> Option<GoAway> {
+        self.connection.received_go_away().cloned().map(GoAway::new)
+    }
+
+    /// Polls for the completion of the initial `SETTINGS` exchange.
+    ///
+    /// This resolves with the client's settings once its first `SETTINGS`
+    /// frame has been received and our own `SETTINGS` frame has been
+    /// acknowledged. The connection must also be driven with
+    /// [`poll_accept`] or [`poll_closed`] for this to make progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns the connection error if the connection fails first.
+    ///
+    /// [`poll_accept`]: #method.poll_accept
+    /// [`poll_closed`]: #method.poll_closed
+    pub fn poll_remote_settings(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<RemoteSettings, crate::Error>> {
+        self.connection
+            .poll_remote_settings(cx)
+            .map_ok(RemoteSettings::new)
+            .map_err(Into::into)
+    }
 }
 
 #[cfg(feature = "stream")]
@@ -620,6 +912,14 @@ impl Builder {
             reset_stream_max: proto::DEFAULT_RESET_STREAM_MAX,
             settings: Settings::default(),
             initial_target_connection_window_size: None,
+            adaptive_window: None,
+            auto_release_capacity: false,
+            max_send_buffer_size: usize::MAX,
+            max_connection_send_buffer_size: usize::MAX,
+            recv_memory_budget: None,
+            origin_set: Vec::new(),
+            padding: Padding::none(),
+            time: Time::default(),
         }
     }
 
@@ -654,6 +954,7 @@ impl Builder {
     /// ```
     pub fn initial_window_size(&mut self, size: u32) -> &mut Self {
         self.settings.set_initial_window_size(Some(size));
+        self.cap_initial_window_size();
         self
     }
 
@@ -691,6 +992,241 @@ impl Builder {
         self
     }
 
+    /// Enables adaptive flow control for received data.
+    ///
+    /// The connection estimates the bandwidth-delay product of the link, by
+    /// sending `PING` frames while `DATA` frames are received and measuring
+    /// how much data arrives over a round trip. As the estimate grows, the
+    /// connection window and the windows of the streams are grown to match,
+    /// up to `max_window_size`. Windows are never shrunk, and windows set
+    /// larger than the estimate with [`initial_window_size`] or
+    /// [`initial_connection_window_size`] are kept.
+    ///
+    /// This lets transfers over links with a high latency use the available
+    /// bandwidth, without configuring large windows for every peer.
+    ///
+    /// By default, adaptive flow control is disabled.
+    ///
+    /// [`initial_window_size`]: #method.initial_window_size
+    /// [`initial_connection_window_size`]: #method.initial_connection_window_size
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .adaptive_window(16 * 1024 * 1024)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn adaptive_window(&mut self, max_window_size: u32) -> &mut Self {
+        self.adaptive_window = Some(std::cmp::min(max_window_size, proto::MAX_WINDOW_SIZE));
+        self
+    }
+
+    /// Sets whether received data releases its capacity automatically.
+    ///
+    /// By default, the application has to release the capacity of every
+    /// chunk of data it takes from a [`RecvStream`] by calling
+    /// [`FlowControl::release_capacity`]. If it forgets to, the peer runs out
+    /// of window and the stream stalls.
+    ///
+    /// When enabled, capacity is released as soon as data is handed to the
+    /// application, and `WINDOW_UPDATE` frames are sent once enough of it
+    /// has been released. This gives up backpressure: the peer may keep
+    /// sending as long as the application keeps reading. The setting can be
+    /// changed for a single stream with [`FlowControl::set_auto_release`].
+    ///
+    /// Data whose capacity has already been released automatically must not
+    /// be released again: while enabled, [`FlowControl::release_capacity`]
+    /// returns an error unless the data was taken from the stream before
+    /// automatic release was enabled for it.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`RecvStream`]: ../struct.RecvStream.html
+    /// [`FlowControl::release_capacity`]: ../struct.FlowControl.html#method.release_capacity
+    /// [`FlowControl::set_auto_release`]: ../struct.FlowControl.html#method.set_auto_release
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .auto_release_capacity(true)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn auto_release_capacity(&mut self, enabled: bool) -> &mut Self {
+        self.auto_release_capacity = enabled;
+        self
+    }
+
+    /// Sets the maximum amount of data buffered on each stream, waiting to
+    /// be sent.
+    ///
+    /// Data passed to [`SendStream::send_data`] is held by the connection
+    /// until the peer's flow control windows allow it to be sent. Without a
+    /// limit, a slow peer can make that buffer grow without bound. Once
+    /// `max` bytes are buffered on a stream, `send_data` returns an error
+    /// instead of buffering more. A single chunk larger than `max` is still
+    /// accepted when nothing is buffered on the stream, so it can be sent at
+    /// all.
+    ///
+    /// The capacity reported by [`SendStream::capacity`] and
+    /// [`SendStream::poll_capacity`] accounts for this limit, so data sent
+    /// within the capacity is never refused. To also bound the data buffered
+    /// by the whole connection, use [`max_connection_send_buffer_size`].
+    ///
+    /// The default value is unlimited.
+    ///
+    /// [`SendStream::send_data`]: ../struct.SendStream.html#method.send_data
+    /// [`SendStream::capacity`]: ../struct.SendStream.html#method.capacity
+    /// [`SendStream::poll_capacity`]: ../struct.SendStream.html#method.poll_capacity
+    /// [`max_connection_send_buffer_size`]: #method.max_connection_send_buffer_size
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .max_send_buffer_size(1024 * 1024)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_send_buffer_size(&mut self, max: usize) -> &mut Self {
+        self.max_send_buffer_size = max;
+        self
+    }
+
+    /// Sets the maximum amount of data buffered on the whole connection,
+    /// waiting to be sent.
+    ///
+    /// Only data sent beyond the capacity of its stream counts against this
+    /// limit, as data within the capacity is bounded by the peer's flow
+    /// control windows. Once `max` bytes are buffered on the connection,
+    /// [`SendStream::send_data`] returns an error for data exceeding the
+    /// capacity of its stream. A single chunk larger than `max` is still
+    /// accepted when nothing is buffered on the connection.
+    ///
+    /// The default value is unlimited.
+    ///
+    /// [`SendStream::send_data`]: ../struct.SendStream.html#method.send_data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .max_connection_send_buffer_size(16 * 1024 * 1024)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn max_connection_send_buffer_size(&mut self, max: usize) -> &mut Self {
+        self.max_connection_send_buffer_size = max;
+        self
+    }
+
+    /// Sets the maximum amount of received data the connection may hold.
+    ///
+    /// Each stream has its own flow control window, so with many streams
+    /// open, the data the peer is allowed to send can add up to far more
+    /// than the memory available for one connection. With a budget set, the
+    /// connection window never grows past `budget`, which bounds the data
+    /// received but not released yet across all streams. Within it, every
+    /// open stream is given an equal share: a stream's window is only grown
+    /// while its window and the data it has not released stay within
+    /// `budget` divided by the number of open streams.
+    ///
+    /// The [`initial_window_size`] sent to the peer is capped to `budget`
+    /// divided by [`max_concurrent_streams`], or to `budget` if the number of
+    /// streams is not limited, so that new streams start within their share.
+    /// It is never capped below one byte.
+    /// The connection window always starts at 65,535 bytes, so a lower
+    /// budget only takes effect once that window has been used.
+    ///
+    /// The default value is unlimited.
+    ///
+    /// [`initial_window_size`]: #method.initial_window_size
+    /// [`max_concurrent_streams`]: #method.max_concurrent_streams
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .recv_memory_budget(16 * 1024 * 1024)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn recv_memory_budget(&mut self, budget: u32) -> &mut Self {
+        self.recv_memory_budget = Some(std::cmp::min(budget, proto::MAX_WINDOW_SIZE));
+        self.cap_initial_window_size();
+        self
+    }
+
+    /// Keeps the initial window size sent to the peer within the share of
+    /// the receive memory budget of each stream.
+    fn cap_initial_window_size(&mut self) {
+        if let Some(budget) = self.recv_memory_budget {
+            self.settings.cap_initial_window_size(budget);
+        }
+    }
+
     /// Indicates the size (in octets) of the largest HTTP/2.0 frame payload that the
     /// configured server is able to accept.
     ///
@@ -808,6 +1344,7 @@ impl Builder {
     /// ```
     pub fn max_concurrent_streams(&mut self, max: u32) -> &mut Self {
         self.settings.set_max_concurrent_streams(Some(max));
+        self.cap_initial_window_size();
         self
     }
 
@@ -904,26 +1441,18 @@ impl Builder {
         self
     }
 
-    /// Creates a new configured HTTP/2.0 server backed by `io`.
-    ///
-    /// It is expected that `io` already be in an appropriate state to commence
-    /// the [HTTP/2.0 handshake]. See [Handshake] for more details.
+    /// Enables the [extended CONNECT protocol].
     ///
-    /// Returns a future which resolves to the [`Connection`] instance once the
-    /// HTTP/2.0 handshake has been completed.
-    ///
-    /// This function also allows the caller to configure the send payload data
-    /// type. See [Outbound data type] for more details.
+    /// When enabled, the server advertises `SETTINGS_ENABLE_CONNECT_PROTOCOL`
+    /// and accepts `CONNECT` requests carrying the `:protocol` pseudo-header.
+    /// The value of `:protocol` is made available through the request
+    /// extensions as a [`Protocol`].
     ///
-    /// [HTTP/2.0 handshake]: http://httpwg.org/specs/rfc7540.html#ConnectionHeader
-    /// [Handshake]: ../index.html#handshake
-    /// [`Connection`]: struct.Connection.html
-    /// [Outbound data type]: ../index.html#outbound-data-type.
+    /// [extended CONNECT protocol]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+    /// [`Protocol`]: ../ext/struct.Protocol.html
     ///
     /// # Examples
     ///
-    /// Basic usage:
-    ///
     /// ```
     /// # use tokio::io::{AsyncRead, AsyncWrite};
     /// # use h2::server::*;
@@ -934,15 +1463,267 @@ impl Builder {
     /// // `server_fut` is a future representing the completion of the HTTP/2.0
     /// // handshake.
     /// let server_fut = Builder::new()
+    ///     .enable_connect_protocol()
     ///     .handshake(my_io);
     /// # server_fut
     /// # }
     /// #
     /// # pub fn main() {}
     /// ```
+    pub fn enable_connect_protocol(&mut self) -> &mut Self {
+        self.settings.set_enable_connect_protocol(Some(1));
+        self
+    }
+
+    /// Disables the priority scheme defined in [RFC 7540].
     ///
-    /// Configures the send-payload data type. In this case, the outbound data
-    /// type will be `&'static [u8]`.
+    /// When disabled, the server advertises
+    /// `SETTINGS_NO_RFC7540_PRIORITIES` and ignores the priority information
+    /// carried by `PRIORITY` and `HEADERS` frames. Responses are then
+    /// scheduled using the [extensible priorities] signaled by the client
+    /// only, see [`Priority`].
+    ///
+    /// By default, both schemes are honored, extensible priorities taking
+    /// precedence once the client uses them.
+    ///
+    /// [RFC 7540]: https://datatracker.ietf.org/doc/html/rfc7540#section-5.3
+    /// [extensible priorities]: https://datatracker.ietf.org/doc/html/rfc9218
+    /// [`Priority`]: ../ext/struct.Priority.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .disable_rfc7540_priorities()
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn disable_rfc7540_priorities(&mut self) -> &mut Self {
+        self.settings.set_no_rfc7540_priorities(Some(1));
+        self
+    }
+
+    /// Adds a setting that h2 doesn't implement to the initial SETTINGS
+    /// frame.
+    ///
+    /// Extensions to HTTP/2.0 may define new settings, which peers that don't
+    /// know them ignore. This allows advertising such a setting, while the
+    /// extension itself is implemented by the application. Setting the same
+    /// `id` twice keeps the last value.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `id` identifies a setting that h2 implements,
+    /// in which case the dedicated builder method must be used instead, or if
+    /// 64 custom settings have already been added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .custom_setting(0xf000, 1)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn custom_setting(&mut self, id: u16, value: u32) -> &mut Self {
+        self.settings.set_unknown_setting(id, value);
+        self
+    }
+
+    /// Adds an origin to the origin set advertised to clients.
+    ///
+    /// When the origin set is not empty, the server sends it in `ORIGIN`
+    /// frames (see [RFC 8336]) right after its `SETTINGS` frame, telling the
+    /// client which origins it may reuse the connection for. `origin` is the
+    /// ASCII serialization of an origin, e.g. `https://example.com` or
+    /// `https://example.com:8443`.
+    ///
+    /// By default, no `ORIGIN` frame is sent.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `origin` is empty, is not ASCII, or is longer
+    /// than 16,382 bytes.
+    ///
+    /// [RFC 8336]: https://datatracker.ietf.org/doc/html/rfc8336
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .origin("https://example.com")
+    ///     .origin("https://www.example.com")
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn origin(&mut self, origin: &str) -> &mut Self {
+        assert!(!origin.is_empty(), "origin must not be empty");
+        assert!(origin.is_ascii(), "origin must be ASCII");
+        assert!(
+            origin.len() + 2 <= frame::DEFAULT_MAX_FRAME_SIZE as usize,
+            "origin too long"
+        );
+
+        self.origin_set
+            .push(Bytes::copy_from_slice(origin.as_bytes()));
+        self
+    }
+
+    /// Sets the padding policy for outgoing `DATA`, `HEADERS` and
+    /// `PUSH_PROMISE` frames.
+    ///
+    /// Padding hides the size of messages from traffic analysis, at the cost
+    /// of bandwidth. The padding of `DATA` frames is counted against flow
+    /// control windows, as any other payload.
+    ///
+    /// By default, frames are not padded. See [`Padding`] for the available
+    /// policies.
+    ///
+    /// [`Padding`]: ../struct.Padding.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use h2::Padding;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .padding(Padding::random(32))
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn padding(&mut self, padding: Padding) -> &mut Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the timer used to wake up connections when a deadline passes.
+    ///
+    /// Deadlines, such as the one of
+    /// [`Connection::graceful_shutdown_with_deadline`], are checked whenever
+    /// the connection is polled. Without a timer, a deadline that passes
+    /// while the connection is idle is only noticed once it is polled again.
+    ///
+    /// By default, no timer is set. See [`Timer`] for an example using the
+    /// Tokio timer.
+    ///
+    /// [`Connection::graceful_shutdown_with_deadline`]: struct.Connection.html#method.graceful_shutdown_with_deadline
+    /// [`Timer`]: ../trait.Timer.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use h2::{Sleep, Timer};
+    /// # use std::time::Instant;
+    /// #
+    /// # struct MyTimer;
+    /// # impl Timer for MyTimer {
+    /// #     fn sleep_until(&self, deadline: Instant) -> Sleep {
+    /// #         Box::pin(tokio::time::sleep_until(deadline.into()))
+    /// #     }
+    /// # }
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .timer(MyTimer)
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn timer<T: Timer + 'static>(&mut self, timer: T) -> &mut Self {
+        self.time = Time::new(timer);
+        self
+    }
+
+    /// Creates a new configured HTTP/2.0 server backed by `io`.
+    ///
+    /// It is expected that `io` already be in an appropriate state to commence
+    /// the [HTTP/2.0 handshake]. See [Handshake] for more details.
+    ///
+    /// Returns a future which resolves to the [`Connection`] instance once the
+    /// HTTP/2.0 handshake has been completed.
+    ///
+    /// This function also allows the caller to configure the send payload data
+    /// type. See [Outbound data type] for more details.
+    ///
+    /// [HTTP/2.0 handshake]: http://httpwg.org/specs/rfc7540.html#ConnectionHeader
+    /// [Handshake]: ../index.html#handshake
+    /// [`Connection`]: struct.Connection.html
+    /// [Outbound data type]: ../index.html#outbound-data-type.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Handshake<T>
+    /// # {
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .handshake(my_io);
+    /// # server_fut
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    ///
+    /// Configures the send-payload data type. In this case, the outbound data
+    /// type will be `&'static [u8]`.
     ///
     /// ```
     /// # use tokio::io::{AsyncRead, AsyncWrite};
@@ -967,6 +1748,130 @@ impl Builder {
     {
         Connection::handshake2(io, self.clone())
     }
+
+    /// Creates a new configured HTTP/2.0 server backed by `io`, which was
+    /// upgraded from HTTP/1.1 with `Upgrade: h2c`.
+    ///
+    /// `http2_settings` is the value of the `HTTP2-Settings` header of the
+    /// upgrade request, and `request` the upgrade request itself. It is
+    /// expected that the `101 Switching Protocols` response was written to
+    /// `io` and that the request body, if any, was read from it.
+    ///
+    /// The settings are applied as if they had been received in the client's
+    /// SETTINGS frame, and the request is accepted as stream 1, already
+    /// half-closed by the client. Its response must be sent with the
+    /// [`SendResponse`] returned along with it by [`Connection::accept`], as
+    /// for any other request. Connection-specific headers, such as
+    /// `Connection`, `Upgrade` or `HTTP2-Settings`, are removed from it.
+    ///
+    /// See [RFC 7540, section 3.2] for more details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with reason `PROTOCOL_ERROR` if `http2_settings`
+    /// does not hold a valid base64url encoded SETTINGS payload. The
+    /// connection must not be upgraded in that case.
+    ///
+    /// [`SendResponse`]: struct.SendResponse.html
+    /// [`Connection::accept`]: struct.Connection.html#method.accept
+    /// [RFC 7540, section 3.2]: https://httpwg.org/specs/rfc7540.html#discover-http
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use http::{HeaderValue, Request};
+    /// #
+    /// # fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T, request: Request<()>)
+    /// # -> Result<Handshake<T>, h2::Error>
+    /// # {
+    /// let http2_settings = request.headers()["http2-settings"].clone();
+    ///
+    /// // `server_fut` is a future representing the completion of the HTTP/2.0
+    /// // handshake.
+    /// let server_fut = Builder::new()
+    ///     .handshake_upgrade(my_io, &http2_settings, request)?;
+    /// # Ok(server_fut)
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn handshake_upgrade<T, B>(
+        &self,
+        io: T,
+        http2_settings: &HeaderValue,
+        request: Request<()>,
+    ) -> Result<Handshake<T, B>, crate::Error>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+        B: Buf + 'static,
+    {
+        let settings = match proto::upgrade::decode_settings(http2_settings) {
+            Some(settings) => settings,
+            None => return Err(Reason::PROTOCOL_ERROR.into()),
+        };
+
+        let headers = Peer::convert_upgrade_request(request);
+
+        let mut handshake = Connection::handshake2(io, self.clone());
+        handshake.upgrade = Some((settings, headers));
+        Ok(handshake)
+    }
+
+    /// Creates a new configured HTTP/2.0 server backed by `io`, if the client
+    /// sends the HTTP/2.0 connection preface.
+    ///
+    /// This allows serving both HTTP/1.1 and prior knowledge HTTP/2.0 on the
+    /// same listener. The returned future first reads from `io` until either
+    /// the whole client connection preface was read, or a byte does not match
+    /// it. Nothing is written to `io` before that.
+    ///
+    /// If the preface was read, the handshake proceeds as with [`handshake`]
+    /// and the future resolves to [`Sniffed::Http2`]. Otherwise, including
+    /// when `io` reaches EOF before the whole preface was read, it resolves
+    /// to [`Sniffed::Other`] with `io` and the bytes read from it.
+    ///
+    /// [`handshake`]: #method.handshake
+    /// [`Sniffed::Http2`]: enum.Sniffed.html#variant.Http2
+    /// [`Sniffed::Other`]: enum.Sniffed.html#variant.Other
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use h2::server::*;
+    /// # use bytes::Bytes;
+    /// #
+    /// # async fn doc<T: AsyncRead + AsyncWrite + Unpin>(my_io: T)
+    /// # -> Result<(), h2::Error>
+    /// # {
+    /// match Builder::new().sniff_handshake::<_, Bytes>(my_io).await? {
+    ///     Sniffed::Http2(connection) => {
+    ///         // Accept inbound HTTP/2.0 streams on `connection`.
+    ///     }
+    ///     Sniffed::Other { io, read } => {
+    ///         // Serve HTTP/1.1 on `io`, starting with the bytes in `read`.
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # pub fn main() {}
+    /// ```
+    pub fn sniff_handshake<T, B>(&self, io: T) -> SniffHandshake<T, B>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+        B: Buf + 'static,
+    {
+        SniffHandshake {
+            builder: self.clone(),
+            io: Some(io),
+            buf: [0; 24],
+            pos: 0,
+            handshake: None,
+        }
+    }
 }
 
 impl Default for Builder {
@@ -1005,6 +1910,40 @@ impl<B: Buf> SendResponse<B> {
             .map_err(Into::into)
     }
 
+    /// Send an informational (1xx) response to a client request.
+    ///
+    /// Any number of informational responses may be sent before the final
+    /// response is sent with [`send_response`], for example `103 Early Hints`
+    /// to let the client preload resources, or `100 Continue` to ask the
+    /// client to send the body of a request with `Expect: 100-continue`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the status code of `response` isn't 1xx, if it's
+    /// `101 Switching Protocols`, which HTTP/2 doesn't support, or if the
+    /// final response has already been sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use h2::server::SendResponse;
+    /// # use http::{Response, StatusCode};
+    /// # fn doc(mut respond: SendResponse<bytes::Bytes>) {
+    /// let hints = Response::builder()
+    ///     .status(StatusCode::from_u16(103).unwrap())
+    ///     .header("link", "</style.css>; rel=preload; as=style")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// respond.send_informational(hints).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`send_response`]: #method.send_response
+    pub fn send_informational(&mut self, response: Response<()>) -> Result<(), crate::Error> {
+        self.inner.send_informational(response).map_err(Into::into)
+    }
+
     /// Push a request and response to the client
     ///
     /// On success, a [`SendResponse`] instance is returned.
@@ -1041,6 +1980,25 @@ impl<B: Buf> SendResponse<B> {
         self.inner.send_reset(reason)
     }
 
+    /// Advertises alternative services for the origin of the request with an
+    /// `ALTSVC` frame sent on the stream.
+    ///
+    /// `value` has the syntax of the `Alt-Svc` header field (see [RFC 7838]).
+    /// The frame may be sent before or after the response headers, as long as
+    /// the stream is not closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame is larger than the maximum frame size
+    /// advertised by the client, or if the stream is closed.
+    ///
+    /// [RFC 7838]: https://datatracker.ietf.org/doc/html/rfc7838
+    pub fn send_alt_svc(&mut self, value: HeaderValue) -> Result<(), crate::Error> {
+        self.inner
+            .send_alt_svc(Bytes::copy_from_slice(value.as_bytes()))
+            .map_err(Into::into)
+    }
+
     /// Polls to be notified when the client resets this stream.
     ///
     /// If stream is still open, this returns `Poll::Pending`, and
@@ -1065,6 +2023,35 @@ impl<B: Buf> SendResponse<B> {
     pub fn stream_id(&self) -> crate::StreamId {
         crate::StreamId::from_internal(self.inner.stream_id())
     }
+
+    /// Returns the current priority of the stream.
+    ///
+    /// The priority is initially taken from the `priority` header of the
+    /// request, if any, and is updated by the `PRIORITY_UPDATE` frames sent
+    /// by the client. When the client did not signal any priority, the
+    /// default [`Priority`] is returned.
+    ///
+    /// [`Priority`]: ../ext/struct.Priority.html
+    ///
+    /// # Panics
+    ///
+    /// If the lock on the stream store has been poisoned.
+    pub fn priority(&self) -> Priority {
+        self.inner.priority()
+    }
+
+    /// Changes the priority used to schedule the `DATA` frames of the
+    /// response.
+    ///
+    /// This overrides the priority signaled by the client, until the client
+    /// sends a new `PRIORITY_UPDATE` frame for the stream.
+    ///
+    /// # Panics
+    ///
+    /// If the lock on the stream store has been poisoned.
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.inner.set_priority(priority)
+    }
 }
 
 // ===== impl SendPushedResponse =====
@@ -1242,7 +2229,11 @@ where
                     flushed
                 }
             };
-            Handshaking::from(ReadPreface::new(codec))
+            let mut read = ReadPreface::new(codec);
+            if self.preface_read {
+                read.pos = PREFACE.len();
+            }
+            Handshaking::from(read)
         } else {
             // Otherwise, we haven't actually advanced the state, but we have
             // to replace it with itself, because we have to return a value.
@@ -1272,6 +2263,17 @@ where
                     reset_stream_duration: self.builder.reset_stream_duration,
                     reset_stream_max: self.builder.reset_stream_max,
                     settings: self.builder.settings.clone(),
+                    padding: self.builder.padding.clone(),
+                    expect_continue_timeout: None,
+                    push_cache: None,
+                    push_policy: None,
+                    max_pending_pushed_streams: None,
+                    adaptive_window: self.builder.adaptive_window,
+                    auto_release_capacity: self.builder.auto_release_capacity,
+                    max_send_buffer_size: self.builder.max_send_buffer_size,
+                    max_connection_send_buffer_size: self.builder.max_connection_send_buffer_size,
+                    recv_memory_budget: self.builder.recv_memory_budget,
+                    time: self.builder.time.clone(),
                 },
             );
 
@@ -1280,11 +2282,92 @@ where
             if let Some(sz) = self.builder.initial_target_connection_window_size {
                 c.set_target_window_size(sz);
             }
+            if !self.builder.origin_set.is_empty() {
+                // Nothing can have gone wrong with the connection yet, and
+                // every origin fits in a frame of the minimum max size.
+                c.connection
+                    .send_origin(self.builder.origin_set.clone())
+                    .expect("send ORIGIN");
+            }
+            if let Some((settings, headers)) = self.upgrade.take() {
+                c.connection
+                    .apply_upgrade_settings(&settings)
+                    .and_then(|()| c.connection.recv_upgrade_request(headers))
+                    .map_err(|e| match e {
+                        RecvError::Connection(reason) | RecvError::Stream { reason, .. } => {
+                            crate::Error::from(reason)
+                        }
+                        RecvError::Io(e) => crate::Error::from_io(e),
+                    })?;
+            }
             Ok(c)
         })
     }
 }
 
+// ===== impl SniffHandshake =====
+
+impl<T, B> Future for SniffHandshake<T, B>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    B: Buf + 'static,
+{
+    type Output = Result<Sniffed<T, B>, crate::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = &mut *self;
+
+        if let Some(ref mut handshake) = me.handshake {
+            return Pin::new(handshake)
+                .poll(cx)
+                .map_ok(|conn| Sniffed::Http2(Box::new(conn)));
+        }
+
+        let io = me.io.as_mut().expect("polled after complete");
+
+        while me.pos < PREFACE.len() {
+            let mut buf = ReadBuf::new(&mut me.buf[me.pos..]);
+            ready!(Pin::new(&mut *io).poll_read(cx, &mut buf)).map_err(crate::Error::from_io)?;
+            let n = buf.filled().len();
+            if n == 0 {
+                tracing::debug!("sniff_handshake: EOF before the HTTP/2.0 preface");
+                return Poll::Ready(Ok(Sniffed::Other {
+                    io: me.io.take().unwrap(),
+                    read: Bytes::copy_from_slice(&me.buf[..me.pos]),
+                }));
+            }
+
+            let end = me.pos + n;
+            if me.buf[me.pos..end] != PREFACE[me.pos..end] {
+                tracing::debug!("sniff_handshake: not the HTTP/2.0 preface");
+                return Poll::Ready(Ok(Sniffed::Other {
+                    io: me.io.take().unwrap(),
+                    read: Bytes::copy_from_slice(&me.buf[..end]),
+                }));
+            }
+
+            me.pos = end;
+        }
+
+        tracing::trace!("sniff_handshake: read the HTTP/2.0 preface");
+        let mut handshake = Connection::handshake2(me.io.take().unwrap(), me.builder.clone());
+        handshake.preface_read = true;
+        me.handshake = Some(handshake);
+
+        self.poll(cx)
+    }
+}
+
+impl<T, B> fmt::Debug for SniffHandshake<T, B>
+where
+    T: AsyncRead + AsyncWrite + fmt::Debug,
+    B: fmt::Debug + Buf,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "server::SniffHandshake")
+    }
+}
+
 impl<T, B> fmt::Debug for Handshake<T, B>
 where
     T: AsyncRead + AsyncWrite + fmt::Debug,
@@ -1325,6 +2408,68 @@ impl Peer {
         frame
     }
 
+    /// Converts the request of an HTTP/1.1 upgrade to the HEADERS frame that
+    /// opens stream 1.
+    pub fn convert_upgrade_request(request: Request<()>) -> frame::Headers {
+        use http::header::{self, HeaderName};
+        use http::request::Parts;
+        use http::uri::Scheme;
+
+        let (
+            Parts {
+                method,
+                uri,
+                mut headers,
+                ..
+            },
+            _,
+        ) = request.into_parts();
+
+        // 8.1.2.2. Connection-Specific Header Fields
+        let nominated: Vec<HeaderName> = headers
+            .get_all(header::CONNECTION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+            .collect();
+
+        for name in nominated {
+            headers.remove(name);
+        }
+
+        headers.remove(header::CONNECTION);
+        headers.remove(header::TRANSFER_ENCODING);
+        headers.remove(header::UPGRADE);
+        headers.remove("http2-settings");
+        headers.remove("keep-alive");
+        headers.remove("proxy-connection");
+
+        if let Some(te) = headers.get(header::TE) {
+            if te != "trailers" {
+                headers.remove(header::TE);
+            }
+        }
+
+        let mut pseudo = Pseudo::request(method, uri, None);
+
+        // An `Upgrade: h2c` request is made over cleartext HTTP/1.1, usually
+        // with an origin-form target and a `Host` header.
+        if pseudo.scheme.is_none() {
+            pseudo.set_scheme(Scheme::HTTP);
+        }
+
+        if pseudo.authority.is_none() {
+            if let Some(host) = headers.get(header::HOST).and_then(|v| v.to_str().ok()) {
+                pseudo.set_authority(BytesStr::from(host));
+            }
+        }
+
+        let mut frame = frame::Headers::new(StreamId::from(1), pseudo, headers);
+        frame.set_end_stream();
+        frame
+    }
+
     pub fn convert_push_message(
         stream_id: StreamId,
         promised_id: StreamId,
@@ -1360,7 +2505,7 @@ impl Peer {
             _,
         ) = request.into_parts();
 
-        let pseudo = Pseudo::request(method, uri);
+        let pseudo = Pseudo::request(method, uri, None);
 
         Ok(frame::PushPromise::new(
             stream_id,
@@ -1413,6 +2558,15 @@ impl proto::Peer for Peer {
             malformed!("malformed headers: missing method");
         }
 
+        let has_protocol = pseudo.protocol.is_some();
+        if let Some(protocol) = pseudo.protocol {
+            if is_connect {
+                b = b.extension(protocol);
+            } else {
+                malformed!("malformed headers: :protocol on non-CONNECT request");
+            }
+        }
+
         // Specifying :status for a request is a protocol error
         if pseudo.status.is_some() {
             tracing::trace!("malformed headers: :status field on request; PROTOCOL_ERROR");
@@ -1437,7 +2591,7 @@ impl proto::Peer for Peer {
 
         // A :scheme is required, except CONNECT.
         if let Some(scheme) = pseudo.scheme {
-            if is_connect {
+            if is_connect && !has_protocol {
                 malformed!(":scheme in CONNECT");
             }
             let maybe_scheme = scheme.parse();
@@ -1455,12 +2609,12 @@ impl proto::Peer for Peer {
             if parts.authority.is_some() {
                 parts.scheme = Some(scheme);
             }
-        } else if !is_connect {
+        } else if !is_connect || has_protocol {
             malformed!("malformed headers: missing scheme");
         }
 
         if let Some(path) = pseudo.path {
-            if is_connect {
+            if is_connect && !has_protocol {
                 malformed!(":path in CONNECT");
             }
 
@@ -1473,6 +2627,8 @@ impl proto::Peer for Peer {
             parts.path_and_query = Some(maybe_path.or_else(|why| {
                 malformed!("malformed headers: malformed path ({:?}): {}", path, why,)
             })?);
+        } else if has_protocol {
+            malformed!("malformed headers: missing path in extended CONNECT");
         }
 
         b = b.uri(parts);
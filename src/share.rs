@@ -1,5 +1,6 @@
 use crate::codec::UserError;
-use crate::frame::Reason;
+use crate::ext::{ExtensionFrame, Priority};
+use crate::frame::{self, Reason};
 use crate::proto::{self, WindowSize};
 
 use bytes::{Buf, Bytes};
@@ -9,6 +10,7 @@ use crate::PollExt;
 use std::fmt;
 #[cfg(feature = "stream")]
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 /// Sends the body stream and trailers to the remote peer.
@@ -212,6 +214,20 @@ pub struct Pong {
     _p: (),
 }
 
+/// The settings received from the peer.
+///
+/// This is a snapshot of the values the peer has set with its `SETTINGS`
+/// frames when it was taken, including the settings that h2 doesn't
+/// implement. When a setting has been sent more than once, the last value
+/// is kept.
+///
+/// The typed accessors return the default value defined by the
+/// specification for settings that the peer didn't send.
+#[derive(Clone)]
+pub struct RemoteSettings {
+    inner: Arc<frame::Settings>,
+}
+
 // ===== impl SendStream =====
 
 impl<B: Buf> SendStream<B> {
@@ -321,9 +337,12 @@ impl<B: Buf> SendStream<B> {
     /// `send_data` can be called without reserving capacity. In this case, the
     /// data is buffered and the capacity is implicitly requested. Once the
     /// capacity becomes available, the data is flushed to the connection.
-    /// However, this buffering is unbounded. As such, sending large amounts of
-    /// data without reserving capacity before hand could result in large
-    /// amounts of data being buffered in memory.
+    /// Unless `max_send_buffer_size` or `max_connection_send_buffer_size` is
+    /// set on the client or server `Builder`, this buffering is unbounded. As
+    /// such, sending large amounts of data without reserving capacity before
+    /// hand could result in large amounts of data being buffered in memory.
+    /// If a limit is set, data that would be buffered beyond it is refused
+    /// with an [`Error`].
     ///
     /// [`Error`]: struct.Error.html
     pub fn send_data(&mut self, data: B, end_of_stream: bool) -> Result<(), crate::Error> {
@@ -340,6 +359,21 @@ impl<B: Buf> SendStream<B> {
         self.inner.send_trailers(trailers).map_err(Into::into)
     }
 
+    /// Sends an extension frame on this stream.
+    ///
+    /// Extension frames do not change the state of the stream and are not
+    /// subject to flow control. They are queued along with the other frames
+    /// of the stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame type is one that h2 implements, if the
+    /// payload is larger than the maximum frame size advertised by the
+    /// remote, or if the send half of the stream is closed.
+    pub fn send_extension_frame(&mut self, frame: ExtensionFrame) -> Result<(), crate::Error> {
+        self.inner.send_extension_frame(frame).map_err(Into::into)
+    }
+
     /// Resets the stream.
     ///
     /// This cancels the request / response exchange. If the response has not
@@ -375,6 +409,29 @@ impl<B: Buf> SendStream<B> {
     pub fn stream_id(&self) -> StreamId {
         StreamId::from_internal(self.inner.stream_id())
     }
+
+    /// Returns the current priority of the stream.
+    ///
+    /// See [`SendResponse::priority`] for details.
+    ///
+    /// [`SendResponse::priority`]: server/struct.SendResponse.html#method.priority
+    ///
+    /// # Panics
+    ///
+    /// If the lock on the stream store has been poisoned.
+    pub fn priority(&self) -> Priority {
+        self.inner.priority()
+    }
+
+    /// Changes the priority used to schedule the `DATA` frames sent on this
+    /// stream.
+    ///
+    /// # Panics
+    ///
+    /// If the lock on the stream store has been poisoned.
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.inner.set_priority(priority)
+    }
 }
 
 // ===== impl StreamId =====
@@ -383,6 +440,10 @@ impl StreamId {
     pub(crate) fn from_internal(id: crate::frame::StreamId) -> Self {
         StreamId(id.into())
     }
+
+    pub(crate) fn to_internal(&self) -> crate::frame::StreamId {
+        crate::frame::StreamId::from(self.0)
+    }
 }
 // ===== impl RecvStream =====
 
@@ -418,6 +479,20 @@ impl RecvStream {
         }
     }
 
+    /// Poll for the next extension frame received on this stream.
+    ///
+    /// Returns `None` once the receive half of the stream is closed and all
+    /// buffered frames have been returned.
+    pub fn poll_extension_frame(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<ExtensionFrame, crate::Error>>> {
+        self.inner
+            .inner
+            .poll_extension_frame(cx)
+            .map_err_(Into::into)
+    }
+
     /// Returns true if the receive half has reached the end of stream.
     ///
     /// A return value of `true` means that calls to `poll` and `poll_trailers`
@@ -511,6 +586,9 @@ impl FlowControl {
     /// received. If 1024 bytes of data have been received, at most 1024 bytes
     /// can be released.
     ///
+    /// While capacity is released automatically, only the data taken from
+    /// the stream before that was enabled can be released.
+    ///
     /// [struct level]: #
     pub fn release_capacity(&mut self, sz: usize) -> Result<(), crate::Error> {
         if sz > proto::MAX_WINDOW_SIZE as usize {
@@ -520,6 +598,47 @@ impl FlowControl {
             .release_capacity(sz as proto::WindowSize)
             .map_err(Into::into)
     }
+
+    /// Sets the target window size for this stream.
+    ///
+    /// This overrides the `initial_window_size` setting of the connection
+    /// for this stream only, such as the body of a request a server has just
+    /// accepted. Clients can also choose the window of a response body when
+    /// sending the request, with an [`InitialWindowSize`] extension.
+    ///
+    /// If `size` is greater than the current value, a `WINDOW_UPDATE` frame
+    /// increasing the stream level window by `size - current_value` is sent
+    /// to the remote, once the increase is large enough to be worth a frame.
+    ///
+    /// If `size` is less than the current value, nothing will happen
+    /// immediately. However, as capacity is released, no `WINDOW_UPDATE`
+    /// frames will be sent out until the number of "in flight" bytes drops
+    /// below `size`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `size` is larger than 2^31-1.
+    ///
+    /// [`InitialWindowSize`]: ext/struct.InitialWindowSize.html
+    pub fn set_target_window_size(&mut self, size: u32) {
+        assert!(size <= proto::MAX_WINDOW_SIZE);
+        self.inner.set_target_window_size(size)
+    }
+
+    /// Sets whether data received on this stream releases its capacity
+    /// automatically.
+    ///
+    /// When enabled, capacity is released as soon as data is handed to the
+    /// application, so [`release_capacity`] must not be called for it. This
+    /// overrides the connection wide setting, which is configured with
+    /// `auto_release_capacity` on the client or server `Builder`.
+    ///
+    /// Only data taken from the stream after the call is affected.
+    ///
+    /// [`release_capacity`]: #method.release_capacity
+    pub fn set_auto_release(&mut self, enabled: bool) {
+        self.inner.set_auto_release_capacity(enabled)
+    }
 }
 
 // ===== impl PingPong =====
@@ -587,3 +706,83 @@ impl fmt::Debug for Pong {
         fmt.debug_struct("Pong").finish()
     }
 }
+
+// ===== impl RemoteSettings =====
+
+impl RemoteSettings {
+    pub(crate) fn new(inner: Arc<frame::Settings>) -> Self {
+        RemoteSettings { inner }
+    }
+
+    /// Returns the size of the header compression table the peer uses to
+    /// decode header blocks (`SETTINGS_HEADER_TABLE_SIZE`).
+    pub fn header_table_size(&self) -> u32 {
+        self.inner
+            .header_table_size()
+            .unwrap_or(frame::DEFAULT_SETTINGS_HEADER_TABLE_SIZE as u32)
+    }
+
+    /// Returns whether the peer accepts server push (`SETTINGS_ENABLE_PUSH`).
+    ///
+    /// Only clients send this setting.
+    pub fn is_push_enabled(&self) -> bool {
+        self.inner.is_push_enabled().unwrap_or(true)
+    }
+
+    /// Returns the maximum number of concurrent streams the peer allows us to
+    /// open (`SETTINGS_MAX_CONCURRENT_STREAMS`), or `None` if unlimited.
+    pub fn max_concurrent_streams(&self) -> Option<u32> {
+        self.inner.max_concurrent_streams()
+    }
+
+    /// Returns the initial window size of the streams we send data on
+    /// (`SETTINGS_INITIAL_WINDOW_SIZE`).
+    pub fn initial_window_size(&self) -> u32 {
+        self.inner
+            .initial_window_size()
+            .unwrap_or(frame::DEFAULT_INITIAL_WINDOW_SIZE)
+    }
+
+    /// Returns the largest frame payload the peer accepts
+    /// (`SETTINGS_MAX_FRAME_SIZE`).
+    pub fn max_frame_size(&self) -> u32 {
+        self.inner
+            .max_frame_size()
+            .unwrap_or(frame::DEFAULT_MAX_FRAME_SIZE)
+    }
+
+    /// Returns the maximum size of header list the peer accepts
+    /// (`SETTINGS_MAX_HEADER_LIST_SIZE`), or `None` if unlimited.
+    pub fn max_header_list_size(&self) -> Option<u32> {
+        self.inner.max_header_list_size()
+    }
+
+    /// Returns whether the peer supports the extended CONNECT protocol
+    /// (`SETTINGS_ENABLE_CONNECT_PROTOCOL`).
+    pub fn is_extended_connect_protocol_enabled(&self) -> bool {
+        self.inner
+            .is_extended_connect_protocol_enabled()
+            .unwrap_or(false)
+    }
+
+    /// Returns the value of the setting identified by `id`, as defined in
+    /// [section 6.5.2] or by an extension, if the peer sent it.
+    ///
+    /// [section 6.5.2]: https://datatracker.ietf.org/doc/html/rfc7540#section-6.5.2
+    pub fn get(&self, id: u16) -> Option<u32> {
+        self.inner.get(id)
+    }
+
+    /// Returns every setting sent by the peer, as (id, value) pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, u32)> {
+        let mut settings = Vec::new();
+        self.inner.for_each_raw(|id, val| settings.push((id, val)));
+        settings.into_iter()
+    }
+}
+
+impl fmt::Debug for RemoteSettings {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_map().entries(self.iter()).finish()
+    }
+}
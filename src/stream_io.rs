@@ -0,0 +1,190 @@
+use crate::{RecvStream, SendStream};
+
+use bytes::{Buf, Bytes};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Byte oriented I/O over an HTTP/2.0 stream.
+///
+/// `StreamIo` wraps the two halves of a stream, such as the ones of an
+/// established `CONNECT` tunnel, and implements `AsyncRead` and `AsyncWrite`
+/// over them. This lets a protocol that expects a byte stream, like TLS or a
+/// proxied TCP connection, run directly over the HTTP/2.0 stream.
+///
+/// Reading returns the `DATA` received on the stream. Automatic capacity
+/// release is enabled on the `RecvStream`, so the flow control window does
+/// not need to be managed by hand. Reading returns EOF once the peer ends the
+/// stream.
+///
+/// Writing reserves send capacity on the stream and sends as much of the
+/// buffer as the capacity allows. Shutting the writer down sends an empty
+/// `DATA` frame with the `END_STREAM` flag set. Flushing does nothing, as
+/// frames are written out when the connection is polled.
+///
+/// If the stream is reset, reads and writes fail with an `io::Error` of kind
+/// `ConnectionReset`, wrapping an [`Error`] that holds the reset reason.
+/// Other errors are converted to an `io::Error` wrapping the [`Error`], or
+/// returned as is if they are I/O errors.
+///
+/// [`Error`]: struct.Error.html
+///
+/// # Examples
+///
+/// ```
+/// # use h2::StreamIo;
+/// # use h2::client::ResponseFuture;
+/// # use h2::SendStream;
+/// # use bytes::Bytes;
+/// # use tokio::io::{AsyncReadExt, AsyncWriteExt};
+/// # async fn doc(response: ResponseFuture, send: SendStream<Bytes>) -> std::io::Result<()> {
+/// // `response` and `send` come from a `CONNECT` request sent with
+/// // `end_of_stream` set to `false`.
+/// let response = response.await.unwrap();
+/// let mut io = StreamIo::new(send, response.into_body());
+///
+/// io.write_all(b"hello").await?;
+/// io.shutdown().await?;
+///
+/// let mut reply = Vec::new();
+/// io.read_to_end(&mut reply).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct StreamIo {
+    send: SendStream<Bytes>,
+    recv: RecvStream,
+
+    /// Data received but not read yet
+    buf: Bytes,
+
+    /// Set once the end of the stream was sent
+    is_shutdown: bool,
+}
+
+impl StreamIo {
+    /// Creates a new `StreamIo` over the two halves of a stream.
+    pub fn new(send: SendStream<Bytes>, mut recv: RecvStream) -> Self {
+        recv.flow_control().set_auto_release(true);
+
+        StreamIo {
+            send,
+            recv,
+            buf: Bytes::new(),
+            is_shutdown: false,
+        }
+    }
+}
+
+impl AsyncRead for StreamIo {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        dst: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = self.get_mut();
+
+        while me.buf.is_empty() {
+            match ready!(me.recv.poll_data(cx)) {
+                Some(Ok(data)) => me.buf = data,
+                Some(Err(e)) => return Poll::Ready(Err(into_io_error(e))),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+
+        let n = std::cmp::min(me.buf.len(), dst.remaining());
+        dst.put_slice(&me.buf[..n]);
+        me.buf.advance(n);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for StreamIo {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = self.get_mut();
+
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        // Capacity assigned earlier can be used right away.
+        let capacity = me.send.capacity();
+        if capacity > 0 {
+            let n = std::cmp::min(capacity, buf.len());
+            me.send
+                .send_data(Bytes::copy_from_slice(&buf[..n]), false)
+                .map_err(into_io_error)?;
+
+            return Poll::Ready(Ok(n));
+        }
+
+        me.send.reserve_capacity(buf.len());
+
+        let n = loop {
+            match ready!(me.send.poll_capacity(cx)) {
+                Some(Ok(0)) => continue,
+                Some(Ok(n)) => break std::cmp::min(n, buf.len()),
+                Some(Err(e)) => return Poll::Ready(Err(into_io_error(e))),
+                // The stream can no longer send data.
+                None => {
+                    return Poll::Ready(Err(match me.send.poll_reset(cx) {
+                        Poll::Ready(Ok(reason)) => into_io_error(reason.into()),
+                        Poll::Ready(Err(e)) => into_io_error(e),
+                        Poll::Pending => io::ErrorKind::BrokenPipe.into(),
+                    }));
+                }
+            }
+        };
+
+        me.send
+            .send_data(Bytes::copy_from_slice(&buf[..n]), false)
+            .map_err(into_io_error)?;
+
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let me = self.get_mut();
+
+        if me.is_shutdown {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Any capacity still reserved is not needed anymore.
+        me.send.reserve_capacity(0);
+
+        me.send
+            .send_data(Bytes::new(), true)
+            .map_err(into_io_error)?;
+        me.is_shutdown = true;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn into_io_error(err: crate::Error) -> io::Error {
+    if err.is_io() {
+        return err.into_io().unwrap();
+    }
+
+    let kind = if err.is_go_away() {
+        io::ErrorKind::ConnectionAborted
+    } else if err.reason().is_some() {
+        io::ErrorKind::ConnectionReset
+    } else {
+        io::ErrorKind::Other
+    };
+
+    io::Error::new(kind, err)
+}
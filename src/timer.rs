@@ -0,0 +1,111 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+/// A future returned by a [`Timer`] that completes at a deadline.
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+/// Source of the timers used to enforce deadlines on a connection.
+///
+/// h2 does not depend on a specific runtime. Deadlines, such as the one given
+/// to [`graceful_shutdown_with_deadline`] or the
+/// [`expect_continue_timeout`], are always checked when the connection is
+/// polled. Without a timer, a deadline that passes while nothing else happens
+/// on the connection is only acted upon once the connection is polled again.
+/// Setting a timer on the client or server `Builder` makes the connection
+/// wake up when each deadline passes.
+///
+/// # Examples
+///
+/// Using the Tokio timer:
+///
+/// ```
+/// # use h2::{Sleep, Timer};
+/// # use std::time::Instant;
+/// struct TokioTimer;
+///
+/// impl Timer for TokioTimer {
+///     fn sleep_until(&self, deadline: Instant) -> Sleep {
+///         Box::pin(tokio::time::sleep_until(deadline.into()))
+///     }
+/// }
+///
+/// let mut builder = h2::server::Builder::new();
+/// builder.timer(TokioTimer);
+/// ```
+///
+/// [`graceful_shutdown_with_deadline`]: server/struct.Connection.html#method.graceful_shutdown_with_deadline
+/// [`expect_continue_timeout`]: client/struct.Builder.html#method.expect_continue_timeout
+pub trait Timer: Send + Sync {
+    /// Returns a future that completes once `deadline` has passed.
+    fn sleep_until(&self, deadline: Instant) -> Sleep;
+}
+
+/// The timer configured for a connection, if any.
+#[derive(Clone, Default)]
+pub(crate) struct Time(Option<Arc<dyn Timer>>);
+
+/// A point in time that a connection waits for.
+pub(crate) struct Deadline {
+    at: Instant,
+    sleep: Option<Sleep>,
+}
+
+// ===== impl Time =====
+
+impl Time {
+    pub fn new<T: Timer + 'static>(timer: T) -> Self {
+        Time(Some(Arc::new(timer)))
+    }
+
+    /// Returns a deadline at `at`, backed by the timer if one is set.
+    pub fn deadline(&self, at: Instant) -> Deadline {
+        Deadline {
+            at,
+            sleep: self.0.as_ref().map(|timer| timer.sleep_until(at)),
+        }
+    }
+}
+
+impl fmt::Debug for Time {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_tuple("Time")
+            .field(&self.0.as_ref().map(|_| "Timer"))
+            .finish()
+    }
+}
+
+// ===== impl Deadline =====
+
+impl Deadline {
+    pub fn at(&self) -> Instant {
+        self.at
+    }
+
+    /// Returns `Ready` once the deadline has passed.
+    ///
+    /// If there is a timer, the task is woken up when the deadline passes.
+    /// Otherwise, the deadline is checked again on the next poll.
+    pub fn poll(&mut self, cx: &mut Context) -> Poll<()> {
+        if Instant::now() >= self.at {
+            return Poll::Ready(());
+        }
+
+        match self.sleep {
+            Some(ref mut sleep) => sleep.as_mut().poll(cx),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl fmt::Debug for Deadline {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Deadline")
+            .field("at", &self.at)
+            .field("has_timer", &self.sleep.is_some())
+            .finish()
+    }
+}
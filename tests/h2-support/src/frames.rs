@@ -4,7 +4,10 @@ use std::fmt;
 use bytes::Bytes;
 use http::{self, HeaderMap};
 
-use h2::frame::{self, Frame, StreamId};
+use h2::{
+    ext::Protocol,
+    frame::{self, Frame, StreamId},
+};
 
 pub const SETTINGS: &'static [u8] = &[0, 0, 0, 4, 0, 0, 0, 0, 0];
 pub const SETTINGS_ACK: &'static [u8] = &[0, 0, 0, 4, 1, 0, 0, 0, 0];
@@ -65,6 +68,51 @@ where
     Mock(frame::Reset::new(id.into(), frame::Reason::NO_ERROR))
 }
 
+pub fn priority<T1, T2>(id: T1, dependency: T2) -> Mock<frame::Priority>
+where
+    T1: Into<StreamId>,
+    T2: Into<StreamId>,
+{
+    Mock(frame::Priority::new(
+        id.into(),
+        frame::StreamDependency::new(dependency.into(), 15, false),
+    ))
+}
+
+pub fn priority_update<T>(id: T, value: &'static str) -> frame::PriorityUpdate
+where
+    T: Into<StreamId>,
+{
+    frame::PriorityUpdate::new(id.into(), Bytes::from_static(value.as_bytes()))
+}
+
+pub fn alt_svc<T>(id: T, origin: &'static str, value: &'static str) -> frame::AltSvc
+where
+    T: Into<StreamId>,
+{
+    frame::AltSvc::new(
+        id.into(),
+        Bytes::from_static(origin.as_bytes()),
+        Bytes::from_static(value.as_bytes()),
+    )
+}
+
+pub fn origin(origins: &[&'static str]) -> frame::Origin {
+    frame::Origin::new(
+        origins
+            .iter()
+            .map(|origin| Bytes::from_static(origin.as_bytes()))
+            .collect(),
+    )
+}
+
+pub fn unknown<T>(id: T, kind: u8, payload: &'static [u8]) -> frame::Unknown
+where
+    T: Into<StreamId>,
+{
+    frame::Unknown::new(id.into(), kind, 0, Bytes::from_static(payload))
+}
+
 pub fn settings() -> Mock<frame::Settings> {
     Mock(frame::Settings::default())
 }
@@ -109,7 +157,7 @@ impl Mock<frame::Headers> {
         let method = method.try_into().unwrap();
         let uri = uri.try_into().unwrap();
         let (id, _, fields) = self.into_parts();
-        let frame = frame::Headers::new(id, frame::Pseudo::request(method, uri), fields);
+        let frame = frame::Headers::new(id, frame::Pseudo::request(method, uri, None), fields);
         Mock(frame)
     }
 
@@ -171,11 +219,36 @@ impl Mock<frame::Headers> {
         Mock(frame::Headers::new(id, pseudo, fields))
     }
 
+    pub fn protocol(self, value: &str) -> Self {
+        let (id, mut pseudo, fields) = self.into_parts();
+        let value = Protocol::from(value);
+
+        pseudo.set_protocol(value);
+
+        Mock(frame::Headers::new(id, pseudo, fields))
+    }
+
     pub fn eos(mut self) -> Self {
         self.0.set_end_stream();
         self
     }
 
+    pub fn padded(mut self) -> Self {
+        self.0.set_padded();
+        self
+    }
+
+    /// Sets the stream dependency, with a weight in the range [1, 256].
+    pub fn stream_dep<T>(mut self, dependency: T, weight: u16, exclusive: bool) -> Self
+    where
+        T: Into<StreamId>,
+    {
+        assert!(weight >= 1 && weight <= 256);
+        let dep = frame::StreamDependency::new(dependency.into(), (weight - 1) as u8, exclusive);
+        self.0.set_stream_dep(dep);
+        self
+    }
+
     pub fn into_fields(self) -> HeaderMap {
         self.0.into_parts().1
     }
@@ -203,6 +276,11 @@ impl Mock<frame::Data> {
         self
     }
 
+    pub fn padding(mut self, pad_len: u8) -> Self {
+        self.0.set_padding(Some(pad_len));
+        self
+    }
+
     pub fn eos(mut self) -> Self {
         self.0.set_end_stream(true);
         self
@@ -222,8 +300,12 @@ impl Mock<frame::PushPromise> {
         let method = method.try_into().unwrap();
         let uri = uri.try_into().unwrap();
         let (id, promised, _, fields) = self.into_parts();
-        let frame =
-            frame::PushPromise::new(id, promised, frame::Pseudo::request(method, uri), fields);
+        let frame = frame::PushPromise::new(
+            id,
+            promised,
+            frame::Pseudo::request(method, uri, None),
+            fields,
+        );
         Mock(frame)
     }
 
@@ -279,7 +361,17 @@ impl Mock<frame::GoAway> {
     }
 
     pub fn reason(self, reason: frame::Reason) -> Self {
-        Mock(frame::GoAway::new(self.0.last_stream_id(), reason))
+        let mut frame = frame::GoAway::new(self.0.last_stream_id(), reason);
+        frame.set_debug_data(Bytes::copy_from_slice(self.0.debug_data()));
+        Mock(frame)
+    }
+
+    pub fn debug_data<T>(mut self, debug_data: T) -> Self
+    where
+        T: Into<Bytes>,
+    {
+        self.0.set_debug_data(debug_data.into());
+        self
     }
 }
 
@@ -322,6 +414,28 @@ impl Mock<frame::Reset> {
     }
 }
 
+// ==== Priority helpers
+
+impl Mock<frame::Priority> {
+    /// Sets the weight, in the range [1, 256].
+    pub fn weight(self, weight: u16) -> Self {
+        assert!(weight >= 1 && weight <= 256);
+        let dep = self.0.dependency();
+        let dep = frame::StreamDependency::new(
+            dep.dependency_id(),
+            (weight - 1) as u8,
+            dep.is_exclusive(),
+        );
+        Mock(frame::Priority::new(self.0.stream_id(), dep))
+    }
+
+    pub fn exclusive(self) -> Self {
+        let dep = self.0.dependency();
+        let dep = frame::StreamDependency::new(dep.dependency_id(), dep.weight(), true);
+        Mock(frame::Priority::new(self.0.stream_id(), dep))
+    }
+}
+
 // ==== Settings helpers
 
 impl Mock<frame::Settings> {
@@ -344,6 +458,21 @@ impl Mock<frame::Settings> {
         self.0.set_enable_push(false);
         self
     }
+
+    pub fn enable_connect_protocol(mut self, val: u32) -> Self {
+        self.0.set_enable_connect_protocol(Some(val));
+        self
+    }
+
+    pub fn no_rfc7540_priorities(mut self, val: u32) -> Self {
+        self.0.set_no_rfc7540_priorities(Some(val));
+        self
+    }
+
+    pub fn custom(mut self, id: u16, val: u32) -> Self {
+        self.0.set_unknown_setting(id, val);
+        self
+    }
 }
 
 impl From<Mock<frame::Settings>> for frame::Settings {
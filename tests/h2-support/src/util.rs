@@ -5,6 +5,7 @@ use futures::ready;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
 pub fn byte_str(s: &str) -> h2::frame::BytesStr {
     h2::frame::BytesStr::try_from(Bytes::copy_from_slice(s.as_bytes())).unwrap()
@@ -18,6 +19,15 @@ pub async fn concat(mut body: h2::RecvStream) -> Result<Bytes, h2::Error> {
     Ok(vec.into())
 }
 
+/// A `h2::Timer` backed by the Tokio timer.
+pub struct TokioTimer;
+
+impl h2::Timer for TokioTimer {
+    fn sleep_until(&self, deadline: Instant) -> h2::Sleep {
+        Box::pin(tokio::time::sleep_until(deadline.into()))
+    }
+}
+
 pub async fn yield_once() {
     let mut yielded = false;
     futures::future::poll_fn(move |cx| {
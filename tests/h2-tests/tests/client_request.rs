@@ -1301,19 +1301,1243 @@ async fn informational_while_local_streaming() {
     join(srv, h2).await;
 }
 
+#[tokio::test]
+async fn recv_informational_responses() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(
+            frames::headers(1)
+                .response(103)
+                .field("link", "</style.css>; rel=preload; as=style"),
+        )
+        .await;
+        srv.send_frame(
+            frames::headers(1)
+                .response(103)
+                .field("link", "</script.js>; rel=preload; as=script"),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (mut response, _) = client.send_request(request, true).unwrap();
+
+        let mut informational = response.informational_responses();
+        let links = h2
+            .drive(async move {
+                let mut links = Vec::new();
+                while let Some(interim) = informational.informational_response().await {
+                    assert_eq!(interim.status(), StatusCode::from_u16(103).unwrap());
+                    links.push(interim.headers()["link"].clone());
+                }
+                links
+            })
+            .await;
+        assert_eq!(
+            links,
+            [
+                "</style.css>; rel=preload; as=style",
+                "</script.js>; rel=preload; as=script",
+            ]
+        );
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn recv_informational_responses_on_another_task() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        idle_ms(10).await;
+        srv.send_frame(frames::headers(1).response(103)).await;
+        idle_ms(10).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (mut response, _) = client.send_request(request, true).unwrap();
+        let mut informational = response.informational_responses();
+
+        // The response and the informational responses are awaited by
+        // different tasks, which must both be notified.
+        let response = tokio::spawn(response);
+        let num_informational = h2
+            .drive(async move {
+                let mut num = 0;
+                while informational.informational_response().await.is_some() {
+                    num += 1;
+                }
+                num
+            })
+            .await;
+        assert_eq!(num_informational, 1);
+
+        let response = h2.drive(response).await.unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(response);
+        drop(client);
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn recv_too_many_informational_responses() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        for _ in 0..20 {
+            srv.send_frame(frames::headers(1).response(103)).await;
+        }
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (mut response, _) = client.send_request(request, true).unwrap();
+
+        // Receive everything before reading the informational responses.
+        h2.drive(idle_ms(10)).await;
+
+        let mut informational = response.informational_responses();
+        let mut num_informational = 0;
+        while informational.informational_response().await.is_some() {
+            num_informational += 1;
+        }
+        // Only the first ones are buffered.
+        assert_eq!(num_informational, 16);
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(informational);
+        drop(response);
+        drop(client);
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn recv_many_informational_responses_read_as_they_arrive() {
+    use tokio::sync::oneshot;
+
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+    let (read_tx, read_rx) = oneshot::channel();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        for _ in 0..10 {
+            srv.send_frame(frames::headers(1).response(103)).await;
+        }
+        read_rx.await.unwrap();
+        for _ in 0..10 {
+            srv.send_frame(frames::headers(1).response(103)).await;
+        }
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (mut response, _) = client.send_request(request, true).unwrap();
+        let mut informational = response.informational_responses();
+
+        // Informational responses that were read, on another task, no longer
+        // count against the buffer limit, so all of them are yielded.
+        let reader = tokio::spawn(async move {
+            for _ in 0..10 {
+                assert!(informational.informational_response().await.is_some());
+            }
+            read_tx.send(()).unwrap();
+
+            let mut num = 10;
+            while informational.informational_response().await.is_some() {
+                num += 1;
+            }
+            num
+        });
+        let num_informational = h2.drive(reader).await.unwrap();
+        assert_eq!(num_informational, 20);
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(response);
+        drop(client);
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn recv_informational_with_eos_is_stream_error() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(103).eos()).await;
+        srv.recv_frame(frames::reset(1).protocol_error()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(request, true).unwrap();
+
+        let err = h2.drive(response).await.unwrap_err();
+        assert_eq!(err.reason(), Some(Reason::PROTOCOL_ERROR));
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn expect_continue_holds_body_until_100() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("POST", "https://example.com/")
+                .field("expect", "100-continue"),
+        )
+        .await;
+        idle_ms(100).await;
+        srv.send_frame(frames::headers(1).response(100)).await;
+        srv.recv_frame(frames::data(1, "hello").eos()).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .expect_continue_timeout(Duration::from_secs(10))
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/")
+            .header("expect", "100-continue")
+            .body(())
+            .unwrap();
+        let (response, mut stream) = client.send_request(request, false).unwrap();
+        stream.reserve_capacity(5);
+
+        // The headers are sent, but the body is held back.
+        h2.drive(idle_ms(50)).await;
+        assert_eq!(stream.capacity(), 0);
+
+        let mut stream = h2.drive(util::wait_for_capacity(stream, 5)).await;
+        stream.send_data("hello".into(), true).unwrap();
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn expect_continue_releases_body_after_timeout() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("POST", "https://example.com/")
+                .field("expect", "100-continue"),
+        )
+        .await;
+        srv.recv_frame(frames::data(1, "hello").eos()).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .expect_continue_timeout(Duration::from_millis(50))
+            .timer(util::TokioTimer)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/")
+            .header("expect", "100-continue")
+            .body(())
+            .unwrap();
+        let (response, mut stream) = client.send_request(request, false).unwrap();
+        stream.reserve_capacity(5);
+
+        h2.drive(idle_ms(10)).await;
+        assert_eq!(stream.capacity(), 0);
+
+        // The server never answers, so the buffered body is sent once the
+        // timeout elapses, without the capacity being polled again.
+        stream.send_data("hello".into(), true).unwrap();
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn extended_connect_protocol_disabled_by_default() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        idle_ms(10).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+
+        // Let the connection read the server's SETTINGS frame.
+        h2.drive(idle_ms(5)).await;
+        assert!(!client.is_extended_connect_protocol_enabled());
+
+        let request = Request::builder()
+            .method(Method::CONNECT)
+            .uri("http://bread/baguette")
+            .extension(ext::Protocol::from_static("the-bread-protocol"))
+            .body(())
+            .unwrap();
+        client
+            .send_request(request, true)
+            .expect_err("send_request should error");
+
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn extended_connect_request() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv
+            .assert_client_handshake_with_settings(frames::settings().enable_connect_protocol(1))
+            .await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("CONNECT", "http://bread/baguette")
+                .protocol("the-bread-protocol")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+
+        h2.drive(idle_ms(5)).await;
+        assert!(client.is_extended_connect_protocol_enabled());
+
+        let request = Request::builder()
+            .method(Method::CONNECT)
+            .uri("http://bread/baguette")
+            .extension(ext::Protocol::from_static("the-bread-protocol"))
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(request, true).unwrap();
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn extended_connect_protocol_on_non_connect_request() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv
+            .assert_client_handshake_with_settings(frames::settings().enable_connect_protocol(1))
+            .await;
+        assert_default_settings!(settings);
+        idle_ms(10).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+
+        h2.drive(idle_ms(5)).await;
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("http://bread/baguette")
+            .extension(ext::Protocol::from_static("the-bread-protocol"))
+            .body(())
+            .unwrap();
+        client
+            .send_request(request, true)
+            .expect_err("send_request should error");
+
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
 const SETTINGS: &'static [u8] = &[0, 0, 0, 4, 0, 0, 0, 0, 0];
 const SETTINGS_ACK: &'static [u8] = &[0, 0, 0, 4, 1, 0, 0, 0, 0];
 
-trait MockH2 {
-    fn handshake(&mut self) -> &mut Self;
+trait MockH2 {
+    fn handshake(&mut self) -> &mut Self;
+}
+
+impl MockH2 for mock_io::Builder {
+    fn handshake(&mut self) -> &mut Self {
+        self.write(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+            // Settings frame
+            .write(SETTINGS)
+            .read(SETTINGS)
+            .read(SETTINGS_ACK)
+    }
+}
+
+#[tokio::test]
+async fn connection_extension_frames() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::unknown(0, 0xf0, b"hello")).await;
+        srv.send_frame(frames::unknown(0, 0xf1, b"world")).await;
+        // Frames on idle streams are ignored.
+        srv.send_frame(frames::unknown(3, 0xf1, b"idle")).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.expect("handshake");
+
+        let frame = ext::ExtensionFrame::new(0xf0, 0, Bytes::from_static(b"hello"));
+        client.send_extension_frame(frame).unwrap();
+
+        let frame = conn
+            .drive(poll_fn(|cx| client.poll_extension_frame(cx)))
+            .await
+            .unwrap();
+        assert_eq!(frame.kind(), 0xf1);
+        assert_eq!(frame.payload(), &b"world"[..]);
+
+        // Frame types implemented by h2 cannot be sent this way.
+        let ping = ext::ExtensionFrame::new(6, 0, Bytes::from_static(b"12345678"));
+        assert!(client.send_extension_frame(ping).is_err());
+
+        conn.drive(idle_ms(10)).await;
+        drop(client);
+        conn.await.expect("client");
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn recv_alt_svc() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        // Ignored: no origin on stream 0.
+        srv.send_frame(frames::alt_svc(0, "", "h3=\":443\"")).await;
+        // Ignored: the origin length is larger than the payload.
+        srv.send_bytes(&[0, 0, 3, 0x0a, 0, 0, 0, 0, 0, 0, 16, b'h'])
+            .await;
+        srv.send_frame(frames::alt_svc(0, "https://example.com", "h3=\":443\""))
+            .await;
+        // Ignored: an origin on a stream.
+        srv.send_frame(frames::alt_svc(1, "https://example.com", "h3=\":443\""))
+            .await;
+        // Ignored: the stream is idle.
+        srv.send_frame(frames::alt_svc(3, "", "h3=\":443\"")).await;
+        srv.send_frame(frames::alt_svc(1, "", "h3=\":8443\"")).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.expect("handshake");
+        let request = Request::builder()
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+
+        let (response, _) = client.send_request(request, true).unwrap();
+        let response = conn.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let stream_id = response.body().stream_id();
+
+        let alt_svc = conn
+            .drive(poll_fn(|cx| client.poll_alt_svc(cx)))
+            .await
+            .unwrap();
+        assert_eq!(alt_svc.stream_id(), None);
+        assert_eq!(alt_svc.origin(), Some(&b"https://example.com"[..]));
+        assert_eq!(alt_svc.value(), b"h3=\":443\"");
+
+        let alt_svc = conn
+            .drive(poll_fn(|cx| client.poll_alt_svc(cx)))
+            .await
+            .unwrap();
+        assert_eq!(alt_svc.stream_id(), Some(stream_id));
+        assert_eq!(alt_svc.origin(), None);
+        assert_eq!(alt_svc.value(), b"h3=\":8443\"");
+
+        drop(response);
+        drop(client);
+        conn.await.expect("client");
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn recv_origin_set() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.send_frame(frames::origin(&["https://example.com"]))
+            .await;
+        idle_ms(10).await;
+        srv.send_frame(frames::origin(&[
+            "https://example.com",
+            "https://www.example.com",
+        ]))
+        .await;
+        idle_ms(10).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (client, mut conn) = client::handshake(io).await.expect("handshake");
+        assert_eq!(client.origin_set(), None);
+
+        conn.drive(idle_ms(5)).await;
+        assert_eq!(
+            client.origin_set(),
+            Some(vec!["https://example.com".to_string()])
+        );
+
+        conn.drive(idle_ms(10)).await;
+        assert_eq!(
+            client.origin_set(),
+            Some(vec![
+                "https://example.com".to_string(),
+                "https://www.example.com".to_string(),
+            ])
+        );
+
+        drop(client);
+        conn.await.expect("client");
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn custom_settings() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv
+            .assert_client_handshake_with_settings(frames::settings().custom(0xf000, 7))
+            .await;
+        assert_frame_eq(settings, frames::settings().custom(0xf001, 1));
+        // Later frames update the settings.
+        srv.send_frame(frames::settings().custom(0xf000, 9).custom(0xf003, 0))
+            .await;
+        srv.recv_frame(frames::settings_ack()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (client, mut conn) = client::Builder::new()
+            .custom_setting(0xf001, 1)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        conn.drive(idle_ms(10)).await;
+
+        let settings = conn.remote_settings();
+        assert_eq!(settings.get(0xf000), Some(9));
+        assert_eq!(settings.get(0xf003), Some(0));
+        assert_eq!(
+            settings.iter().collect::<Vec<_>>(),
+            vec![(0xf000, 9), (0xf003, 0)]
+        );
+
+        drop(client);
+        conn.await.expect("client");
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn poll_remote_settings() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv
+            .assert_client_handshake_with_settings(
+                frames::settings()
+                    .max_concurrent_streams(10)
+                    .enable_connect_protocol(1),
+            )
+            .await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.expect("handshake");
+
+        let settings = conn
+            .drive(poll_fn(|cx| client.poll_remote_settings(cx)))
+            .await
+            .expect("poll_remote_settings");
+
+        assert_eq!(settings.max_concurrent_streams(), Some(10));
+        assert!(settings.is_extended_connect_protocol_enabled());
+        assert_eq!(settings.initial_window_size(), 65_535);
+
+        assert_eq!(client.remote_settings().max_concurrent_streams(), Some(10));
+
+        drop(client);
+        conn.await.expect("client");
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn h2c_upgrade() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_eq!(settings.initial_window_size(), Some(1_000));
+        // The response to the upgrade request
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, "hello").eos()).await;
+        srv.recv_frame(
+            frames::headers(3)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(3).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let mut builder = client::Builder::new();
+        builder.initial_window_size(1_000);
+
+        // INITIAL_WINDOW_SIZE = 1000
+        assert_eq!(builder.http2_settings(), "AAQAAAPo");
+
+        let (mut client, response, mut conn) = builder
+            .handshake_upgrade::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        let response = conn.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut body = response.into_body();
+        let data = conn.drive(body.data()).await.unwrap().unwrap();
+        assert_eq!(data, "hello");
+        assert!(body.is_end_stream());
+
+        let request = Request::builder()
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(request, true).unwrap();
+        let response = conn.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(body);
+        drop(response);
+        drop(client);
+        conn.await.expect("client");
+    };
+
+    join(srv, client).await;
+}
+
+#[test]
+fn http2_settings_apply_recv_memory_budget() {
+    let mut builder = client::Builder::new();
+    builder.recv_memory_budget(50).max_concurrent_streams(100);
+
+    // MAX_CONCURRENT_STREAMS = 100, INITIAL_WINDOW_SIZE = 1
+    assert_eq!(builder.http2_settings(), "AAMAAABkAAQAAAAB");
+}
+
+#[tokio::test]
+async fn handshake_upgrade_respects_max_send_streams() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        srv.read_preface().await.unwrap();
+    };
+
+    let client = async move {
+        let err = client::Builder::new()
+            .initial_max_send_streams(0)
+            .handshake_upgrade::<_, Bytes>(io)
+            .await
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "user error: rejected");
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn send_request_with_priority() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.recv_frame(
+            frames::headers(3)
+                .request("GET", "https://example.com/style.css")
+                .eos()
+                .stream_dep(1, 32, true),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_frame(frames::priority(3, 0).weight(256).exclusive())
+            .await;
+        srv.send_frame(frames::headers(3).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.expect("handshake");
+
+        let request = Request::builder()
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (response1, _) = client.send_request(request, true).unwrap();
+
+        let mut request = Request::builder()
+            .uri("https://example.com/style.css")
+            .body(())
+            .unwrap();
+        request.extensions_mut().insert(
+            h2::ext::StreamDependency::new(32)
+                .parent(response1.stream_id())
+                .exclusive(true),
+        );
+        let (mut response3, _) = client.send_request(request, true).unwrap();
+
+        let response1 = conn.drive(response1).await.unwrap();
+        assert_eq!(response1.status(), StatusCode::OK);
+
+        // A stream cannot depend on itself
+        let self_dep = h2::ext::StreamDependency::new(16).parent(response3.stream_id());
+        assert!(response3.reprioritize(self_dep).is_err());
+
+        response3
+            .reprioritize(h2::ext::StreamDependency::new(256).exclusive(true))
+            .unwrap();
+        let response3 = conn.drive(response3).await.unwrap();
+        assert_eq!(response3.status(), StatusCode::OK);
+
+        drop(response1);
+        drop(response3);
+        drop(client);
+        conn.await.expect("client");
+    };
+
+    join(srv, client).await;
 }
 
-impl MockH2 for mock_io::Builder {
-    fn handshake(&mut self) -> &mut Self {
-        self.write(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
-            // Settings frame
-            .write(SETTINGS)
-            .read(SETTINGS)
-            .read(SETTINGS_ACK)
-    }
+#[tokio::test]
+async fn reprioritize_before_headers_are_sent() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        // The dependency set before the HEADERS frame was written is sent in
+        // it, rather than in a PRIORITY frame ahead of it.
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos()
+                .stream_dep(0, 64, false),
+        )
+        .await;
+        srv.recv_frame(frames::priority(1, 0).weight(128)).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.expect("handshake");
+
+        let request = Request::builder()
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (mut response, _) = client.send_request(request, true).unwrap();
+        response
+            .reprioritize(h2::ext::StreamDependency::new(64))
+            .unwrap();
+
+        // Write the HEADERS frame.
+        conn.drive(idle_ms(10)).await;
+
+        response
+            .reprioritize(h2::ext::StreamDependency::new(128))
+            .unwrap();
+        let response = conn.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(response);
+        drop(client);
+        conn.await.expect("client");
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn recv_go_away_with_debug_data() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        idle_ms(10).await;
+        srv.send_frame(
+            frames::go_away(1)
+                .reason(Reason::ENHANCE_YOUR_CALM)
+                .debug_data("too_many_requests"),
+        )
+        .await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.expect("handshake");
+
+        let request = Request::builder()
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(request, true).unwrap();
+        let stream_id = response.stream_id();
+        let response = conn.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(response);
+
+        let err = conn.await.expect_err("client should error");
+        assert!(err.is_go_away());
+        assert_eq!(err.reason(), Some(Reason::ENHANCE_YOUR_CALM));
+        assert_eq!(err.debug_data(), Some(&b"too_many_requests"[..]));
+        assert_eq!(err.last_stream_id(), Some(stream_id));
+
+        drop(client);
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn recv_graceful_go_away() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::go_away(1).debug_data("restarting"))
+            .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.expect("handshake");
+
+        let request = Request::builder()
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(request, true).unwrap();
+        let stream_id = response.stream_id();
+        let response = conn.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drop(response);
+        drop(client);
+
+        // A graceful GOAWAY is not an error, but can still be inspected.
+        (&mut conn).await.expect("client");
+        let go_away = conn.received_go_away().expect("GOAWAY");
+        assert_eq!(go_away.reason(), Reason::NO_ERROR);
+        assert_eq!(go_away.debug_data(), b"restarting");
+        assert_eq!(go_away.last_stream_id(), stream_id);
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn graceful_shutdown_lets_in_flight_requests_finish() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::headers(1).request("POST", "https://example.com/"))
+            .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.recv_frame(frames::go_away(0)).await;
+        srv.recv_frame(frames::data(1, "hello").eos()).await;
+        srv.send_frame(frames::data(1, "world").eos()).await;
+        srv.recv_eof().await;
+    };
+
+    let client = async move {
+        let (mut client, mut conn) = client::handshake(io).await.expect("handshake");
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (response, mut stream) = client.send_request(request, false).unwrap();
+        let response = conn.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        conn.graceful_shutdown();
+
+        stream.send_data("hello".into(), true).unwrap();
+        let body = conn.drive(util::concat(response.into_body())).await;
+        assert_eq!(body.unwrap(), "world");
+
+        // The connection closes once the request completes, even though the
+        // `SendRequest` handle is still alive.
+        conn.await.expect("client");
+        drop(client);
+    };
+
+    join(srv, client).await;
+}
+
+#[tokio::test]
+async fn connect_tunnel_over_stream_io() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::headers(1).request("CONNECT", "bread:443"))
+            .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, "hello")).await;
+        srv.recv_frame(frames::data(1, "world")).await;
+        srv.recv_frame(frames::data(1, "").eos()).await;
+        srv.send_frame(frames::data(1, "").eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+
+        let request = Request::builder()
+            .method(Method::CONNECT)
+            .uri("bread:443")
+            .body(())
+            .unwrap();
+        let (response, send) = client.send_request(request, false).unwrap();
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut tunnel = h2::StreamIo::new(send, response.into_body());
+        h2.drive(async {
+            tunnel.write_all(b"world").await.unwrap();
+            tunnel.shutdown().await.unwrap();
+
+            let mut buf = Vec::new();
+            tunnel.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf, b"hello");
+        })
+        .await;
+
+        drop(tunnel);
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn stream_io_uses_assigned_capacity() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::headers(1).request("CONNECT", "bread:443"))
+            .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.recv_frame(frames::data(1, "hello")).await;
+        srv.recv_frame(frames::data(1, "").eos()).await;
+        srv.send_frame(frames::data(1, "").eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+
+        let request = Request::builder()
+            .method(Method::CONNECT)
+            .uri("bread:443")
+            .body(())
+            .unwrap();
+        let (response, mut send) = client.send_request(request, false).unwrap();
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The capacity is assigned and polled before the tunnel is created.
+        send.reserve_capacity(10);
+        let send = h2.drive(util::wait_for_capacity(send, 10)).await;
+
+        let mut tunnel = h2::StreamIo::new(send, response.into_body());
+        h2.drive(async {
+            tunnel.write_all(b"hello").await.unwrap();
+            tunnel.shutdown().await.unwrap();
+            // Shutting down again does nothing.
+            tunnel.shutdown().await.unwrap();
+
+            let mut buf = Vec::new();
+            tunnel.read_to_end(&mut buf).await.unwrap();
+            assert!(buf.is_empty());
+        })
+        .await;
+
+        drop(tunnel);
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn connect_tunnel_reset_is_io_error() {
+    use tokio::io::AsyncReadExt;
+
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::headers(1).request("CONNECT", "bread:443"))
+            .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::reset(1).cancel()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+
+        let request = Request::builder()
+            .method(Method::CONNECT)
+            .uri("bread:443")
+            .body(())
+            .unwrap();
+        let (response, send) = client.send_request(request, false).unwrap();
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut tunnel = h2::StreamIo::new(send, response.into_body());
+        let mut buf = Vec::new();
+        let err = h2
+            .drive(tunnel.read_to_end(&mut buf))
+            .await
+            .expect_err("read_to_end");
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+        let err = err.into_inner().unwrap().downcast::<h2::Error>().unwrap();
+        assert_eq!(err.reason(), Some(Reason::CANCEL));
+
+        drop(tunnel);
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
 }
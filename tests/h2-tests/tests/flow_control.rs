@@ -287,6 +287,65 @@ async fn recv_data_overflows_stream_window() {
     join(mock, h2).await;
 }
 
+#[tokio::test]
+async fn recv_padding_is_released_automatically() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    // 1 pad length octet + 16_128 octets of data + 255 octets of padding
+    // fill the whole window.
+    let mut body = vec![255];
+    body.extend_from_slice(&[b'z'; 16_128][..]);
+    body.extend_from_slice(&[0; 255][..]);
+
+    let mock = async move {
+        let _ = srv.assert_client_handshake().await;
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, body).padded()).await;
+        // The padding is released on receipt, the data once the user
+        // releases it.
+        srv.recv_frame(frames::window_update(1, 256)).await;
+        srv.recv_frame(frames::window_update(1, 16_128)).await;
+        srv.send_frame(frames::data(1, &[0; 16][..]).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, conn) = client::Builder::new()
+            .initial_window_size(16_384)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+
+        let req = async move {
+            let resp = client.send_request(request, true).unwrap().0.await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let mut body = resp.into_parts().1;
+
+            let buf = body.data().await.unwrap().unwrap();
+            assert_eq!(buf.len(), 16_128);
+            body.flow_control().release_capacity(buf.len()).unwrap();
+
+            let buf = body.data().await.unwrap().unwrap();
+            assert_eq!(buf.len(), 16);
+            assert!(body.data().await.is_none());
+        };
+
+        join(async move { conn.await.unwrap() }, req).await;
+    };
+    join(mock, h2).await;
+}
+
 #[test]
 #[ignore]
 fn recv_window_update_causes_overflow() {
@@ -1561,3 +1620,680 @@ async fn data_padding() {
 
     join(srv, h2).await;
 }
+
+#[tokio::test]
+async fn send_padding_does_not_use_reserved_capacity() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("POST", "https://example.com/")
+                .padded(),
+        )
+        .await;
+        srv.recv_frame(frames::data(1, &b"hello"[..]).padding(8))
+            .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.recv_frame(frames::data(1, &b""[..]).padding(8).eos())
+            .await;
+        srv.send_frame(frames::data(1, &b""[..]).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut conn) = client::Builder::new()
+            .padding(h2::Padding::fixed(8))
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+
+        let (response, mut stream) = client.send_request(request, false).unwrap();
+
+        stream.reserve_capacity(100);
+        let capacity = conn
+            .drive(poll_fn(|cx| stream.poll_capacity(cx)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(capacity, 100);
+
+        stream.send_data("hello".into(), false).unwrap();
+        let response = conn.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The padding is taken from the connection window, not from the
+        // capacity reserved for the stream.
+        assert_eq!(stream.capacity(), 95);
+
+        stream.send_data("".into(), true).unwrap();
+        drop(client);
+        drop(stream);
+        drop(response);
+        conn.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn adaptive_window_grows_windows_from_bdp_estimate() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "http://example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, vec![0; 16_384])).await;
+        // The first DATA frame starts an estimate.
+        srv.recv_frame(frames::ping(frame::Ping::BDP)).await;
+        // A whole window is received over the round trip.
+        srv.send_frame(frames::data(1, vec![0; 16_384])).await;
+        srv.send_frame(frames::data(1, vec![0; 16_384])).await;
+        srv.send_frame(frames::data(1, vec![0; 16_383])).await;
+        srv.send_frame(frames::ping(frame::Ping::BDP).pong()).await;
+        // So both windows are doubled.
+        srv.recv_frame(frames::window_update(0, 65_535)).await;
+        srv.recv_frame(frames::window_update(1, 65_535)).await;
+        srv.send_frame(frames::data(1, "").eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .adaptive_window(1024 * 1024)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("http://example.com/")
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(request, true).unwrap();
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Hold on to the body without releasing any capacity.
+        let _body = response.into_body();
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn auto_release_capacity_sends_window_update() {
+    h2_support::trace_init!();
+
+    let payload = vec![0u8; 16_384];
+
+    let (io, mut srv) = mock::new();
+
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, &payload[..])).await;
+        srv.send_frame(frames::data(1, &payload[..])).await;
+        // The capacity is released once enough data has been read.
+        srv.recv_frame(frames::window_update(0, 32_768)).await;
+        srv.recv_frame(frames::window_update(1, 32_768)).await;
+        srv.send_frame(frames::data(1, &payload[..])).await;
+        srv.send_frame(frames::data(1, &payload[..]).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, h2) = client::Builder::new()
+            .auto_release_capacity(true)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+
+        let req = async move {
+            let resp = client.send_request(request, true).unwrap().0.await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let mut body = resp.into_body();
+
+            for _ in 0..4 {
+                let buf = body.data().await.unwrap().unwrap();
+                assert_eq!(buf.len(), 16_384);
+            }
+            assert!(body.data().await.is_none());
+
+            // The capacity has been released already.
+            assert!(body.flow_control().release_capacity(1).is_err());
+        };
+
+        join(
+            async move {
+                h2.await.unwrap();
+            },
+            req,
+        )
+        .await
+    };
+    join(mock, h2).await;
+}
+
+#[tokio::test]
+async fn auto_release_capacity_can_be_set_per_stream() {
+    h2_support::trace_init!();
+
+    let payload = vec![0u8; 16_384];
+
+    let (io, mut srv) = mock::new();
+
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, &payload[..])).await;
+        srv.send_frame(frames::data(1, &payload[..])).await;
+        srv.recv_frame(frames::window_update(0, 32_768)).await;
+        srv.recv_frame(frames::window_update(1, 32_768)).await;
+        srv.send_frame(frames::data(1, &payload[..]).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, h2) = client::handshake(io).await.unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+
+        let req = async move {
+            let resp = client.send_request(request, true).unwrap().0.await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let mut body = resp.into_body();
+            body.flow_control().set_auto_release(true);
+
+            for _ in 0..3 {
+                let buf = body.data().await.unwrap().unwrap();
+                assert_eq!(buf.len(), 16_384);
+            }
+            assert!(body.data().await.is_none());
+        };
+
+        join(
+            async move {
+                h2.await.unwrap();
+            },
+            req,
+        )
+        .await
+    };
+    join(mock, h2).await;
+}
+
+#[tokio::test]
+async fn auto_release_capacity_with_padded_data() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, &b"hello"[..]).padding(32))
+            .await;
+        srv.send_frame(frames::data(1, &b"world"[..]).padding(32).eos())
+            .await;
+    };
+
+    let h2 = async move {
+        let (mut client, h2) = client::Builder::new()
+            .auto_release_capacity(true)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+
+        let req = async move {
+            let resp = client.send_request(request, true).unwrap().0.await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let mut body = resp.into_body();
+
+            // The padding is released along with the data.
+            assert_eq!(body.data().await.unwrap().unwrap(), "hello");
+            assert_eq!(body.data().await.unwrap().unwrap(), "world");
+            assert!(body.data().await.is_none());
+        };
+
+        join(
+            async move {
+                h2.await.unwrap();
+            },
+            req,
+        )
+        .await
+    };
+    join(mock, h2).await;
+}
+
+#[tokio::test]
+async fn auto_release_capacity_rejects_releasing_read_data() {
+    h2_support::trace_init!();
+
+    let payload = vec![0u8; 16_384];
+
+    let (io, mut srv) = mock::new();
+
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, &payload[..])).await;
+        srv.send_frame(frames::data(1, &payload[..])).await;
+        srv.send_frame(frames::data(1, &payload[..])).await;
+        srv.recv_frame(frames::window_update(0, 49_152)).await;
+        srv.recv_frame(frames::window_update(1, 49_152)).await;
+        srv.send_frame(frames::data(1, "hello").eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, h2) = client::handshake(io).await.unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+
+        let req = async move {
+            let resp = client.send_request(request, true).unwrap().0.await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let mut body = resp.into_body();
+
+            // Let all the data arrive.
+            idle_ms(10).await;
+
+            // The first chunk is taken before automatic release is enabled.
+            let buf = body.data().await.unwrap().unwrap();
+            assert_eq!(buf.len(), 16_384);
+            body.flow_control().set_auto_release(true);
+            let buf = body.data().await.unwrap().unwrap();
+            assert_eq!(buf.len(), 16_384);
+
+            // The third chunk is still in flight, but only the first one can
+            // be released by the application.
+            assert!(body.flow_control().release_capacity(16_385).is_err());
+            body.flow_control().release_capacity(16_384).unwrap();
+            assert!(body.flow_control().release_capacity(1).is_err());
+
+            let buf = body.data().await.unwrap().unwrap();
+            assert_eq!(buf.len(), 16_384);
+            let buf = body.data().await.unwrap().unwrap();
+            assert_eq!(buf, "hello");
+            assert!(body.data().await.is_none());
+        };
+
+        join(
+            async move {
+                h2.await.unwrap();
+            },
+            req,
+        )
+        .await
+    };
+    join(mock, h2).await;
+}
+
+#[tokio::test]
+async fn max_send_buffer_size_limits_buffered_data() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::headers(1).request("POST", "https://www.example.com/"))
+            .await;
+        srv.recv_frame(frames::data(1, vec![0; 20])).await;
+        srv.recv_frame(frames::data(1, vec![0; 10])).await;
+        srv.recv_frame(frames::data(1, vec![0; 10]).eos()).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .max_send_buffer_size(10)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("https://www.example.com/")
+            .body(())
+            .unwrap();
+        let (response, mut stream) = client.send_request(request, false).unwrap();
+
+        // A chunk larger than the limit is accepted when nothing is
+        // buffered, but nothing more is until it is sent.
+        stream.send_data(vec![0; 20].into(), false).unwrap();
+        let err = stream.send_data(vec![0; 1].into(), false).unwrap_err();
+        assert_eq!(err.to_string(), "user error: send buffer full");
+        h2.drive(idle_ms(10)).await;
+
+        // The window is larger, but the capacity stops at the limit.
+        stream.reserve_capacity(20);
+        let capacity = h2.drive(poll_fn(|cx| stream.poll_capacity(cx))).await;
+        assert_eq!(capacity.unwrap().unwrap(), 10);
+        stream.send_data(vec![0; 10].into(), false).unwrap();
+        assert_eq!(stream.capacity(), 0);
+        assert!(stream.send_data(vec![0; 1].into(), false).is_err());
+
+        // Once the buffered data is sent, there is room for more.
+        let capacity = h2.drive(poll_fn(|cx| stream.poll_capacity(cx))).await;
+        assert_eq!(capacity.unwrap().unwrap(), 10);
+        stream.send_data(vec![0; 10].into(), true).unwrap();
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn max_connection_send_buffer_size_limits_buffered_data() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(frames::headers(1).request("POST", "https://www.example.com/"))
+            .await;
+        srv.recv_frame(frames::headers(3).request("POST", "https://www.example.com/"))
+            .await;
+        srv.recv_frame(frames::data(1, vec![0; 10]).eos()).await;
+        srv.recv_frame(frames::data(3, vec![0; 10]).eos()).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.send_frame(frames::headers(3).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .max_connection_send_buffer_size(15)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = || {
+            Request::builder()
+                .method(Method::POST)
+                .uri("https://www.example.com/")
+                .body(())
+                .unwrap()
+        };
+        let (response1, mut stream1) = client.send_request(request(), false).unwrap();
+        let (response3, mut stream3) = client.send_request(request(), false).unwrap();
+
+        // Neither stream has capacity, so their data adds up on the
+        // connection.
+        stream1.send_data(vec![0; 10].into(), true).unwrap();
+        let err = stream3.send_data(vec![0; 10].into(), true).unwrap_err();
+        assert_eq!(err.to_string(), "user error: send buffer full");
+
+        // Once the buffered data is sent, there is room for more.
+        h2.drive(idle_ms(10)).await;
+        stream3.send_data(vec![0; 10].into(), true).unwrap();
+
+        let response = h2.drive(response1).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = h2.drive(response3).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn recv_memory_budget_is_shared_by_streams() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let _ = srv.assert_client_handshake().await;
+        for id in [1, 3, 5] {
+            srv.recv_frame(
+                frames::headers(id)
+                    .request("GET", "https://www.example.com/")
+                    .eos(),
+            )
+            .await;
+        }
+        for id in [1, 3, 5] {
+            srv.send_frame(frames::headers(id).response(200)).await;
+        }
+        srv.send_frame(frames::data(1, vec![0; 16_384])).await;
+        // With three streams open, each gets a third of the budget.
+        srv.recv_frame(frames::window_update(1, 10_922)).await;
+        for id in [1, 3, 5] {
+            srv.send_frame(frames::data(id, "").eos()).await;
+        }
+    };
+
+    let h2 = async move {
+        let (mut client, h2) = client::Builder::new()
+            .initial_window_size(16_384)
+            .recv_memory_budget(32_768)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+
+        let mut responses = Vec::new();
+        for _ in 0..3 {
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri("https://www.example.com/")
+                .body(())
+                .unwrap();
+            let (response, _) = client.send_request(request, true).unwrap();
+            responses.push(response);
+        }
+
+        let req = async move {
+            for (i, response) in responses.into_iter().enumerate() {
+                let mut body = response.await.unwrap().into_body();
+                if i == 0 {
+                    let data = body.data().await.unwrap().unwrap();
+                    assert_eq!(data.len(), 16_384);
+                    body.flow_control().release_capacity(data.len()).unwrap();
+                }
+                while let Some(data) = body.data().await {
+                    assert!(data.unwrap().is_empty());
+                }
+            }
+        };
+
+        join(async move { h2.await.unwrap() }, req).await;
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn recv_memory_budget_applies_without_window_settings() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        // The initial stream window is capped to the budget.
+        assert_eq!(settings.initial_window_size(), Some(32_768));
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://www.example.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(frames::data(1, vec![0; 16_384])).await;
+        srv.send_frame(frames::data(1, vec![0; 16_384])).await;
+        // The connection window is not grown back past the budget, so only
+        // the stream window is updated.
+        srv.recv_frame(frames::window_update(1, 32_768)).await;
+        srv.send_frame(frames::data(1, "hello").eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, h2) = client::Builder::new()
+            .recv_memory_budget(32_768)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://www.example.com/")
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(request, true).unwrap();
+
+        let req = async move {
+            let mut body = response.await.unwrap().into_body();
+            for _ in 0..2 {
+                let data = body.data().await.unwrap().unwrap();
+                assert_eq!(data.len(), 16_384);
+                body.flow_control().release_capacity(data.len()).unwrap();
+            }
+            let data = body.data().await.unwrap().unwrap();
+            assert_eq!(data, "hello");
+            assert!(body.data().await.is_none());
+        };
+
+        join(async move { h2.await.unwrap() }, req).await;
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn initial_window_size_of_request_stream() {
+    h2_support::trace_init!();
+    let (io, mut srv) = mock::new();
+
+    let srv = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://www.example.com/")
+                .eos(),
+        )
+        .await;
+        // The window is grown right after the request is sent.
+        srv.recv_frame(frames::window_update(1, (1 << 20) - 65_535))
+            .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+        let mut request = Request::builder()
+            .method(Method::GET)
+            .uri("https://www.example.com/")
+            .body(())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(h2::ext::InitialWindowSize::new(1 << 20));
+        let (response, _) = client.send_request(request, true).unwrap();
+
+        let response = h2.drive(response).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        h2.await.unwrap();
+    };
+
+    join(srv, h2).await;
+}
+
+#[tokio::test]
+async fn server_target_window_size_of_accepted_stream() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("POST", "https://example.com/"))
+            .await;
+        client
+            .recv_frame(frames::window_update(1, 1_000_000 - 65_535))
+            .await;
+        client.send_frame(frames::data(1, "hello").eos()).await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let mut body = req.into_body();
+        body.flow_control().set_target_window_size(1_000_000);
+
+        let srv = async move {
+            assert!(srv.next().await.is_none());
+        };
+        let body = async move {
+            let data = body.data().await.unwrap().unwrap();
+            assert_eq!(data, "hello");
+
+            let rsp = http::Response::builder().status(200).body(()).unwrap();
+            stream.send_response(rsp, true).unwrap();
+        };
+
+        join(srv, body).await;
+    };
+
+    join(client, srv).await;
+}
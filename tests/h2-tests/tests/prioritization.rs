@@ -408,3 +408,524 @@ async fn send_data_receive_window_update() {
 
     join(mock, h2).await;
 }
+
+#[tokio::test]
+async fn send_data_in_dependency_order() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        // Stream 1 depends on stream 3, which is still idle.
+        client
+            .send_frame(frames::priority(1, 3).weight(256).exclusive())
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client
+            .recv_frame(frames::data(3, &b"world"[..]).eos())
+            .await;
+        client
+            .recv_frame(frames::data(1, &b"hello"[..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        stream1.send_data("hello".into(), true).unwrap();
+        stream3.send_data("world".into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_data_by_weight() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        // Stream 1 gets four times the share of stream 3.
+        client.send_frame(frames::priority(1, 0).weight(256)).await;
+        client.send_frame(frames::priority(3, 0).weight(64)).await;
+        for id in [1, 3] {
+            client
+                .send_frame(
+                    frames::headers(id)
+                        .request("GET", "https://example.com/")
+                        .eos(),
+                )
+                .await;
+        }
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client.recv_frame(frames::data(1, &b"aaaaa"[..])).await;
+        client.recv_frame(frames::data(3, &b"bbbbb"[..])).await;
+        client.recv_frame(frames::data(1, &b"aaaaa"[..])).await;
+        client.recv_frame(frames::data(1, &b"aaaaa"[..])).await;
+        client
+            .recv_frame(frames::data(1, &b"aaaaa"[..]).eos())
+            .await;
+        client.recv_frame(frames::data(3, &b"bbbbb"[..])).await;
+        client.recv_frame(frames::data(3, &b"bbbbb"[..])).await;
+        client
+            .recv_frame(frames::data(3, &b"bbbbb"[..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        for i in 0..4 {
+            stream1.send_data("aaaaa".into(), i == 3).unwrap();
+            stream3.send_data("bbbbb".into(), i == 3).unwrap();
+        }
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_data_by_urgency() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .field("priority", "u=0")
+                    .eos(),
+            )
+            .await;
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client
+            .recv_frame(frames::data(3, &b"world"[..]).eos())
+            .await;
+        client
+            .recv_frame(frames::data(1, &b"hello"[..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        assert_eq!(stream1.priority(), h2::ext::Priority::default());
+        assert_eq!(stream3.priority(), h2::ext::Priority::new(0, false));
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        stream1.send_data("hello".into(), true).unwrap();
+        stream3.send_data("world".into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_data_non_incremental_streams_in_order() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let payload = vec![0; 20_000];
+    let payload_clone = payload.clone();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .field("priority", "u=2")
+                    .eos(),
+            )
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .field("priority", "u=2")
+                    .eos(),
+            )
+            .await;
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client.recv_frame(frames::data(1, &payload[..16_384])).await;
+        client
+            .recv_frame(frames::data(1, &payload[16_384..]).eos())
+            .await;
+        client.recv_frame(frames::data(3, &payload[..16_384])).await;
+        client
+            .recv_frame(frames::data(3, &payload[16_384..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        stream1
+            .send_data(payload_clone.clone().into(), true)
+            .unwrap();
+        stream3.send_data(payload_clone.into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_data_incremental_streams_round_robin() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let payload = vec![0; 20_000];
+    let payload_clone = payload.clone();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .field("priority", "u=2, i")
+                    .eos(),
+            )
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .field("priority", "u=2, i")
+                    .eos(),
+            )
+            .await;
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client.recv_frame(frames::data(1, &payload[..16_384])).await;
+        client.recv_frame(frames::data(3, &payload[..16_384])).await;
+        client
+            .recv_frame(frames::data(1, &payload[16_384..]).eos())
+            .await;
+        client
+            .recv_frame(frames::data(3, &payload[16_384..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        stream1
+            .send_data(payload_clone.clone().into(), true)
+            .unwrap();
+        stream3.send_data(payload_clone.into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn priority_update_before_request() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        // The update takes precedence over the header
+        client
+            .send_frame(frames::priority_update(3, "u=0, i"))
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .field("priority", "u=7")
+                    .eos(),
+            )
+            .await;
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client
+            .recv_frame(frames::data(3, &b"world"[..]).eos())
+            .await;
+        client
+            .recv_frame(frames::data(1, &b"hello"[..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        assert_eq!(stream3.priority(), h2::ext::Priority::new(0, true));
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        stream1.send_data("hello".into(), true).unwrap();
+        stream3.send_data("world".into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn server_set_priority() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client
+            .recv_frame(frames::data(3, &b"world"[..]).eos())
+            .await;
+        client
+            .recv_frame(frames::data(1, &b"hello"[..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        stream3.set_priority(h2::ext::Priority::new(1, false));
+        assert_eq!(stream3.priority(), h2::ext::Priority::new(1, false));
+
+        stream1.send_data("hello".into(), true).unwrap();
+        stream3.send_data("world".into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn disable_rfc7540_priorities() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_eq!(settings.is_rfc7540_priorities_disabled(), Some(true));
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        // Ignored, stream 1 does not wait for stream 3.
+        client
+            .send_frame(frames::priority(1, 3).weight(256).exclusive())
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client
+            .recv_frame(frames::data(1, &b"hello"[..]).eos())
+            .await;
+        client
+            .recv_frame(frames::data(3, &b"world"[..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut builder = server::Builder::new();
+        builder.disable_rfc7540_priorities();
+
+        let mut srv = builder.handshake::<_, Bytes>(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        stream1.send_data("hello".into(), true).unwrap();
+        stream3.send_data("world".into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn remote_disables_rfc7540_priorities() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        client
+            .assert_server_handshake_with_settings(frames::settings().no_rfc7540_priorities(1))
+            .await;
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .send_frame(frames::priority(1, 3).weight(256).exclusive())
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::headers(3).response(200)).await;
+        client
+            .recv_frame(frames::data(1, &b"hello"[..]).eos())
+            .await;
+        client
+            .recv_frame(frames::data(3, &b"world"[..]).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_, mut stream1) = srv.next().await.unwrap().unwrap();
+        let (_, mut stream3) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream1 = stream1.send_response(rsp, false).unwrap();
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream3 = stream3.send_response(rsp, false).unwrap();
+
+        stream1.send_data("hello".into(), true).unwrap();
+        stream3.send_data("world".into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
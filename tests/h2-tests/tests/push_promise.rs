@@ -368,7 +368,8 @@ async fn recv_push_promise_skipped_stream_id() {
             frames::push_promise(1, 2).request("GET", "https://http2.akamai.com/style.css"),
         )
         .await;
-        srv.recv_frame(frames::go_away(0).protocol_error()).await;
+        // The push promise for stream 4 was accepted before the error.
+        srv.recv_frame(frames::go_away(4).protocol_error()).await;
     };
 
     let h2 = async move {
@@ -422,7 +423,8 @@ async fn recv_push_promise_dup_stream_id() {
             frames::push_promise(1, 2).request("GET", "https://http2.akamai.com/style.css"),
         )
         .await;
-        srv.recv_frame(frames::go_away(0).protocol_error()).await;
+        // The first push promise for stream 2 was accepted before the error.
+        srv.recv_frame(frames::go_away(2).protocol_error()).await;
     };
 
     let h2 = async move {
@@ -453,3 +455,322 @@ async fn recv_push_promise_dup_stream_id() {
 
     join(mock, h2).await;
 }
+
+#[tokio::test]
+async fn graceful_shutdown_ignores_later_push_promises() {
+    h2_support::trace_init!();
+
+    let (io, mut srv) = mock::new();
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200)).await;
+        srv.send_frame(
+            frames::push_promise(1, 2).request("GET", "https://http2.akamai.com/style.css"),
+        )
+        .await;
+        srv.recv_frame(frames::go_away(2)).await;
+        // Sent before the server saw the GOAWAY, so the client ignores it.
+        srv.send_frame(
+            frames::push_promise(1, 4).request("GET", "https://http2.akamai.com/style2.css"),
+        )
+        .await;
+        srv.send_frame(frames::headers(4).response(200).eos()).await;
+        srv.send_frame(frames::headers(2).response(200).eos()).await;
+        srv.send_frame(frames::data(1, "").eos()).await;
+        srv.recv_eof().await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::handshake(io).await.unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+        let (mut resp, _) = client.send_request(request, true).unwrap();
+        let mut pushed = resp.push_promises();
+
+        let push = h2.drive(pushed.next()).await.unwrap().unwrap();
+        h2.graceful_shutdown();
+
+        let check_resp = async move {
+            let resp = resp.await.unwrap();
+            let body = util::concat(resp.into_body()).await.unwrap();
+            assert!(body.is_empty());
+        };
+        let check_pushes = async move {
+            let (_, response) = push.into_parts();
+            let response = response.await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(pushed.next().await.is_none());
+        };
+
+        h2.drive(join(check_resp, check_pushes)).await;
+        h2.await.expect("client");
+        drop(client);
+    };
+
+    join(mock, h2).await;
+}
+
+#[tokio::test]
+async fn push_cache_serves_matching_requests() {
+    h2_support::trace_init!();
+
+    let (io, mut srv) = mock::new();
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(
+            frames::push_promise(1, 2).request("GET", "https://http2.akamai.com/style.css"),
+        )
+        .await;
+        srv.send_frame(
+            frames::push_promise(1, 4).request("GET", "https://http2.akamai.com/app.js"),
+        )
+        .await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.send_frame(frames::headers(2).response(200)).await;
+        srv.send_frame(frames::data(2, "promised_data").eos()).await;
+        srv.send_frame(
+            frames::headers(4)
+                .response(200)
+                .field("vary", "accept-encoding")
+                .eos(),
+        )
+        .await;
+        // The pushed response varies on a header the request sets
+        // differently, so it is requested over a new stream.
+        srv.recv_frame(
+            frames::headers(3)
+                .request("GET", "https://http2.akamai.com/app.js")
+                .field("accept-encoding", "gzip")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(frames::headers(3).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .push_cache(8, Duration::from_secs(60))
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+        let (resp, _) = client.send_request(request, true).unwrap();
+        let resp = h2.drive(resp).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Let the pushed responses come in.
+        h2.drive(idle_ms(10)).await;
+
+        // Pushes are matched on method, authority and path only.
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("http://http2.akamai.com/style.css")
+            .body(())
+            .unwrap();
+        let (resp, _) = client.send_request(request, true).unwrap();
+        let resp = h2.drive(resp).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = h2.drive(util::concat(resp.into_body())).await.unwrap();
+        assert_eq!(body, "promised_data");
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/app.js")
+            .header("accept-encoding", "gzip")
+            .body(())
+            .unwrap();
+        let (resp, _) = client.send_request(request, true).unwrap();
+        let resp = h2.drive(resp).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    };
+
+    join(mock, h2).await;
+}
+
+#[tokio::test]
+async fn push_cache_cancels_evicted_streams() {
+    h2_support::trace_init!();
+
+    let (io, mut srv) = mock::new();
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(
+            frames::push_promise(1, 2).request("GET", "https://http2.akamai.com/style.css"),
+        )
+        .await;
+        srv.send_frame(
+            frames::push_promise(1, 4).request("GET", "https://http2.akamai.com/app.js"),
+        )
+        .await;
+        // The cache only holds one stream, so the oldest one is canceled.
+        srv.recv_frame(frames::reset(2).cancel()).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        // The remaining stream is canceled once it expires.
+        srv.recv_frame(frames::reset(4).cancel()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .push_cache(1, Duration::from_millis(20))
+            .timer(util::TokioTimer)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+        let (resp, _) = client.send_request(request, true).unwrap();
+        let resp = h2.drive(resp).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // The timer wakes up the idle connection once the push expires.
+        h2.await.unwrap();
+    };
+
+    join(mock, h2).await;
+}
+
+#[tokio::test]
+async fn push_policy_resets_refused_pushes() {
+    h2_support::trace_init!();
+
+    let (io, mut srv) = mock::new();
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(
+            frames::push_promise(1, 2).request("GET", "https://http2.akamai.com/style.css"),
+        )
+        .await;
+        srv.send_frame(
+            frames::push_promise(1, 4).request("GET", "https://http2.akamai.com/app.js"),
+        )
+        .await;
+        srv.recv_frame(frames::reset(4).cancel()).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.send_frame(frames::headers(2).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .push_policy(|request| {
+                if request.uri().path().ends_with(".css") {
+                    Ok(())
+                } else {
+                    Err(Reason::CANCEL)
+                }
+            })
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+        let (mut resp, _) = client.send_request(request, true).unwrap();
+        let pushed = resp.push_promises();
+        let check_resp_status = async move {
+            let resp = resp.await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        };
+        let check_pushed = async move {
+            let ps: Vec<_> = pushed
+                .map_ok(|p| p.into_parts().0.uri().path().to_owned())
+                .collect()
+                .await;
+            assert_eq!(ps.len(), 1);
+            assert_eq!(ps[0].as_ref().unwrap(), "/style.css");
+        };
+
+        h2.drive(join(check_resp_status, check_pushed)).await;
+    };
+
+    join(mock, h2).await;
+}
+
+#[tokio::test]
+async fn max_pending_pushed_streams_refuses_extra_pushes() {
+    h2_support::trace_init!();
+
+    let (io, mut srv) = mock::new();
+    let mock = async move {
+        let settings = srv.assert_client_handshake().await;
+        assert_default_settings!(settings);
+        srv.recv_frame(
+            frames::headers(1)
+                .request("GET", "https://http2.akamai.com/")
+                .eos(),
+        )
+        .await;
+        srv.send_frame(
+            frames::push_promise(1, 2).request("GET", "https://http2.akamai.com/style.css"),
+        )
+        .await;
+        srv.send_frame(
+            frames::push_promise(1, 4).request("GET", "https://http2.akamai.com/app.js"),
+        )
+        .await;
+        srv.recv_frame(frames::reset(4).refused()).await;
+        srv.send_frame(frames::headers(1).response(200).eos()).await;
+        srv.send_frame(frames::headers(2).response(200).eos()).await;
+    };
+
+    let h2 = async move {
+        let (mut client, mut h2) = client::Builder::new()
+            .max_pending_pushed_streams(1)
+            .handshake::<_, Bytes>(io)
+            .await
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("https://http2.akamai.com/")
+            .body(())
+            .unwrap();
+        let (mut resp, _) = client.send_request(request, true).unwrap();
+        let pushed = resp.push_promises();
+        let resp = h2.drive(resp).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let ps: Vec<_> = h2.drive(pushed.collect()).await;
+        assert_eq!(ps.len(), 1);
+    };
+
+    join(mock, h2).await;
+}
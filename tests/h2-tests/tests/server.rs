@@ -3,6 +3,7 @@
 use futures::future::{join, poll_fn};
 use futures::StreamExt;
 use h2_support::prelude::*;
+use std::task::Poll;
 use tokio::io::AsyncWriteExt;
 
 const SETTINGS: &'static [u8] = &[0, 0, 0, 4, 0, 0, 0, 0, 0];
@@ -136,6 +137,109 @@ async fn serve_connect() {
     join(client, srv).await;
 }
 
+#[tokio::test]
+async fn extended_connect_protocol_enabled_during_handshake() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_eq!(settings.is_extended_connect_protocol_enabled(), Some(true));
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("CONNECT", "http://bread/baguette")
+                    .protocol("the-bread-protocol")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut builder = server::Builder::new();
+        builder.enable_connect_protocol();
+
+        let mut srv = builder.handshake::<_, Bytes>(io).await.expect("handshake");
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        assert_eq!(req.method(), &http::Method::CONNECT);
+        assert_eq!(req.uri(), "http://bread/baguette");
+        assert_eq!(
+            req.extensions().get::<ext::Protocol>(),
+            Some(&ext::Protocol::from_static("the-bread-protocol"))
+        );
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn reject_pseudo_protocol_when_not_enabled() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("CONNECT", "http://bread/baguette")
+                    .protocol("the-bread-protocol")
+                    .eos(),
+            )
+            .await;
+        client.recv_frame(frames::reset(1).protocol_error()).await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn reject_pseudo_protocol_on_non_connect_request() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_eq!(settings.is_extended_connect_protocol_enabled(), Some(true));
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "http://bread/baguette")
+                    .protocol("the-bread-protocol")
+                    .eos(),
+            )
+            .await;
+        client.recv_frame(frames::reset(1).protocol_error()).await;
+    };
+
+    let srv = async move {
+        let mut builder = server::Builder::new();
+        builder.enable_connect_protocol();
+
+        let mut srv = builder.handshake::<_, Bytes>(io).await.expect("handshake");
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
 #[tokio::test]
 async fn push_request() {
     h2_support::trace_init!();
@@ -628,6 +732,206 @@ async fn abrupt_shutdown() {
     join(client, srv).await;
 }
 
+#[tokio::test]
+async fn abrupt_shutdown_with_debug_data() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("POST", "https://example.com/"))
+            .await;
+        client
+            .recv_frame(
+                frames::go_away(1)
+                    .internal_error()
+                    .debug_data("database unavailable"),
+            )
+            .await;
+        client.recv_eof().await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (req, tx) = srv.next().await.unwrap().expect("server receives request");
+
+        srv.abrupt_shutdown_with_debug_data(
+            Reason::INTERNAL_ERROR,
+            Bytes::from_static(b"database unavailable"),
+        );
+        drop((req, tx));
+
+        poll_fn(move |cx| srv.poll_closed(cx))
+            .await
+            .expect("server");
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn graceful_shutdown_with_debug_data() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::go_away(2147483647).debug_data("restarting"))
+            .await;
+        client.recv_frame(frames::ping(frame::Ping::SHUTDOWN)).await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+        client
+            .send_frame(frames::ping(frame::Ping::SHUTDOWN).pong())
+            .await;
+        client
+            .recv_frame(frames::go_away(1).debug_data("restarting"))
+            .await;
+        client.recv_eof().await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_req, mut stream) = srv.next().await.unwrap().expect("request");
+
+        srv.graceful_shutdown_with_debug_data(Bytes::from_static(b"restarting"));
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn graceful_shutdown_with_deadline() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("POST", "https://example.com/"))
+            .await;
+        client.recv_frame(frames::go_away(2147483647)).await;
+        client.recv_frame(frames::ping(frame::Ping::SHUTDOWN)).await;
+        client
+            .send_frame(frames::ping(frame::Ping::SHUTDOWN).pong())
+            .await;
+        client.recv_frame(frames::go_away(1)).await;
+        // The request never finishes and the client stays idle, so the
+        // server closes the connection once the deadline has passed.
+        client
+            .recv_frame(frames::go_away(1).reason(Reason::CANCEL))
+            .await;
+        client.recv_eof().await;
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .timer(util::TokioTimer)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+        let (req, _stream) = srv.next().await.unwrap().expect("request");
+
+        srv.graceful_shutdown_with_deadline(
+            std::time::Instant::now() + Duration::from_millis(50),
+            Reason::CANCEL,
+        );
+        assert_eq!(srv.num_open_streams(), 1);
+
+        let req_fut = async move {
+            let err = util::concat(req.into_body())
+                .await
+                .expect_err("request body should error");
+            assert_eq!(err.reason(), Some(Reason::CANCEL));
+        };
+        let srv_fut = async move {
+            poll_fn(move |cx| srv.poll_closed(cx))
+                .await
+                .expect("server");
+        };
+
+        join(req_fut, srv_fut).await;
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_informational_response() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(
+                frames::headers(1)
+                    .response(103)
+                    .field("link", "</style.css>; rel=preload; as=style"),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_req, mut stream) = srv.next().await.unwrap().expect("request");
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let err = stream.send_informational(rsp).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "user error: invalid informational status code"
+        );
+
+        let hints = http::Response::builder()
+            .status(103)
+            .header("link", "</style.css>; rel=preload; as=style")
+            .body(())
+            .unwrap();
+        stream.send_informational(hints).unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        // Informational responses can't follow the final response.
+        let hints = http::Response::builder().status(103).body(()).unwrap();
+        assert!(stream.send_informational(hints).is_err());
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
 #[tokio::test]
 async fn graceful_shutdown() {
     h2_support::trace_init!();
@@ -1093,3 +1397,646 @@ async fn serve_when_request_in_response_extensions() {
 
     join(client, srv).await;
 }
+
+#[tokio::test]
+async fn connection_extension_frames() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client.send_frame(frames::unknown(0, 0xf0, b"hello")).await;
+        client.recv_frame(frames::unknown(0, 0xf1, b"world")).await;
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+
+        let frame = poll_fn(|cx| {
+            if let Poll::Ready(frame) = srv.poll_extension_frame(cx) {
+                return Poll::Ready(frame);
+            }
+            assert!(srv.poll_accept(cx).is_pending());
+            Poll::Pending
+        })
+        .await
+        .expect("poll_extension_frame");
+
+        assert_eq!(frame.kind(), 0xf0);
+        assert_eq!(frame.flags(), 0);
+        assert_eq!(frame.payload(), &b"hello"[..]);
+
+        // Frame types implemented by h2 cannot be sent this way.
+        let data = ext::ExtensionFrame::new(0, 0, Bytes::from_static(b"nope"));
+        assert!(srv.send_extension_frame(data).is_err());
+
+        let frame = ext::ExtensionFrame::new(0xf1, 0, Bytes::from_static(b"world"));
+        srv.send_extension_frame(frame).unwrap();
+
+        let (_req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn stream_extension_frames() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(frames::headers(1).request("POST", "https://example.com/"))
+            .await;
+        client.send_frame(frames::unknown(1, 0xf0, b"ping")).await;
+        client.send_frame(frames::data(1, "hello").eos()).await;
+        client.recv_frame(frames::headers(1).response(200)).await;
+        client.recv_frame(frames::unknown(1, 0xf0, b"pong")).await;
+        client.recv_frame(frames::data(1, "world").eos()).await;
+        // Ignored, the stream is closed.
+        client.send_frame(frames::unknown(1, 0xf0, b"late")).await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let conn = async move {
+            assert!(srv.next().await.is_none());
+        };
+
+        let handler = async move {
+            let mut body = req.into_body();
+
+            let frame = poll_fn(|cx| body.poll_extension_frame(cx))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(frame.kind(), 0xf0);
+            assert_eq!(frame.payload(), &b"ping"[..]);
+
+            assert_eq!(body.data().await.unwrap().unwrap(), "hello");
+            assert!(poll_fn(|cx| body.poll_extension_frame(cx)).await.is_none());
+
+            let rsp = http::Response::builder().status(200).body(()).unwrap();
+            let mut tx = stream.send_response(rsp, false).unwrap();
+
+            let frame = ext::ExtensionFrame::new(0xf0, 0, Bytes::from_static(b"pong"));
+            tx.send_extension_frame(frame).unwrap();
+
+            tx.send_data("world".into(), true).unwrap();
+
+            // The send half is closed.
+            let frame = ext::ExtensionFrame::new(0xf0, 0, Bytes::from_static(b"late"));
+            assert!(tx.send_extension_frame(frame).is_err());
+        };
+
+        join(conn, handler).await;
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_alt_svc() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::alt_svc(0, "https://example.com", "h3=\":443\""))
+            .await;
+        client
+            .recv_frame(frames::alt_svc(1, "", "h3=\":8443\""))
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+        // Servers ignore ALTSVC frames.
+        client
+            .send_frame(frames::alt_svc(0, "https://example.com", "clear"))
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        srv.send_alt_svc(
+            "https://example.com",
+            http::HeaderValue::from_static("h3=\":443\""),
+        )
+        .unwrap();
+
+        // Origins must not be empty.
+        assert!(srv
+            .send_alt_svc("", http::HeaderValue::from_static("h3=\":443\""))
+            .is_err());
+
+        stream
+            .send_alt_svc(http::HeaderValue::from_static("h3=\":8443\""))
+            .unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        // The stream is closed.
+        assert!(stream
+            .send_alt_svc(http::HeaderValue::from_static("clear"))
+            .is_err());
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_origin_set() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .recv_frame(frames::origin(&[
+                "https://example.com",
+                "https://www.example.com",
+            ]))
+            .await;
+        // Servers ignore ORIGIN frames.
+        client
+            .send_frame(frames::origin(&["https://example.net"]))
+            .await;
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .origin("https://example.com")
+            .origin("https://www.example.com")
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+        let (_req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn custom_settings() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client
+            .assert_server_handshake_with_settings(
+                frames::settings()
+                    .max_concurrent_streams(10)
+                    .custom(0xf000, 7)
+                    .custom(0xf001, 8),
+            )
+            .await;
+        assert_frame_eq(settings, frames::settings().custom(0xf002, 1));
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .custom_setting(0xf002, 1)
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+        let (_req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let settings = srv.remote_settings();
+        assert_eq!(settings.get(0xf000), Some(7));
+        assert_eq!(settings.get(0xf001), Some(8));
+        assert_eq!(settings.get(0xf002), None);
+        assert_eq!(
+            settings.iter().collect::<Vec<_>>(),
+            vec![(3, 10), (0xf000, 7), (0xf001, 8)]
+        );
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn too_many_custom_settings_are_ignored() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+
+        // A SETTINGS frame with 100 distinct unknown settings.
+        let mut frame = vec![0, 2, 88, 4, 0, 0, 0, 0, 0];
+        for id in 0xf000u16..0xf064 {
+            frame.extend_from_slice(&id.to_be_bytes());
+            frame.extend_from_slice(&1u32.to_be_bytes());
+        }
+        client.send_bytes(&frame).await;
+        client.recv_frame(frames::settings_ack()).await;
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+        let (_req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let settings = srv.remote_settings();
+        assert_eq!(settings.iter().count(), 64);
+        assert_eq!(settings.get(0xf03f), Some(1));
+        assert_eq!(settings.get(0xf040), None);
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn poll_remote_settings() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        client.write_preface().await;
+        client
+            .send_frame(
+                frames::settings()
+                    .initial_window_size(1_000)
+                    .max_header_list_size(2_000),
+            )
+            .await;
+        client.recv_frame(frames::settings()).await;
+        client.recv_frame(frames::settings_ack()).await;
+        // Remote settings are only ready once ours are acknowledged.
+        idle_ms(10).await;
+        client.send_frame(frames::settings_ack()).await;
+        idle_ms(10).await;
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::handshake(io).await.expect("handshake");
+
+        let settings = srv.remote_settings();
+        assert_eq!(settings.initial_window_size(), 65_535);
+        assert_eq!(settings.max_header_list_size(), None);
+
+        let settings = poll_fn(|cx| {
+            if let Poll::Ready(settings) = srv.poll_remote_settings(cx) {
+                return Poll::Ready(settings);
+            }
+            assert!(srv.poll_accept(cx).is_pending());
+            Poll::Pending
+        })
+        .await
+        .expect("poll_remote_settings");
+
+        assert_eq!(settings.initial_window_size(), 1_000);
+        assert_eq!(settings.max_header_list_size(), Some(2_000));
+        assert_eq!(settings.max_frame_size(), 16_384);
+        assert_eq!(settings.header_table_size(), 4_096);
+        assert_eq!(settings.max_concurrent_streams(), None);
+        assert!(settings.is_push_enabled());
+
+        let (_req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn h2c_upgrade() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        // The `101 Switching Protocols` response was sent, the client starts
+        // speaking HTTP/2.0.
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+        client
+            .send_frame(
+                frames::headers(3)
+                    .request("GET", "http://example.com/second")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(3).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let request = Request::builder()
+            .uri("/first")
+            .header("host", "example.com")
+            .header("connection", "Upgrade, HTTP2-Settings, x-hop")
+            .header("upgrade", "h2c")
+            // INITIAL_WINDOW_SIZE = 1000
+            .header("http2-settings", "AAQAAAPo")
+            .header("x-hop", "1")
+            .header("x-end", "1")
+            .body(())
+            .unwrap();
+        let http2_settings = request.headers()["http2-settings"].clone();
+
+        let mut srv = server::Builder::new()
+            .handshake_upgrade::<_, Bytes>(io, &http2_settings, request)
+            .expect("upgrade")
+            .await
+            .expect("handshake");
+
+        assert_eq!(srv.remote_settings().initial_window_size(), 1_000);
+
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        assert_eq!(req.method(), &http::Method::GET);
+        assert_eq!(req.uri(), "http://example.com/first");
+        assert_eq!(req.headers()["host"], "example.com");
+        assert_eq!(req.headers()["x-end"], "1");
+        assert!(req.headers().get("connection").is_none());
+        assert!(req.headers().get("upgrade").is_none());
+        assert!(req.headers().get("http2-settings").is_none());
+        assert!(req.headers().get("x-hop").is_none());
+        assert!(req.body().is_end_stream());
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+        assert_eq!(req.uri(), "http://example.com/second");
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn h2c_upgrade_invalid_settings() {
+    h2_support::trace_init!();
+    let (io, _client) = mock::new();
+
+    let request = Request::builder()
+        .uri("/")
+        .header("host", "example.com")
+        .body(())
+        .unwrap();
+
+    let err = server::Builder::new()
+        .handshake_upgrade::<_, Bytes>(io, &http::HeaderValue::from_static("AA=IAAAAA"), request)
+        .err()
+        .expect("invalid settings");
+
+    assert_eq!(err.reason(), Some(Reason::PROTOCOL_ERROR));
+}
+
+#[tokio::test]
+async fn sniff_handshake_http2() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let sniffed = server::Builder::new()
+            .sniff_handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        let mut srv = match sniffed {
+            server::Sniffed::Http2(srv) => *srv,
+            server::Sniffed::Other { .. } => panic!("not sniffed as HTTP/2.0"),
+        };
+
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+        assert_eq!(req.method(), &http::Method::GET);
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        stream.send_response(rsp, true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn sniff_handshake_http1() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        client.write_all(b"PRI").await.expect("write");
+        idle_ms(10).await;
+        client
+            .write_all(b"NT / HTTP/1.1\r\nhost: example.com\r\n\r\n")
+            .await
+            .expect("write");
+        client
+    };
+
+    let srv = async move {
+        let sniffed = server::Builder::new()
+            .sniff_handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        let (mut io, read) = match sniffed {
+            server::Sniffed::Other { io, read } => (io, read),
+            server::Sniffed::Http2(..) => panic!("sniffed as HTTP/2.0"),
+        };
+
+        // At most the length of the preface is read.
+        assert_eq!(read, "PRINT / HTTP/1.1\r\nhost: ");
+
+        let mut rest = [0; 15];
+        tokio::io::AsyncReadExt::read_exact(&mut io, &mut rest)
+            .await
+            .expect("read");
+        assert_eq!(&rest, b"example.com\r\n\r\n");
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn sniff_handshake_eof_in_preface() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        client.write_all(b"PRI * HTTP/2.0").await.expect("write");
+        drop(client);
+    };
+
+    let srv = async move {
+        let sniffed = server::Builder::new()
+            .sniff_handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+
+        // The bytes read before EOF are handed back.
+        match sniffed {
+            server::Sniffed::Other { read, .. } => assert_eq!(read, "PRI * HTTP/2.0"),
+            server::Sniffed::Http2(..) => panic!("sniffed as HTTP/2.0"),
+        }
+    };
+
+    join(client, srv).await;
+}
+
+#[tokio::test]
+async fn send_padded_response() {
+    h2_support::trace_init!();
+    let (io, mut client) = mock::new();
+
+    let client = async move {
+        let settings = client.assert_server_handshake().await;
+        assert_default_settings!(settings);
+        client
+            .send_frame(
+                frames::headers(1)
+                    .request("GET", "https://example.com/")
+                    .eos(),
+            )
+            .await;
+        client
+            .recv_frame(frames::headers(1).response(200).padded())
+            .await;
+        client
+            .recv_frame(frames::data(1, &b"hello"[..]).padding(8))
+            .await;
+        client
+            .recv_frame(frames::data(1, &b"world"[..]).padding(8).eos())
+            .await;
+    };
+
+    let srv = async move {
+        let mut srv = server::Builder::new()
+            .padding(h2::Padding::fixed(8))
+            .handshake::<_, Bytes>(io)
+            .await
+            .expect("handshake");
+        let (req, mut stream) = srv.next().await.unwrap().unwrap();
+
+        assert_eq!(req.method(), &http::Method::GET);
+
+        let rsp = http::Response::builder().status(200).body(()).unwrap();
+        let mut stream = stream.send_response(rsp, false).unwrap();
+        stream.send_data("hello".into(), false).unwrap();
+        stream.send_data("world".into(), true).unwrap();
+
+        assert!(srv.next().await.is_none());
+    };
+
+    join(client, srv).await;
+}